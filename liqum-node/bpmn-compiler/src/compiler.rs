@@ -0,0 +1,702 @@
+//! Translation of the BPMN 2.0 XML tree into element tables.
+
+use crate::diagnostic::{CompileError, Diagnostic};
+use crate::model::{
+    CompiledModel, Element, Flow, LoopCharacteristics, MultiInstanceCharacteristics,
+    SubProcessLink, Timer,
+};
+use crate::timers;
+use bpmn_interpreter::types::{
+    ElementType, EventKind, EventPosition, GatewayKind, MultiInstance, SubProcessKind, TaskKind,
+    TimerDefinition,
+};
+pub use bpmn_interpreter::MAX_BIT_INDEX;
+use bpmn_interpreter::{BitSet, MAX_LOOP_ITERATIONS, MAX_SUB_PROCESSES};
+use roxmltree::{Document, Node};
+use std::collections::BTreeMap;
+
+pub const BPMN_NAMESPACE: &str = "http://www.omg.org/spec/BPMN/20100524/MODEL";
+
+const TASKS: &[&str] = &[
+    "task",
+    "manualTask",
+    "userTask",
+    "scriptTask",
+    "serviceTask",
+    "sendTask",
+    "businessRuleTask",
+    "receiveTask",
+];
+const GATEWAYS: &[&str] = &[
+    "exclusiveGateway",
+    "parallelGateway",
+    "inclusiveGateway",
+    "eventBasedGateway",
+];
+const EVENTS: &[&str] = &[
+    "startEvent",
+    "intermediateCatchEvent",
+    "intermediateThrowEvent",
+    "boundaryEvent",
+    "endEvent",
+];
+const SUB_PROCESSES: &[&str] = &["subProcess", "callActivity"];
+const UNSUPPORTED_FLOW_NODES: &[&str] = &[
+    "complexGateway",
+    "transaction",
+    "adHocSubProcess",
+    "callChoreography",
+    "choreographyTask",
+    "subChoreography",
+];
+
+/// Compiles the processes of a BPMN 2.0 `definitions` document.
+///
+/// Every process and embedded sub-process becomes a flow whose elements and sequence flows
+/// are indexed from 1 in document order. Constructs the interpreter can't execute are
+/// reported in `CompiledModel::diagnostics` rather than failing the compilation.
+pub fn compile(xml: &str) -> Result<CompiledModel, CompileError> {
+    let document = Document::parse(xml)?;
+    let definitions = document.root_element();
+    if !is_bpmn(definitions, "definitions") {
+        return Err(CompileError::NotBpmn);
+    }
+    let processes: Vec<_> = bpmn_children(definitions)
+        .filter(|node| node.tag_name().name() == "process")
+        .collect();
+    if processes.is_empty() {
+        return Err(CompileError::NoProcess);
+    }
+
+    let mut compiler = Compiler {
+        definitions,
+        model: CompiledModel::default(),
+        processes: BTreeMap::new(),
+        call_activities: Vec::new(),
+    };
+    for process in processes {
+        let position = compiler.compile_flow(process, false);
+        compiler.processes.insert(id_of(process), position);
+    }
+    compiler.link_call_activities();
+    for message_flow in definitions
+        .descendants()
+        .filter(|node| is_bpmn(*node, "messageFlow"))
+    {
+        compiler.warning(
+            message_flow,
+            "message flows are ignored, messages are matched by event code",
+        );
+    }
+    Ok(compiler.model)
+}
+
+/// Call activity whose called process may not be compiled yet.
+struct PendingCallActivity<'a> {
+    id: &'a str,
+    called_element: &'a str,
+    link: SubProcessLink,
+}
+
+struct Compiler<'a, 'input> {
+    definitions: Node<'a, 'input>,
+    model: CompiledModel,
+    /// Process id => flow position
+    processes: BTreeMap<&'a str, usize>,
+    call_activities: Vec<PendingCallActivity<'a>>,
+}
+
+impl<'a, 'input> Compiler<'a, 'input> {
+    /// Compiles a process or sub-process, returning its position in `CompiledModel::flows`.
+    ///
+    /// The start event of an event sub-process is compiled as a none start event, its trigger
+    /// is caught in the enclosing flow, see `event_sub_process_start`.
+    fn compile_flow(&mut self, container: Node<'a, 'input>, event_sub_process: bool) -> usize {
+        let position = self.model.flows.len();
+        self.model.flows.push(Flow {
+            id: id_of(container).to_string(),
+            elements: Vec::new(),
+        });
+
+        let mut nodes = Vec::new();
+        let mut skipped = Vec::new();
+        for child in bpmn_children(container) {
+            let name = child.tag_name().name();
+            if is_flow_node(name) {
+                nodes.push(child);
+            } else if UNSUPPORTED_FLOW_NODES.contains(&name) {
+                self.error(child, format!("{} is not supported", name));
+                skipped.push(id_of(child));
+            }
+        }
+        let index_of = |id: &str| {
+            nodes
+                .iter()
+                .position(|node| id_of(*node) == id)
+                .map(|position| position as u128 + 1)
+        };
+        let mut pre_conditions = vec![BitSet::new(); nodes.len()];
+        let mut post_conditions = vec![BitSet::new(); nodes.len()];
+        let mut next_elems = vec![Vec::new(); nodes.len()];
+
+        let mut edge_index = 0;
+        for sequence_flow in
+            bpmn_children(container).filter(|node| node.tag_name().name() == "sequenceFlow")
+        {
+            let source = sequence_flow.attribute("sourceRef").unwrap_or_default();
+            let target = sequence_flow.attribute("targetRef").unwrap_or_default();
+            match (index_of(source), index_of(target)) {
+                (Some(source), Some(target)) => {
+                    edge_index += 1;
+                    post_conditions[source as usize - 1].insert(edge_index);
+                    next_elems[source as usize - 1].push(target);
+                    pre_conditions[target as usize - 1].insert(edge_index);
+                }
+                _ if skipped.contains(&source) || skipped.contains(&target) => {}
+                _ => self.error(
+                    sequence_flow,
+                    "source or target is not a flow node of the same process",
+                ),
+            }
+        }
+
+        // Sub-process id => indexes of the boundary events attached to it
+        let mut attached_events: BTreeMap<&str, Vec<u128>> = BTreeMap::new();
+        for (node, element_index) in nodes.iter().zip(1..) {
+            if node.tag_name().name() == "boundaryEvent" {
+                let attached_to = node.attribute("attachedToRef").unwrap_or_default();
+                match nodes.iter().find(|node| id_of(**node) == attached_to) {
+                    Some(activity) if SUB_PROCESSES.contains(&activity.tag_name().name()) => {
+                        attached_events
+                            .entry(attached_to)
+                            .or_default()
+                            .push(element_index)
+                    }
+                    _ => self.error(
+                        *node,
+                        "boundary events are only supported on sub-processes and call activities",
+                    ),
+                }
+            }
+        }
+
+        let mut elements = Vec::with_capacity(nodes.len());
+        // Catch events starting the event sub-processes, indexed after the flow nodes
+        let mut event_sub_process_starts = Vec::new();
+        let mut start_events = 0;
+        for (((node, pre_condition), post_condition), next_elem) in nodes
+            .iter()
+            .zip(pre_conditions)
+            .zip(post_conditions)
+            .zip(next_elems)
+        {
+            let name = node.tag_name().name();
+            let element_index = elements.len() as u128 + 1;
+            let mut event_code = [0; 32];
+            let element_type = if TASKS.contains(&name) {
+                self.task_type(*node)
+            } else if GATEWAYS.contains(&name) {
+                self.gateway_type(
+                    *node,
+                    pre_condition.iter().count(),
+                    post_condition.iter().count(),
+                )
+            } else if EVENTS.contains(&name) {
+                if name == "startEvent" {
+                    start_events += 1;
+                }
+                if name == "startEvent" && event_sub_process {
+                    ElementType::Event {
+                        position: EventPosition::Start,
+                        kind: EventKind::Default,
+                        interrupting: false,
+                    }
+                } else {
+                    let (element_type, code) = self.event_type(*node);
+                    event_code = code;
+                    if let ElementType::Event {
+                        kind: EventKind::Timer,
+                        ..
+                    } = element_type
+                    {
+                        self.timer(*node, position, element_index);
+                    }
+                    element_type
+                }
+            } else {
+                let (multi_instance, count_instances) =
+                    self.instances(*node, position, element_index);
+                let mut link = SubProcessLink {
+                    flow: position,
+                    parent_index: element_index,
+                    child_flow: 0,
+                    attached_events: attached_events.remove(id_of(*node)).unwrap_or_default(),
+                    count_instances,
+                };
+                if name == "subProcess" {
+                    let triggered_by_event = node.attribute("triggeredByEvent") == Some("true");
+                    let kind = if triggered_by_event {
+                        if multi_instance != MultiInstance::None {
+                            self.error(
+                                *node,
+                                "loop and multi-instance markers are not supported on event sub-processes",
+                            );
+                        }
+                        if let Some(start) = self.event_sub_process_start(*node) {
+                            let start_index =
+                                (nodes.len() + event_sub_process_starts.len()) as u128 + 1;
+                            link.attached_events.push(start_index);
+                            event_sub_process_starts.push(Element {
+                                element_index: start_index,
+                                ..start
+                            });
+                        }
+                        SubProcessKind::EventSubProcess
+                    } else {
+                        SubProcessKind::Embedded
+                    };
+                    let child_flow = self.compile_flow(*node, triggered_by_event);
+                    self.model.links.push(SubProcessLink { child_flow, ..link });
+                    ElementType::SubProcess {
+                        kind,
+                        multi_instance,
+                    }
+                } else {
+                    self.call_activities.push(PendingCallActivity {
+                        id: id_of(*node),
+                        called_element: node.attribute("calledElement").unwrap_or_default(),
+                        link,
+                    });
+                    ElementType::SubProcess {
+                        kind: SubProcessKind::CallActivity,
+                        multi_instance,
+                    }
+                }
+            };
+            elements.push(Element {
+                id: id_of(*node).to_string(),
+                element_index,
+                pre_condition,
+                post_condition,
+                element_type,
+                event_code,
+                next_elem,
+            });
+        }
+        for (node, element) in nodes.iter().zip(&elements) {
+            let event_based = ElementType::Gateway {
+                kind: GatewayKind::EventBased,
+                join: false,
+            };
+            let waits_for_events = element.next_elem.iter().all(|next| {
+                matches!(
+                    elements[*next as usize - 1].element_type,
+                    ElementType::Event {
+                        position: EventPosition::IntermediateCatch,
+                        kind,
+                        ..
+                    } if kind == EventKind::Message
+                        || kind == EventKind::Signal
+                        || kind == EventKind::Timer
+                )
+            });
+            if element.element_type == event_based
+                && (element.next_elem.is_empty() || !waits_for_events)
+            {
+                self.error(
+                    *node,
+                    "event-based gateways must lead to intermediate message, signal or timer catch events only",
+                );
+            }
+        }
+        elements.extend(event_sub_process_starts);
+        match start_events {
+            0 => self.error(container, "no start event"),
+            1 => {}
+            _ => self.error(container, "multiple start events are not supported"),
+        }
+        if elements.len() as u128 >= MAX_BIT_INDEX || edge_index >= MAX_BIT_INDEX {
+            self.error(
+                container,
+                format!("more than {} elements or sequence flows", MAX_BIT_INDEX - 1),
+            );
+        }
+
+        self.model.flows[position].elements = elements;
+        position
+    }
+
+    fn task_type(&mut self, node: Node) -> ElementType {
+        let kind = match node.tag_name().name() {
+            "task" | "manualTask" => TaskKind::Default,
+            "userTask" => TaskKind::User,
+            "scriptTask" => TaskKind::Script,
+            "serviceTask" => TaskKind::Service,
+            "receiveTask" => TaskKind::Receive,
+            name => {
+                self.warning(node, format!("{} is compiled as a service task", name));
+                TaskKind::Service
+            }
+        };
+        if bpmn_children(node).any(|child| child.tag_name().name().ends_with("LoopCharacteristics"))
+        {
+            self.error(
+                node,
+                "loop and multi-instance markers are only supported on sub-processes and call activities",
+            );
+        }
+        ElementType::Task(kind)
+    }
+
+    fn gateway_type(&mut self, node: Node, incoming: usize, outgoing: usize) -> ElementType {
+        let kind = match node.tag_name().name() {
+            "exclusiveGateway" => GatewayKind::Exclusive,
+            "parallelGateway" => GatewayKind::Parallel,
+            "eventBasedGateway" => GatewayKind::EventBased,
+            _ => GatewayKind::Inclusive,
+        };
+        if kind == GatewayKind::EventBased {
+            if incoming > 1 {
+                self.error(
+                    node,
+                    "event-based gateways joining sequence flows are not supported, use a join before",
+                );
+            }
+            if node.attribute("instantiate") == Some("true") {
+                self.error(node, "instantiating event-based gateways are not supported");
+            }
+        }
+        if incoming > 1 && outgoing > 1 {
+            self.error(
+                node,
+                "gateways both joining and splitting are not supported, use a join followed by a split",
+            );
+        }
+        ElementType::Gateway {
+            kind,
+            join: incoming > 1,
+        }
+    }
+
+    fn event_type(&mut self, node: Node) -> (ElementType, [u8; 32]) {
+        let name = node.tag_name().name();
+        let position = match name {
+            "startEvent" => EventPosition::Start,
+            "intermediateCatchEvent" => EventPosition::IntermediateCatch,
+            "intermediateThrowEvent" => EventPosition::IntermediateThrow,
+            "boundaryEvent" => EventPosition::Boundary,
+            _ => EventPosition::End,
+        };
+        let interrupting =
+            name == "boundaryEvent" && node.attribute("cancelActivity") != Some("false");
+        let (kind, event_code) = self.event_definition(node);
+        let element_type = ElementType::Event {
+            position,
+            kind: kind.unwrap_or(EventKind::Default),
+            interrupting,
+        };
+        (element_type, event_code)
+    }
+
+    /// Catch event of the enclosing flow starting the event sub-process, triggered like the
+    /// start event of the sub-process. Its element index is left to the caller.
+    fn event_sub_process_start(&mut self, sub_process: Node) -> Option<Element> {
+        // Event sub-processes without start event are reported when compiling their flow
+        let start =
+            bpmn_children(sub_process).find(|child| child.tag_name().name() == "startEvent")?;
+        let (kind, event_code) = self.event_definition(start);
+        match kind? {
+            kind @ EventKind::Error | kind @ EventKind::Escalation | kind @ EventKind::Signal => {
+                Some(Element {
+                    id: id_of(start).to_string(),
+                    element_index: 0,
+                    pre_condition: BitSet::new(),
+                    post_condition: BitSet::new(),
+                    element_type: ElementType::Event {
+                        position: EventPosition::EventSubProcessStart,
+                        kind,
+                        interrupting: start.attribute("isInterrupting") != Some("false"),
+                    },
+                    event_code,
+                    next_elem: Vec::new(),
+                })
+            }
+            _ => {
+                self.error(
+                    start,
+                    "event sub-processes are only started by error, escalation or signal events",
+                );
+                None
+            }
+        }
+    }
+
+    /// Kind and code of the event definition of the event, `None` once an unsupported
+    /// definition is reported.
+    fn event_definition(&mut self, node: Node) -> (Option<EventKind>, [u8; 32]) {
+        let name = node.tag_name().name();
+        let definitions: Vec<_> = bpmn_children(node)
+            .filter(|child| child.tag_name().name().ends_with("EventDefinition"))
+            .collect();
+        let mut event_code = [0; 32];
+        let kind = match definitions.as_slice() {
+            [] => Some(EventKind::Default),
+            [definition] => match definition.tag_name().name() {
+                "messageEventDefinition" => {
+                    event_code = self.event_code(node, *definition, "messageRef", "message", &[]);
+                    Some(EventKind::Message)
+                }
+                "errorEventDefinition" => {
+                    event_code =
+                        self.event_code(node, *definition, "errorRef", "error", &["errorCode"]);
+                    Some(EventKind::Error)
+                }
+                "escalationEventDefinition" => {
+                    event_code = self.event_code(
+                        node,
+                        *definition,
+                        "escalationRef",
+                        "escalation",
+                        &["escalationCode"],
+                    );
+                    Some(EventKind::Escalation)
+                }
+                "signalEventDefinition" => {
+                    event_code = self.event_code(node, *definition, "signalRef", "signal", &[]);
+                    Some(EventKind::Signal)
+                }
+                "terminateEventDefinition" if name == "endEvent" => Some(EventKind::Terminate),
+                "timerEventDefinition"
+                    if name != "intermediateThrowEvent" && name != "endEvent" =>
+                {
+                    Some(EventKind::Timer)
+                }
+                definition => {
+                    self.error(node, format!("{} is not supported", definition));
+                    None
+                }
+            },
+            _ => {
+                self.error(node, "multiple event definitions are not supported");
+                None
+            }
+        };
+        (kind, event_code)
+    }
+
+    /// Timer of the timer event, reported unless its definition is a constant ISO 8601
+    /// duration, date or repeating interval.
+    fn timer(&mut self, node: Node, flow: usize, element_index: u128) {
+        let definition = bpmn_children(node)
+            .find(|child| child.tag_name().name() == "timerEventDefinition")
+            .and_then(|definition| bpmn_children(definition).next());
+        let timer = definition.and_then(|definition| {
+            let value = definition.text().unwrap_or_default().trim();
+            match definition.tag_name().name() {
+                "timeDuration" => timers::duration(value).map(TimerDefinition::Duration),
+                "timeDate" => timers::date(value).map(TimerDefinition::Date),
+                "timeCycle" => {
+                    timers::cycle(value).map(|(interval, repetitions)| TimerDefinition::Cycle {
+                        interval,
+                        repetitions,
+                    })
+                }
+                _ => None,
+            }
+        });
+        match timer {
+            Some(TimerDefinition::Cycle {
+                repetitions: Some(0),
+                ..
+            })
+            | Some(TimerDefinition::Cycle { interval: 0, .. })
+            | None => self.error(
+                node,
+                "timers must be a constant ISO 8601 duration, date with an offset, or R[n]/duration cycle",
+            ),
+            Some(timer) => self.model.timers.push(Timer {
+                flow,
+                element_index,
+                timer,
+            }),
+        }
+    }
+
+    /// Code identifying the thrown or caught event: the code (if any), name or id of the
+    /// referenced message, error, escalation or signal.
+    fn event_code(
+        &mut self,
+        node: Node,
+        definition: Node,
+        reference: &str,
+        kind: &str,
+        code_attributes: &[&str],
+    ) -> [u8; 32] {
+        let mut event_code = [0; 32];
+        let reference = match definition.attribute(reference) {
+            Some(reference) => reference,
+            None => return event_code,
+        };
+        let code = match bpmn_children(self.definitions)
+            .find(|child| child.tag_name().name() == kind && id_of(*child) == reference)
+        {
+            Some(referenced) => code_attributes
+                .iter()
+                .chain(&["name"])
+                .find_map(|attribute| referenced.attribute(*attribute))
+                .unwrap_or(reference),
+            None => {
+                self.error(node, format!("unknown {} `{}`", kind, reference));
+                return event_code;
+            }
+        };
+        if code.len() > event_code.len() {
+            self.error(
+                node,
+                format!("event code `{}` is longer than 32 bytes", code),
+            );
+        } else {
+            event_code[..code.len()].copy_from_slice(code.as_bytes());
+        }
+        event_code
+    }
+
+    /// Multi-instance or loop marker and number of instances of a sub-process or call activity,
+    /// the cardinality, completion and loop conditions that are not constants being left to
+    /// the data & scripts contract, see `MultiInstanceCharacteristics`.
+    fn instances(&mut self, node: Node, flow: usize, element_index: u128) -> (MultiInstance, u128) {
+        for child in bpmn_children(node) {
+            match child.tag_name().name() {
+                "multiInstanceLoopCharacteristics" => {
+                    let marker = if child.attribute("isSequential") == Some("true") {
+                        MultiInstance::Sequential
+                    } else {
+                        MultiInstance::Parallel
+                    };
+                    let expression = |name| {
+                        bpmn_children(child)
+                            .find(|child| child.tag_name().name() == name)
+                            .and_then(|expression| expression.text())
+                            .map(str::trim)
+                            .filter(|expression| !expression.is_empty())
+                    };
+                    let completion_condition = expression("completionCondition");
+                    let (count_instances, cardinality) = match expression("loopCardinality") {
+                        Some(cardinality) => match cardinality.parse::<u128>() {
+                            Ok(count_instances) => (count_instances, None),
+                            Err(_) => (1, Some(cardinality)),
+                        },
+                        None => {
+                            self.error(node, "multi-instance activities must have a cardinality");
+                            (1, None)
+                        }
+                    };
+                    if count_instances > u128::from(MAX_SUB_PROCESSES) {
+                        self.error(node, format!("more than {} instances", MAX_SUB_PROCESSES));
+                    }
+                    if cardinality.is_some() || completion_condition.is_some() {
+                        self.model
+                            .multi_instances
+                            .push(MultiInstanceCharacteristics {
+                                flow,
+                                element_index,
+                                cardinality: cardinality.map(String::from),
+                                completion_condition: completion_condition.map(String::from),
+                            });
+                    }
+                    return (marker, count_instances);
+                }
+                "standardLoopCharacteristics" => {
+                    let condition = bpmn_children(child)
+                        .find(|child| child.tag_name().name() == "loopCondition")
+                        .and_then(|condition| condition.text())
+                        .map(str::trim)
+                        .filter(|condition| !condition.is_empty());
+                    let maximum = child.attribute("loopMaximum").map(str::parse::<u32>);
+                    let maximum = match maximum {
+                        Some(Ok(maximum)) if maximum > 0 && maximum <= MAX_LOOP_ITERATIONS => {
+                            Some(maximum)
+                        }
+                        Some(_) => {
+                            self.error(
+                                node,
+                                format!(
+                                    "loop maximum must be a constant from 1 to {}",
+                                    MAX_LOOP_ITERATIONS
+                                ),
+                            );
+                            None
+                        }
+                        None => None,
+                    };
+                    if condition.is_some() || maximum.is_some() {
+                        self.model.loops.push(LoopCharacteristics {
+                            flow,
+                            element_index,
+                            condition: condition.map(String::from),
+                            maximum,
+                        });
+                    }
+                    let marker = MultiInstance::StandardLoop {
+                        test_before: child.attribute("testBefore") == Some("true"),
+                    };
+                    return (marker, 1);
+                }
+                _ => {}
+            }
+        }
+        (MultiInstance::None, 1)
+    }
+
+    fn link_call_activities(&mut self) {
+        for call_activity in core::mem::take(&mut self.call_activities) {
+            match self.processes.get(call_activity.called_element) {
+                Some(child_flow) => self.model.links.push(SubProcessLink {
+                    child_flow: *child_flow,
+                    ..call_activity.link
+                }),
+                None => self.model.diagnostics.push(Diagnostic::error(
+                    Some(call_activity.id),
+                    format!(
+                        "called process `{}` not found",
+                        call_activity.called_element
+                    ),
+                )),
+            }
+        }
+    }
+
+    fn error(&mut self, node: Node, message: impl Into<String>) {
+        self.model
+            .diagnostics
+            .push(Diagnostic::error(node.attribute("id"), message));
+    }
+
+    fn warning(&mut self, node: Node, message: impl Into<String>) {
+        self.model
+            .diagnostics
+            .push(Diagnostic::warning(node.attribute("id"), message));
+    }
+}
+
+fn is_bpmn(node: Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && node.tag_name().namespace() == Some(BPMN_NAMESPACE)
+}
+
+fn bpmn_children<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(|child| child.is_element() && child.tag_name().namespace() == Some(BPMN_NAMESPACE))
+}
+
+fn is_flow_node(name: &str) -> bool {
+    TASKS.contains(&name)
+        || GATEWAYS.contains(&name)
+        || EVENTS.contains(&name)
+        || SUB_PROCESSES.contains(&name)
+}
+
+fn id_of<'a>(node: Node<'a, '_>) -> &'a str {
+    node.attribute("id").unwrap_or_default()
+}
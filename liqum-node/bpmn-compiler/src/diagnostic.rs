@@ -0,0 +1,80 @@
+//! Errors and diagnostics reported while compiling a model.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The construct can't be executed by the interpreter, the model must not be deployed
+    Error,
+    /// The construct is compiled with a different behaviour than the BPMN 2.0 one
+    Warning,
+}
+
+/// A construct of the model the compiler could not translate faithfully.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// BPMN id of the offending element, if any
+    pub element: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(element: Option<&str>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, element, message)
+    }
+
+    pub fn warning(element: Option<&str>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, element, message)
+    }
+
+    fn new(severity: Severity, element: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            element: element.map(String::from),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match &self.element {
+            Some(element) => write!(f, "{} [{}]: {}", severity, element, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+/// Failure preventing the model from being compiled at all.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompileError {
+    /// The document is not well-formed XML
+    Xml(String),
+    /// The root element is not a BPMN 2.0 `definitions`
+    NotBpmn,
+    /// The definitions do not contain any process
+    NoProcess,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::Xml(error) => write!(f, "invalid XML: {}", error),
+            CompileError::NotBpmn => write!(f, "root element is not a BPMN 2.0 definitions"),
+            CompileError::NoProcess => write!(f, "no process found in the definitions"),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+impl From<roxmltree::Error> for CompileError {
+    fn from(error: roxmltree::Error) -> Self {
+        CompileError::Xml(error.to_string())
+    }
+}
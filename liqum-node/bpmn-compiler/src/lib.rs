@@ -0,0 +1,699 @@
+//! Compiler of BPMN 2.0 XML models into the element tables of the BPMN interpreter pallet.
+//!
+//! The output lists, for every process and sub-process, the arguments of the `set_element`
+//! calls (pre and post conditions as sets of sequence flow indexes, element type, event code
+//! and next elements) and the `link_sub_process` calls building the hierarchy.
+
+mod compiler;
+mod diagnostic;
+mod model;
+mod timers;
+
+pub use bpmn_interpreter::{types, BitSet};
+
+pub use compiler::{compile, BPMN_NAMESPACE, MAX_BIT_INDEX};
+pub use diagnostic::{CompileError, Diagnostic, Severity};
+pub use model::{
+    Call, CompiledModel, Element, Flow, LoopCharacteristics, MultiInstanceCharacteristics,
+    SubProcessLink, Timer,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::types::*;
+    use super::*;
+
+    fn definitions(content: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <bpmn:definitions xmlns:bpmn="{}" id="Definitions">{}</bpmn:definitions>"#,
+            BPMN_NAMESPACE, content
+        )
+    }
+
+    fn compile_ok(content: &str) -> CompiledModel {
+        let model = compile(&definitions(content)).unwrap();
+        assert!(!model.has_errors(), "{:?}", model.diagnostics);
+        model
+    }
+
+    fn edges(indexes: &[u128]) -> BitSet {
+        let mut edges = BitSet::new();
+        for &index in indexes {
+            edges.insert(index);
+        }
+        edges
+    }
+
+    fn start_event() -> ElementType {
+        event(EventPosition::Start, EventKind::Default, false)
+    }
+
+    fn event(position: EventPosition, kind: EventKind, interrupting: bool) -> ElementType {
+        ElementType::Event {
+            position,
+            kind,
+            interrupting,
+        }
+    }
+
+    fn code(code: &str) -> [u8; 32] {
+        let mut event_code = [0; 32];
+        event_code[..code.len()].copy_from_slice(code.as_bytes());
+        event_code
+    }
+
+    #[test]
+    fn compiles_sequence() {
+        let model = compile_ok(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:scriptTask id="Script" />
+                <bpmn:userTask id="User" />
+                <bpmn:endEvent id="End" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Script" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Script" targetRef="User" />
+                <bpmn:sequenceFlow id="F3" sourceRef="User" targetRef="End" />
+            </bpmn:process>"#,
+        );
+        assert_eq!(model.flows.len(), 1);
+        assert!(model.links.is_empty());
+        let flow = &model.flows[0];
+        assert_eq!(flow.id, "Process");
+
+        let start = flow.element("Start").unwrap();
+        assert_eq!(start.element_index, 1);
+        assert_eq!(
+            start.element_type,
+            event(EventPosition::Start, EventKind::Default, false)
+        );
+        assert!(start.pre_condition.is_empty());
+        assert_eq!(start.post_condition, edges(&[1]));
+        assert_eq!(start.next_elem, vec![2]);
+
+        let script = flow.element("Script").unwrap();
+        assert_eq!(script.element_type, ElementType::Task(TaskKind::Script));
+        assert_eq!(script.element_type.type_info(), 4105);
+        assert_eq!(script.pre_condition, edges(&[1]));
+        assert_eq!(script.post_condition, edges(&[2]));
+        assert_eq!(script.next_elem, vec![3]);
+
+        let user = flow.element("User").unwrap();
+        assert_eq!(user.element_type, ElementType::Task(TaskKind::User));
+
+        let end = flow.element("End").unwrap();
+        assert_eq!(
+            end.element_type,
+            event(EventPosition::End, EventKind::Default, false)
+        );
+        assert_eq!(end.pre_condition, edges(&[3]));
+        assert!(end.next_elem.is_empty());
+
+        let calls = model.calls();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0], Call::SetElement(0, start));
+    }
+
+    #[test]
+    fn compiles_gateways() {
+        let model = compile_ok(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:parallelGateway id="AndSplit" />
+                <bpmn:inclusiveGateway id="OrSplit" />
+                <bpmn:task id="A" />
+                <bpmn:task id="B" />
+                <bpmn:task id="C" />
+                <bpmn:inclusiveGateway id="OrJoin" />
+                <bpmn:parallelGateway id="AndJoin" />
+                <bpmn:endEvent id="End" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="AndSplit" />
+                <bpmn:sequenceFlow id="F2" sourceRef="AndSplit" targetRef="OrSplit" />
+                <bpmn:sequenceFlow id="F3" sourceRef="AndSplit" targetRef="C" />
+                <bpmn:sequenceFlow id="F4" sourceRef="OrSplit" targetRef="A" />
+                <bpmn:sequenceFlow id="F5" sourceRef="OrSplit" targetRef="B" />
+                <bpmn:sequenceFlow id="F6" sourceRef="A" targetRef="OrJoin" />
+                <bpmn:sequenceFlow id="F7" sourceRef="B" targetRef="OrJoin" />
+                <bpmn:sequenceFlow id="F8" sourceRef="OrJoin" targetRef="AndJoin" />
+                <bpmn:sequenceFlow id="F9" sourceRef="C" targetRef="AndJoin" />
+                <bpmn:sequenceFlow id="F10" sourceRef="AndJoin" targetRef="End" />
+            </bpmn:process>"#,
+        );
+        let flow = &model.flows[0];
+        let and_split = flow.element("AndSplit").unwrap();
+        assert_eq!(
+            and_split.element_type,
+            ElementType::Gateway {
+                kind: GatewayKind::Parallel,
+                join: false,
+            }
+        );
+        assert_eq!(and_split.post_condition, edges(&[2, 3]));
+        assert_eq!(and_split.next_elem, vec![3, 6]);
+
+        let or_split = flow.element("OrSplit").unwrap();
+        assert_eq!(
+            or_split.element_type,
+            ElementType::Gateway {
+                kind: GatewayKind::Inclusive,
+                join: false,
+            }
+        );
+
+        let or_join = flow.element("OrJoin").unwrap();
+        assert!(has(or_join.element_type.type_info(), OR_JOIN));
+        assert_eq!(or_join.pre_condition, edges(&[6, 7]));
+
+        let and_join = flow.element("AndJoin").unwrap();
+        assert_eq!(and_join.element_type.type_info(), AND_JOIN);
+        assert_eq!(and_join.pre_condition, edges(&[8, 9]));
+    }
+
+    #[test]
+    fn compiles_event_based_gateways() {
+        let model = compile(&definitions(
+            r#"<bpmn:message id="Paid" name="PAID" />
+            <bpmn:signal id="Cancelled" name="CANCELLED" />
+            <bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:eventBasedGateway id="Wait" />
+                <bpmn:intermediateCatchEvent id="Payment">
+                    <bpmn:messageEventDefinition messageRef="Paid" />
+                </bpmn:intermediateCatchEvent>
+                <bpmn:intermediateCatchEvent id="Cancellation">
+                    <bpmn:signalEventDefinition signalRef="Cancelled" />
+                </bpmn:intermediateCatchEvent>
+                <bpmn:eventBasedGateway id="Stuck" />
+                <bpmn:task id="Ship" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Wait" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Wait" targetRef="Payment" />
+                <bpmn:sequenceFlow id="F3" sourceRef="Wait" targetRef="Cancellation" />
+                <bpmn:sequenceFlow id="F4" sourceRef="Payment" targetRef="Stuck" />
+                <bpmn:sequenceFlow id="F5" sourceRef="Stuck" targetRef="Ship" />
+            </bpmn:process>"#,
+        ))
+        .unwrap();
+        let flow = &model.flows[0];
+        let wait = flow.element("Wait").unwrap();
+        assert_eq!(
+            wait.element_type,
+            ElementType::Gateway {
+                kind: GatewayKind::EventBased,
+                join: false,
+            }
+        );
+        assert_eq!(wait.post_condition, edges(&[2, 3]));
+        assert_eq!(wait.next_elem, vec![3, 4]);
+        let cancellation = flow.element("Cancellation").unwrap();
+        assert_eq!(
+            cancellation.element_type,
+            event(EventPosition::IntermediateCatch, EventKind::Signal, false)
+        );
+        assert_eq!(cancellation.event_code, code("CANCELLED"));
+        assert_eq!(cancellation.pre_condition, edges(&[3]));
+
+        // Leading to a task, the other gateway can't wait
+        assert_eq!(
+            model.diagnostics,
+            vec![Diagnostic::error(
+                Some("Stuck"),
+                "event-based gateways must lead to intermediate message, signal or timer catch events only"
+            )]
+        );
+    }
+
+    #[test]
+    fn compiles_timer_events() {
+        let model = compile(&definitions(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Daily">
+                    <bpmn:timerEventDefinition>
+                        <bpmn:timeCycle>R/P1D</bpmn:timeCycle>
+                    </bpmn:timerEventDefinition>
+                </bpmn:startEvent>
+                <bpmn:subProcess id="Sub">
+                    <bpmn:startEvent id="SubStart" />
+                </bpmn:subProcess>
+                <bpmn:boundaryEvent id="Reminder" attachedToRef="Sub" cancelActivity="false">
+                    <bpmn:timerEventDefinition>
+                        <bpmn:timeCycle>R3/PT10M</bpmn:timeCycle>
+                    </bpmn:timerEventDefinition>
+                </bpmn:boundaryEvent>
+                <bpmn:intermediateCatchEvent id="Wait">
+                    <bpmn:timerEventDefinition>
+                        <bpmn:timeDuration>PT1H30M</bpmn:timeDuration>
+                    </bpmn:timerEventDefinition>
+                </bpmn:intermediateCatchEvent>
+                <bpmn:intermediateCatchEvent id="Deadline">
+                    <bpmn:timerEventDefinition>
+                        <bpmn:timeDate>2020-06-01T12:00:00+02:00</bpmn:timeDate>
+                    </bpmn:timerEventDefinition>
+                </bpmn:intermediateCatchEvent>
+                <bpmn:intermediateCatchEvent id="Computed">
+                    <bpmn:timerEventDefinition>
+                        <bpmn:timeDuration>${delay}</bpmn:timeDuration>
+                    </bpmn:timerEventDefinition>
+                </bpmn:intermediateCatchEvent>
+                <bpmn:endEvent id="End" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Daily" targetRef="Sub" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Sub" targetRef="Wait" />
+                <bpmn:sequenceFlow id="F3" sourceRef="Wait" targetRef="Deadline" />
+                <bpmn:sequenceFlow id="F4" sourceRef="Deadline" targetRef="Computed" />
+                <bpmn:sequenceFlow id="F5" sourceRef="Computed" targetRef="End" />
+                <bpmn:sequenceFlow id="F6" sourceRef="Reminder" targetRef="End" />
+            </bpmn:process>"#,
+        ))
+        .unwrap();
+        let flow = &model.flows[0];
+        let reminder = flow.element("Reminder").unwrap();
+        assert_eq!(
+            reminder.element_type,
+            event(EventPosition::Boundary, EventKind::Timer, false)
+        );
+        assert_eq!(
+            model.timers,
+            vec![
+                Timer {
+                    flow: 0,
+                    element_index: flow.element("Daily").unwrap().element_index,
+                    timer: TimerDefinition::Cycle {
+                        interval: 24 * 60 * 60 * 1000,
+                        repetitions: None,
+                    },
+                },
+                Timer {
+                    flow: 0,
+                    element_index: reminder.element_index,
+                    timer: TimerDefinition::Cycle {
+                        interval: 10 * 60 * 1000,
+                        repetitions: Some(3),
+                    },
+                },
+                Timer {
+                    flow: 0,
+                    element_index: flow.element("Wait").unwrap().element_index,
+                    timer: TimerDefinition::Duration(90 * 60 * 1000),
+                },
+                Timer {
+                    flow: 0,
+                    element_index: flow.element("Deadline").unwrap().element_index,
+                    timer: TimerDefinition::Date(1_591_005_600_000),
+                },
+            ]
+        );
+        assert!(matches!(model.calls().last(), Some(Call::SetTimer(_))));
+
+        // Expressions are not evaluated by the compiler
+        assert_eq!(
+            model.diagnostics,
+            vec![Diagnostic::error(
+                Some("Computed"),
+                "timers must be a constant ISO 8601 duration, date with an offset, or R[n]/duration cycle"
+            )]
+        );
+    }
+
+    #[test]
+    fn compiles_sub_processes_and_boundary_events() {
+        let model = compile_ok(
+            r#"<bpmn:error id="Err" errorCode="E_LATE" />
+            <bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:subProcess id="Sub">
+                    <bpmn:multiInstanceLoopCharacteristics>
+                        <bpmn:loopCardinality>3</bpmn:loopCardinality>
+                    </bpmn:multiInstanceLoopCharacteristics>
+                    <bpmn:startEvent id="SubStart" />
+                    <bpmn:endEvent id="SubEnd">
+                        <bpmn:errorEventDefinition errorRef="Err" />
+                    </bpmn:endEvent>
+                    <bpmn:sequenceFlow id="S1" sourceRef="SubStart" targetRef="SubEnd" />
+                </bpmn:subProcess>
+                <bpmn:boundaryEvent id="Late" attachedToRef="Sub">
+                    <bpmn:errorEventDefinition errorRef="Err" />
+                </bpmn:boundaryEvent>
+                <bpmn:endEvent id="End" />
+                <bpmn:endEvent id="Failed" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Sub" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Sub" targetRef="End" />
+                <bpmn:sequenceFlow id="F3" sourceRef="Late" targetRef="Failed" />
+            </bpmn:process>"#,
+        );
+        assert_eq!(model.flow_position("Sub"), Some(1));
+        let flow = &model.flows[0];
+        let sub = flow.element("Sub").unwrap();
+        assert_eq!(
+            sub.element_type,
+            ElementType::SubProcess {
+                kind: SubProcessKind::Embedded,
+                multi_instance: MultiInstance::Parallel,
+            }
+        );
+        let late = flow.element("Late").unwrap();
+        assert_eq!(
+            late.element_type,
+            event(EventPosition::Boundary, EventKind::Error, true)
+        );
+        assert_eq!(late.event_code, code("E_LATE"));
+        assert!(late.pre_condition.is_empty());
+
+        assert_eq!(
+            model.links,
+            vec![SubProcessLink {
+                flow: 0,
+                parent_index: sub.element_index,
+                child_flow: 1,
+                attached_events: vec![late.element_index],
+                count_instances: 3,
+            }]
+        );
+        let sub_end = model.flows[1].element("SubEnd").unwrap();
+        assert_eq!(
+            sub_end.element_type,
+            event(EventPosition::End, EventKind::Error, false)
+        );
+        assert_eq!(sub_end.event_code, code("E_LATE"));
+        assert!(matches!(
+            model.calls().last(),
+            Some(Call::LinkSubProcess(_))
+        ));
+    }
+
+    #[test]
+    fn leaves_non_constant_multi_instance_characteristics_to_the_contract() {
+        let model = compile(&definitions(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:subProcess id="Approvals">
+                    <bpmn:multiInstanceLoopCharacteristics>
+                        <bpmn:loopCardinality>approvers</bpmn:loopCardinality>
+                        <bpmn:completionCondition>approved</bpmn:completionCondition>
+                    </bpmn:multiInstanceLoopCharacteristics>
+                    <bpmn:startEvent id="ApprovalStart" />
+                </bpmn:subProcess>
+                <bpmn:subProcess id="Reviews">
+                    <bpmn:multiInstanceLoopCharacteristics isSequential="true">
+                        <bpmn:loopCardinality>2</bpmn:loopCardinality>
+                        <bpmn:completionCondition>reviewed</bpmn:completionCondition>
+                    </bpmn:multiInstanceLoopCharacteristics>
+                    <bpmn:startEvent id="ReviewStart" />
+                </bpmn:subProcess>
+                <bpmn:subProcess id="Crowd">
+                    <bpmn:multiInstanceLoopCharacteristics>
+                        <bpmn:loopCardinality>26</bpmn:loopCardinality>
+                    </bpmn:multiInstanceLoopCharacteristics>
+                    <bpmn:startEvent id="CrowdStart" />
+                </bpmn:subProcess>
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Approvals" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Approvals" targetRef="Reviews" />
+                <bpmn:sequenceFlow id="F3" sourceRef="Reviews" targetRef="Crowd" />
+            </bpmn:process>"#,
+        ))
+        .unwrap();
+        let flow = &model.flows[0];
+        let approvals = flow.element("Approvals").unwrap().element_index;
+        let reviews = flow.element("Reviews").unwrap();
+        assert_eq!(
+            reviews.element_type,
+            ElementType::SubProcess {
+                kind: SubProcessKind::Embedded,
+                multi_instance: MultiInstance::Sequential,
+            }
+        );
+        let count_instances: Vec<_> = model
+            .links
+            .iter()
+            .map(|link| link.count_instances)
+            .collect();
+        assert_eq!(count_instances, vec![1, 2, 26]);
+        assert_eq!(
+            model.multi_instances,
+            vec![
+                MultiInstanceCharacteristics {
+                    flow: 0,
+                    element_index: approvals,
+                    cardinality: Some("approvers".to_string()),
+                    completion_condition: Some("approved".to_string()),
+                },
+                MultiInstanceCharacteristics {
+                    flow: 0,
+                    element_index: reviews.element_index,
+                    cardinality: None,
+                    completion_condition: Some("reviewed".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            model.multi_instances[1].definition(|message| message.as_bytes()[..4].to_vec()),
+            MultiInstanceDefinition {
+                cardinality_selector: vec![],
+                completion_selector: b"revi".to_vec(),
+            }
+        );
+        assert!(matches!(
+            model.calls().last(),
+            Some(Call::SetMultiInstance(_))
+        ));
+
+        // More instances than a call of the interpreter can run
+        assert_eq!(
+            model.diagnostics,
+            vec![Diagnostic::error(Some("Crowd"), "more than 25 instances")]
+        );
+    }
+
+    #[test]
+    fn compiles_standard_loops() {
+        let model = compile(&definitions(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:subProcess id="Reminders">
+                    <bpmn:standardLoopCharacteristics testBefore="true" loopMaximum="12">
+                        <bpmn:loopCondition>unpaid</bpmn:loopCondition>
+                    </bpmn:standardLoopCharacteristics>
+                    <bpmn:startEvent id="ReminderStart" />
+                </bpmn:subProcess>
+                <bpmn:callActivity id="Retry" calledElement="Process">
+                    <bpmn:standardLoopCharacteristics />
+                </bpmn:callActivity>
+                <bpmn:subProcess id="Forever">
+                    <bpmn:standardLoopCharacteristics loopMaximum="1000" />
+                    <bpmn:startEvent id="ForeverStart" />
+                </bpmn:subProcess>
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Reminders" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Reminders" targetRef="Retry" />
+                <bpmn:sequenceFlow id="F3" sourceRef="Retry" targetRef="Forever" />
+            </bpmn:process>"#,
+        ))
+        .unwrap();
+        let flow = &model.flows[0];
+        let reminders = flow.element("Reminders").unwrap();
+        assert_eq!(
+            reminders.element_type,
+            ElementType::SubProcess {
+                kind: SubProcessKind::Embedded,
+                multi_instance: MultiInstance::StandardLoop { test_before: true },
+            }
+        );
+        assert!(has(reminders.element_type.type_info(), LOOP_TEST_BEFORE));
+        assert_eq!(
+            flow.element("Retry").unwrap().element_type,
+            ElementType::SubProcess {
+                kind: SubProcessKind::CallActivity,
+                multi_instance: MultiInstance::StandardLoop { test_before: false },
+            }
+        );
+        // Without condition nor maximum, the loop runs up to `MAX_LOOP_ITERATIONS`
+        assert_eq!(
+            model.loops,
+            vec![LoopCharacteristics {
+                flow: 0,
+                element_index: reminders.element_index,
+                condition: Some("unpaid".to_string()),
+                maximum: Some(12),
+            }]
+        );
+        assert_eq!(
+            model.loops[0].definition(|_| vec![1, 2, 3, 4]),
+            LoopDefinition {
+                condition_selector: vec![1, 2, 3, 4],
+                maximum: Some(12),
+            }
+        );
+        assert!(matches!(model.calls().last(), Some(Call::SetLoop(_))));
+        assert_eq!(
+            model.diagnostics,
+            vec![Diagnostic::error(
+                Some("Forever"),
+                "loop maximum must be a constant from 1 to 100"
+            )]
+        );
+    }
+
+    #[test]
+    fn links_call_activities_to_processes_defined_later() {
+        let model = compile_ok(
+            r#"<bpmn:process id="Main">
+                <bpmn:startEvent id="Start" />
+                <bpmn:callActivity id="Call" calledElement="Called">
+                    <bpmn:multiInstanceLoopCharacteristics isSequential="true">
+                        <bpmn:loopCardinality>2</bpmn:loopCardinality>
+                    </bpmn:multiInstanceLoopCharacteristics>
+                </bpmn:callActivity>
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Call" />
+            </bpmn:process>
+            <bpmn:process id="Called">
+                <bpmn:startEvent id="CalledStart" />
+            </bpmn:process>"#,
+        );
+        let call = model.flows[0].element("Call").unwrap();
+        assert_eq!(
+            call.element_type,
+            ElementType::SubProcess {
+                kind: SubProcessKind::CallActivity,
+                multi_instance: MultiInstance::Sequential,
+            }
+        );
+        assert_eq!(model.links[0].child_flow, 1);
+        assert_eq!(model.links[0].count_instances, 2);
+    }
+
+    #[test]
+    fn compiles_event_sub_processes_with_their_start_event() {
+        let model = compile_ok(
+            r#"<bpmn:escalation id="Esc" escalationCode="E_LATE" />
+            <bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:endEvent id="End" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="End" />
+                <bpmn:subProcess id="OnLate" triggeredByEvent="true">
+                    <bpmn:startEvent id="LateStart" isInterrupting="false">
+                        <bpmn:escalationEventDefinition escalationRef="Esc" />
+                    </bpmn:startEvent>
+                    <bpmn:endEvent id="LateEnd" />
+                    <bpmn:sequenceFlow id="S1" sourceRef="LateStart" targetRef="LateEnd" />
+                </bpmn:subProcess>
+            </bpmn:process>"#,
+        );
+        let flow = &model.flows[0];
+        let on_late = flow.element("OnLate").unwrap();
+        assert_eq!(
+            on_late.element_type,
+            ElementType::SubProcess {
+                kind: SubProcessKind::EventSubProcess,
+                multi_instance: MultiInstance::None,
+            }
+        );
+        // The enclosing process catches the escalation, starting the event sub-process
+        let late_start = flow.element("LateStart").unwrap();
+        assert_eq!(late_start.element_index, 4);
+        assert_eq!(
+            late_start.element_type,
+            event(
+                EventPosition::EventSubProcessStart,
+                EventKind::Escalation,
+                false
+            )
+        );
+        assert!(has(
+            late_start.element_type.type_info(),
+            EVENT_SUB_PROCESS_START | ESCALATION
+        ));
+        assert_eq!(late_start.event_code, code("E_LATE"));
+        assert!(late_start.pre_condition.is_empty() && late_start.next_elem.is_empty());
+        assert_eq!(
+            model.links,
+            vec![SubProcessLink {
+                flow: 0,
+                parent_index: on_late.element_index,
+                child_flow: 1,
+                attached_events: vec![4],
+                count_instances: 1,
+            }]
+        );
+        // Once started, the event sub-process runs from a none start event
+        let child_start = model.flows[1].element("LateStart").unwrap();
+        assert_eq!(child_start.element_type, start_event());
+    }
+
+    #[test]
+    fn reports_unsupported_event_sub_process_triggers() {
+        let model = compile(&definitions(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:subProcess id="OnMessage" triggeredByEvent="true">
+                    <bpmn:startEvent id="MessageStart">
+                        <bpmn:messageEventDefinition />
+                    </bpmn:startEvent>
+                </bpmn:subProcess>
+            </bpmn:process>"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            model.diagnostics,
+            vec![Diagnostic::error(
+                Some("MessageStart"),
+                "event sub-processes are only started by error, escalation or signal events"
+            )]
+        );
+        assert_eq!(model.links[0].attached_events, Vec::<u128>::new());
+    }
+
+    #[test]
+    fn reports_unsupported_constructs() {
+        let model = compile(&definitions(
+            r#"<bpmn:process id="Process">
+                <bpmn:startEvent id="Start" />
+                <bpmn:startEvent id="OtherStart" />
+                <bpmn:complexGateway id="Complex" />
+                <bpmn:intermediateCatchEvent id="Timer">
+                    <bpmn:timerEventDefinition />
+                </bpmn:intermediateCatchEvent>
+                <bpmn:sendTask id="Send" />
+                <bpmn:task id="Looping">
+                    <bpmn:standardLoopCharacteristics />
+                </bpmn:task>
+                <bpmn:exclusiveGateway id="Mixed" />
+                <bpmn:callActivity id="Call" calledElement="Missing" />
+                <bpmn:sequenceFlow id="F1" sourceRef="Start" targetRef="Complex" />
+                <bpmn:sequenceFlow id="F2" sourceRef="Start" targetRef="Mixed" />
+                <bpmn:sequenceFlow id="F3" sourceRef="OtherStart" targetRef="Mixed" />
+                <bpmn:sequenceFlow id="F4" sourceRef="Mixed" targetRef="Send" />
+                <bpmn:sequenceFlow id="F5" sourceRef="Mixed" targetRef="Timer" />
+                <bpmn:sequenceFlow id="F6" sourceRef="Send" targetRef="Nowhere" />
+            </bpmn:process>"#,
+        ))
+        .unwrap();
+        assert!(model.has_errors());
+        let reported = |element: &str, severity: Severity| {
+            model
+                .diagnostics
+                .iter()
+                .any(|d| d.element.as_deref() == Some(element) && d.severity == severity)
+        };
+        assert!(reported("Process", Severity::Error));
+        assert!(reported("Complex", Severity::Error));
+        assert!(reported("Timer", Severity::Error));
+        assert!(reported("Send", Severity::Warning));
+        assert!(reported("Looping", Severity::Error));
+        assert!(reported("Mixed", Severity::Error));
+        assert!(reported("Call", Severity::Error));
+        assert!(reported("F6", Severity::Error));
+        // Sequence flows to skipped elements are not reported twice
+        assert!(!reported("F1", Severity::Error));
+        assert_eq!(
+            model.flows[0].element("Send").unwrap().post_condition.len(),
+            0
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        assert!(matches!(compile("<definitions"), Err(CompileError::Xml(_))));
+        assert_eq!(
+            compile("<definitions xmlns=\"urn:other\" />"),
+            Err(CompileError::NotBpmn)
+        );
+        assert_eq!(compile(&definitions("")), Err(CompileError::NoProcess));
+    }
+}
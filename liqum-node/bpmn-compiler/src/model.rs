@@ -0,0 +1,158 @@
+//! Output of the compiler, mirroring the arguments of the pallet's dispatchables.
+
+use crate::diagnostic::{Diagnostic, Severity};
+use bpmn_interpreter::{
+    types::{ElementType, LoopDefinition, MultiInstanceDefinition, TimerDefinition},
+    BitSet,
+};
+
+/// A compiled BPMN model: one flow per process and per embedded sub-process.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompiledModel {
+    pub flows: Vec<Flow>,
+    pub links: Vec<SubProcessLink>,
+    pub timers: Vec<Timer>,
+    pub multi_instances: Vec<MultiInstanceCharacteristics>,
+    pub loops: Vec<LoopCharacteristics>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Element table of a process or sub-process, i.e. the content of one `Iflow`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Flow {
+    /// BPMN id of the process or sub-process
+    pub id: String,
+    pub elements: Vec<Element>,
+}
+
+/// Arguments of a `set_element` call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element {
+    /// BPMN id of the flow node, or of the start event of the event sub-process it starts
+    pub id: String,
+    pub element_index: u128,
+    /// Indexes of the incoming sequence flows
+    pub pre_condition: BitSet,
+    /// Indexes of the outgoing sequence flows
+    pub post_condition: BitSet,
+    pub element_type: ElementType,
+    pub event_code: [u8; 32],
+    /// Indexes of the targets of the outgoing sequence flows
+    pub next_elem: Vec<u128>,
+}
+
+/// Arguments of a `link_sub_process` call, flows are referred to by their position in
+/// `CompiledModel::flows`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubProcessLink {
+    pub flow: usize,
+    pub parent_index: u128,
+    pub child_flow: usize,
+    /// Indexes of the boundary events attached to the sub-process
+    pub attached_events: Vec<u128>,
+    pub count_instances: u128,
+}
+
+/// Arguments of a `set_timer` call, in milliseconds as the moments of the timestamp pallet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timer {
+    pub flow: usize,
+    pub element_index: u128,
+    pub timer: TimerDefinition<u64>,
+}
+
+/// Arguments of a `set_multi_instance` call, for the multi-instance sub-processes whose number
+/// of instances or completion is decided by the data & scripts contract. The compiler keeps
+/// their expressions, naming the messages of the contract whose selectors the deployer knows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiInstanceCharacteristics {
+    pub flow: usize,
+    pub element_index: u128,
+    /// Expression of the `loopCardinality`, unless a constant
+    pub cardinality: Option<String>,
+    /// Expression of the `completionCondition`
+    pub completion_condition: Option<String>,
+}
+
+impl MultiInstanceCharacteristics {
+    /// Definition calling the messages `selector` resolves the expressions to.
+    pub fn definition(&self, selector: impl Fn(&str) -> Vec<u8>) -> MultiInstanceDefinition {
+        MultiInstanceDefinition {
+            cardinality_selector: self.cardinality.as_deref().map_or_else(Vec::new, &selector),
+            completion_selector: self
+                .completion_condition
+                .as_deref()
+                .map_or_else(Vec::new, &selector),
+        }
+    }
+}
+
+/// Arguments of a `set_loop` call, for the standard loop sub-processes with a loop condition
+/// or a maximum. The condition is kept as an expression, like those of
+/// `MultiInstanceCharacteristics`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoopCharacteristics {
+    pub flow: usize,
+    pub element_index: u128,
+    /// Expression of the `loopCondition`
+    pub condition: Option<String>,
+    pub maximum: Option<u32>,
+}
+
+impl LoopCharacteristics {
+    /// Definition calling the message `selector` resolves the condition to.
+    pub fn definition(&self, selector: impl Fn(&str) -> Vec<u8>) -> LoopDefinition {
+        LoopDefinition {
+            condition_selector: self.condition.as_deref().map_or_else(Vec::new, selector),
+            maximum: self.maximum,
+        }
+    }
+}
+
+/// A call to submit to the pallet in order to deploy the model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Call<'a> {
+    SetElement(usize, &'a Element),
+    LinkSubProcess(&'a SubProcessLink),
+    SetTimer(&'a Timer),
+    SetMultiInstance(&'a MultiInstanceCharacteristics),
+    SetLoop(&'a LoopCharacteristics),
+}
+
+impl CompiledModel {
+    /// Whether the model contains constructs the interpreter can't execute.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    /// Position of the flow compiled from the process or sub-process `id`.
+    pub fn flow_position(&self, id: &str) -> Option<usize> {
+        self.flows.iter().position(|flow| flow.id == id)
+    }
+
+    /// Calls deploying the model, every element is set before the sub-processes are linked and
+    /// the timers, multi-instance and loop characteristics set.
+    pub fn calls(&self) -> Vec<Call<'_>> {
+        self.flows
+            .iter()
+            .enumerate()
+            .flat_map(|(position, flow)| {
+                flow.elements
+                    .iter()
+                    .map(move |element| Call::SetElement(position, element))
+            })
+            .chain(self.links.iter().map(Call::LinkSubProcess))
+            .chain(self.timers.iter().map(Call::SetTimer))
+            .chain(self.multi_instances.iter().map(Call::SetMultiInstance))
+            .chain(self.loops.iter().map(Call::SetLoop))
+            .collect()
+    }
+}
+
+impl Flow {
+    pub fn element(&self, id: &str) -> Option<&Element> {
+        self.elements.iter().find(|element| element.id == id)
+    }
+}
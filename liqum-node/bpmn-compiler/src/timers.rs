@@ -0,0 +1,137 @@
+//! ISO 8601 durations, dates and repeating intervals of timer event definitions, in
+//! milliseconds as the moments of the timestamp pallet.
+
+const SECOND: u64 = 1_000;
+const MINUTE: u64 = 60 * SECOND;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+/// Duration such as `P1DT12H` or `PT0.5S`. Years and months have no fixed length and are
+/// not supported.
+pub fn duration(value: &str) -> Option<u64> {
+    if !value.starts_with('P') {
+        return None;
+    }
+    let mut rest = &value[1..];
+    let mut time = false;
+    let mut millis: u64 = 0;
+    let mut parsed = false;
+    while !rest.is_empty() {
+        if !time && rest.starts_with('T') {
+            time = true;
+            rest = &rest[1..];
+            continue;
+        }
+        let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let unit = match (time, rest[end..].chars().next()?) {
+            (false, 'W') => 7 * DAY,
+            (false, 'D') => DAY,
+            (true, 'H') => HOUR,
+            (true, 'M') => MINUTE,
+            (true, 'S') => SECOND,
+            _ => return None,
+        };
+        millis = millis.checked_add(scaled(&rest[..end], unit)?)?;
+        parsed = true;
+        rest = &rest[end + 1..];
+    }
+    if parsed {
+        Some(millis)
+    } else {
+        None
+    }
+}
+
+/// Date and time with an offset, such as `2020-06-01T10:00:00Z` or
+/// `2020-06-01T12:00:00.5+02:00`, from the Unix epoch.
+pub fn date(value: &str) -> Option<u64> {
+    let separator = value.find('T')?;
+    let (date, time) = (&value[..separator], &value[separator + 1..]);
+
+    let mut parts = date.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || month < 1 || month > 12 || day < 1 || day > 31 {
+        return None;
+    }
+
+    // The offset is required, local times are ambiguous
+    let (clock, offset) = if time.ends_with('Z') {
+        (&time[..time.len() - 1], 0)
+    } else {
+        let sign = time.rfind(|c| c == '+' || c == '-')?;
+        let mut offset = time[sign + 1..].split(':');
+        let hours: i64 = offset.next()?.parse().ok()?;
+        let minutes: i64 = offset
+            .next()
+            .map_or(Some(0), |minutes| minutes.parse().ok())?;
+        let offset = (hours * 60 + minutes) * 60 * SECOND as i64;
+        (
+            &time[..sign],
+            if time[sign..].starts_with('-') {
+                -offset
+            } else {
+                offset
+            },
+        )
+    };
+    let mut clock = clock.split(':');
+    let hours: u64 = clock.next()?.parse().ok()?;
+    let minutes: u64 = clock.next()?.parse().ok()?;
+    let seconds = clock
+        .next()
+        .map_or(Some(0), |seconds| scaled(seconds, SECOND))?;
+    if clock.next().is_some() || hours > 23 || minutes > 59 || seconds >= MINUTE {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let millis = days * DAY as i64 + (hours * HOUR + minutes * MINUTE + seconds) as i64 - offset;
+    if millis < 0 {
+        None
+    } else {
+        Some(millis as u64)
+    }
+}
+
+/// Repeating interval such as `R3/PT10M`, or `R/P1D` repeating forever. Returns the interval
+/// and the number of repetitions.
+pub fn cycle(value: &str) -> Option<(u64, Option<u32>)> {
+    let mut parts = value.split('/');
+    let repetitions = parts.next()?;
+    let interval = duration(parts.next()?)?;
+    if parts.next().is_some() || !repetitions.starts_with('R') {
+        return None;
+    }
+    let repetitions = match &repetitions[1..] {
+        "" => None,
+        repetitions => Some(repetitions.parse().ok()?),
+    };
+    Some((interval, repetitions))
+}
+
+/// `number` times `unit`, `number` having an optional decimal fraction.
+fn scaled(number: &str, unit: u64) -> Option<u64> {
+    let (whole, fraction) = match number.find('.') {
+        Some(dot) => (&number[..dot], &number[dot + 1..]),
+        None => (number, ""),
+    };
+    let mut value = whole.parse::<u64>().ok()?.checked_mul(unit)?;
+    let mut scale = unit;
+    for digit in fraction.chars() {
+        scale /= 10;
+        value = value.checked_add(u64::from(digit.to_digit(10)?) * scale)?;
+    }
+    Some(value)
+}
+
+/// Days from the Unix epoch to the date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((i64::from(month) + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
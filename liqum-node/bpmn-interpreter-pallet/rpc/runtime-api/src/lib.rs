@@ -0,0 +1,120 @@
+//! Runtime API definition of the bpmn interpreter pallet, used by the `bpmn_*` RPC
+//! methods to read process models and instances at a given block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// Lifecycle of a process instance.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum InstanceStatus {
+    /// Tokens left to move, activities or events left to complete
+    Running,
+    /// Every token reached an end event
+    Completed,
+    /// Ended by a terminate end event or an interrupting event, or by the end of its parent
+    Terminated,
+    /// Ended by an error no event caught
+    Failed,
+    /// Execution frozen until resumed
+    Suspended,
+}
+
+impl Default for InstanceStatus {
+    fn default() -> Self {
+        InstanceStatus::Running
+    }
+}
+
+/// Decoded state of a process instance.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct InstanceState<InstanceId> {
+    /// Process model the instance executes
+    pub process: InstanceId,
+    /// Instance that created this one, `None` for a root case
+    pub parent: Option<InstanceId>,
+    /// Sub-process or call activity of the parent this instance executes
+    pub index_in_parent: u128,
+    /// Indexes of the sequence flows holding a token
+    pub marking: Vec<u128>,
+    /// Indexes of the activities started, and of the receive tasks and catch events
+    /// waiting, not completed yet
+    pub started_activities: Vec<u128>,
+    /// Indexes of the elements whose pre condition is satisfied by the marking
+    pub enabled_elements: Vec<u128>,
+    pub status: InstanceStatus,
+    /// Index of the instance among those of its multi-instance activity, from 0
+    pub loop_counter: u128,
+}
+
+/// Node of the sub-process tree of an instance.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct SubProcessInstance<InstanceId> {
+    pub instance: InstanceId,
+    pub parent: Option<InstanceId>,
+    pub index_in_parent: u128,
+}
+
+/// Element of a process model.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ModelElement {
+    pub element_index: u128,
+    /// Indexes of the incoming sequence flows
+    pub pre_condition: Vec<u128>,
+    /// Indexes of the outgoing sequence flows
+    pub post_condition: Vec<u128>,
+    pub type_info: u128,
+    pub event_code: Option<[u8; 32]>,
+    pub next_elem: Vec<u128>,
+}
+
+/// Sub-process or call activity of a process model and the model it instantiates.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ModelSubProcess<InstanceId> {
+    pub parent_index: u128,
+    pub child_flow: InstanceId,
+    pub attached_events: Vec<u128>,
+    pub count_instances: u128,
+}
+
+/// Process model as stored by the pallet.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ProcessModel<InstanceId> {
+    pub start_event: u128,
+    pub elements: Vec<ModelElement>,
+    pub sub_processes: Vec<ModelSubProcess<InstanceId>>,
+}
+
+sp_api::decl_runtime_apis! {
+    /// The API to query process models and instances of the bpmn interpreter.
+    pub trait BpmnInterpreterApi<InstanceId> where
+        InstanceId: Codec,
+    {
+        /// Returns the state of the instance, `None` if it does not exist.
+        fn instance_state(instance_id: InstanceId) -> Option<InstanceState<InstanceId>>;
+
+        /// Returns the indexes of the elements of the instance that can be executed.
+        fn enabled_elements(instance_id: InstanceId) -> Vec<u128>;
+
+        /// Returns the instance followed by its sub-process instances, in pre-order.
+        fn sub_process_tree(instance_id: InstanceId) -> Vec<SubProcessInstance<InstanceId>>;
+
+        /// Returns the process model, `None` if it does not exist.
+        fn process_model(process_id: InstanceId) -> Option<ProcessModel<InstanceId>>;
+    }
+}
@@ -0,0 +1,127 @@
+//! Node-specific `bpmn_*` RPC methods to query process models and instances.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use bpmn_interpreter_rpc_runtime_api::{
+    BpmnInterpreterApi as BpmnInterpreterRuntimeApi, InstanceState, ModelElement, ModelSubProcess,
+    ProcessModel, SubProcessInstance,
+};
+
+const RUNTIME_ERROR: i64 = 1;
+
+/// BPMN interpreter RPC methods.
+#[rpc]
+pub trait BpmnInterpreterApi<BlockHash, InstanceId> {
+    /// Returns the decoded state of a process instance.
+    #[rpc(name = "bpmn_instanceState")]
+    fn instance_state(
+        &self,
+        instance_id: InstanceId,
+        at: Option<BlockHash>,
+    ) -> Result<Option<InstanceState<InstanceId>>>;
+
+    /// Returns the indexes of the elements of a process instance that can be executed.
+    #[rpc(name = "bpmn_enabledElements")]
+    fn enabled_elements(&self, instance_id: InstanceId, at: Option<BlockHash>)
+        -> Result<Vec<u128>>;
+
+    /// Returns a process instance and its sub-process instances, in pre-order.
+    #[rpc(name = "bpmn_subProcessTree")]
+    fn sub_process_tree(
+        &self,
+        instance_id: InstanceId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<SubProcessInstance<InstanceId>>>;
+
+    /// Returns a deployed process model.
+    #[rpc(name = "bpmn_processModel")]
+    fn process_model(
+        &self,
+        process_id: InstanceId,
+        at: Option<BlockHash>,
+    ) -> Result<Option<ProcessModel<InstanceId>>>;
+}
+
+/// An implementation of bpmn interpreter specific RPC methods.
+pub struct BpmnInterpreter<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> BpmnInterpreter<C, B> {
+    /// Create new `BpmnInterpreter` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        BpmnInterpreter {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+fn runtime_error(err: impl std::fmt::Debug) -> Error {
+    Error {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Runtime trapped while querying the bpmn interpreter".into(),
+        data: Some(format!("{:?}", err).into()),
+    }
+}
+
+impl<C, Block, InstanceId> BpmnInterpreterApi<<Block as BlockT>::Hash, InstanceId>
+    for BpmnInterpreter<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static,
+    C: ProvideRuntimeApi<Block>,
+    C: HeaderBackend<Block>,
+    C::Api: BpmnInterpreterRuntimeApi<Block, InstanceId>,
+    InstanceId: Codec,
+{
+    fn instance_state(
+        &self,
+        instance_id: InstanceId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<InstanceState<InstanceId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.instance_state(&at, instance_id).map_err(runtime_error)
+    }
+
+    fn enabled_elements(
+        &self,
+        instance_id: InstanceId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<u128>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.enabled_elements(&at, instance_id)
+            .map_err(runtime_error)
+    }
+
+    fn sub_process_tree(
+        &self,
+        instance_id: InstanceId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<SubProcessInstance<InstanceId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.sub_process_tree(&at, instance_id)
+            .map_err(runtime_error)
+    }
+
+    fn process_model(
+        &self,
+        process_id: InstanceId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<ProcessModel<InstanceId>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        api.process_model(&at, process_id).map_err(runtime_error)
+    }
+}
@@ -0,0 +1,102 @@
+//! Read-only queries backing the `BpmnInterpreterApi` runtime API.
+
+use crate::{Module, Trait};
+use bpmn_interpreter_rpc_runtime_api::{
+    InstanceState, ModelElement, ModelSubProcess, ProcessModel, SubProcessInstance,
+};
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+
+impl<T: Trait> Module<T> {
+    pub fn instance_state(instance_id: T::InstanceId) -> Option<InstanceState<T::InstanceId>> {
+        let idata = Self::ensure_idata_instance_exists(instance_id).ok()?;
+        let enabled_elements = Self::enabled_elements(instance_id);
+        Some(InstanceState {
+            process: idata.iflow_node,
+            parent: idata.idata_parent,
+            index_in_parent: idata.index_in_parent,
+            marking: idata.tokens_on_edges.iter().collect(),
+            started_activities: idata.started_activities.iter().collect(),
+            enabled_elements,
+            status: idata.status,
+            loop_counter: idata.loop_counter,
+        })
+    }
+
+    pub fn enabled_elements(instance_id: T::InstanceId) -> Vec<u128> {
+        let idata = match Self::ensure_idata_instance_exists(instance_id) {
+            Ok(idata) => idata,
+            Err(_) => return vec![],
+        };
+        match Self::ensure_iflow_instance_exists(idata.iflow_node) {
+            Ok(iflow) => {
+                iflow.get_enabled_elements(&idata.tokens_on_edges, &idata.started_activities)
+            }
+            Err(_) => vec![],
+        }
+    }
+
+    pub fn sub_process_tree(instance_id: T::InstanceId) -> Vec<SubProcessInstance<T::InstanceId>> {
+        let mut tree = vec![];
+        let mut visited = BTreeSet::<u128>::new();
+        let mut stack = vec![instance_id];
+        while let Some(instance) = stack.pop() {
+            if !visited.insert(instance.into()) {
+                continue;
+            }
+            let idata = match Self::ensure_idata_instance_exists(instance) {
+                Ok(idata) => idata,
+                Err(_) => continue,
+            };
+            // Reversed so that the children are popped in order
+            stack.extend(idata.children.values().flatten().rev().copied());
+            tree.push(SubProcessInstance {
+                instance,
+                parent: idata.idata_parent,
+                index_in_parent: idata.index_in_parent,
+            });
+        }
+        tree
+    }
+
+    pub fn process_model(process_id: T::InstanceId) -> Option<ProcessModel<T::InstanceId>> {
+        let iflow = Self::ensure_iflow_instance_exists(process_id).ok()?;
+        let elements = iflow
+            .cond_table
+            .iter()
+            .map(
+                |(element_index, (pre_condition, post_condition, type_info))| ModelElement {
+                    element_index: *element_index,
+                    pre_condition: pre_condition.iter().collect(),
+                    post_condition: post_condition.iter().collect(),
+                    type_info: *type_info,
+                    event_code: iflow.event_code.get(element_index).copied(),
+                    next_elem: iflow
+                        .next_elem
+                        .get(element_index)
+                        .cloned()
+                        .unwrap_or_default(),
+                },
+            )
+            .collect();
+        let sub_processes = iflow
+            .parent_references
+            .iter()
+            .map(|(parent_index, child_flow)| ModelSubProcess {
+                parent_index: *parent_index,
+                child_flow: *child_flow,
+                attached_events: iflow
+                    .attached_to
+                    .iter()
+                    .filter(|(_, attached_to)| *attached_to == parent_index)
+                    .map(|(event_index, _)| *event_index)
+                    .collect(),
+                count_instances: iflow.instance_count.get(parent_index).copied().unwrap_or(1),
+            })
+            .collect();
+        Some(ProcessModel {
+            start_event: iflow.start_evt,
+            elements,
+            sub_processes,
+        })
+    }
+}
@@ -0,0 +1,701 @@
+//! Benchmarks of the dispatchables, the components are described in `weights.rs`.
+
+use super::*;
+use frame_benchmarking::{account, benchmarks};
+use frame_support::{storage::IterableStorageMap, traits::OnInitialize};
+use frame_system::RawOrigin;
+use sp_runtime::traits::{Bounded, Hash, Zero};
+
+const SEED: u32 = 0;
+const MAX_SUCCESSORS: u32 = 100;
+const MAX_ATTACHED_EVENTS: u32 = 50;
+const MAX_SELECTOR_BYTES: u32 = 1_000;
+/// Bytes of a condition holding an index close to `MAX_BIT_INDEX`
+const MAX_CONDITION_BYTES: u32 = 8_000;
+const MAX_ELEMENTS: u32 = 100;
+const MAX_EXECUTED_ELEMENTS: u32 = 100;
+/// Parallel branches, kept below the `MaxExecutionSteps` of the runtime
+const MAX_BRANCHES: u32 = 90;
+
+/// Element consuming the token of the sequence flow numbered after it and producing a
+/// token for each of its successors.
+fn element(
+    element_index: u128,
+    element_type: ElementType,
+    next_elem: Vec<u128>,
+) -> ElementDefinition {
+    let post_condition = next_elem
+        .iter()
+        .fold(BitSet::new(), |post_condition, next| {
+            &post_condition | &BitSet::with_bit(*next)
+        });
+    ElementDefinition {
+        element_index,
+        pre_condition: BitSet::with_bit(element_index),
+        post_condition,
+        element_type,
+        event_code: [0; 32],
+        next_elem,
+    }
+}
+
+fn start_event(next_elem: Vec<u128>) -> ElementDefinition {
+    let start = ElementType::Event {
+        position: EventPosition::Start,
+        kind: EventKind::Default,
+        interrupting: false,
+    };
+    element(0, start, next_elem)
+}
+
+fn task(element_index: u128, next_elem: Vec<u128>) -> ElementDefinition {
+    element(
+        element_index,
+        ElementType::Task(TaskKind::Default),
+        next_elem,
+    )
+}
+
+fn timer_event(
+    element_index: u128,
+    position: EventPosition,
+    next_elem: Vec<u128>,
+) -> ElementDefinition {
+    let timer = ElementType::Event {
+        position,
+        kind: EventKind::Timer,
+        interrupting: false,
+    };
+    element(element_index, timer, next_elem)
+}
+
+fn sub_process(element_index: u128, next_elem: Vec<u128>) -> ElementDefinition {
+    let sub_process = ElementType::SubProcess {
+        kind: SubProcessKind::Embedded,
+        multi_instance: MultiInstance::None,
+    };
+    element(element_index, sub_process, next_elem)
+}
+
+fn link<T: Trait>(parent_index: u128, child_flow: T::InstanceId) -> SubProcessLink<T::InstanceId> {
+    SubProcessLink {
+        parent_index,
+        child_flow,
+        attached_events: vec![],
+        count_instances: 1,
+    }
+}
+
+/// Deploys the model and gives it a data & scripts contract, which the benchmarks never
+/// call, so that its instances can be created.
+fn deploy<T: Trait>(
+    caller: &T::AccountId,
+    elements: Vec<ElementDefinition>,
+    links: Vec<SubProcessLink<T::InstanceId>>,
+) -> Result<T::InstanceId, &'static str> {
+    Module::<T>::deploy_process(
+        RawOrigin::Signed(caller.clone()).into(),
+        elements,
+        links,
+        Default::default(),
+        vec![],
+        vec![],
+    )?;
+    let process_id = Module::<T>::process_id_count();
+    <IflowById<T>>::mutate(process_id, |iflow| {
+        iflow.factory.address = Some(account("contract", 0, SEED))
+    });
+    Ok(process_id)
+}
+
+/// Chain of `depth` process models, each one a sub-process of the next, returning the
+/// outermost one.
+fn nested_sub_processes<T: Trait>(
+    caller: &T::AccountId,
+    depth: u32,
+) -> Result<Option<T::InstanceId>, &'static str> {
+    let mut child_flow = None;
+    for _ in 0..depth {
+        let process_id = match child_flow {
+            None => deploy::<T>(caller, vec![start_event(vec![1]), task(1, vec![])], vec![])?,
+            Some(child_flow) => deploy::<T>(
+                caller,
+                vec![start_event(vec![1]), sub_process(1, vec![])],
+                vec![link::<T>(1, child_flow)],
+            )?,
+        };
+        child_flow = Some(process_id);
+    }
+    Ok(child_flow)
+}
+
+/// Stores the instance under a fresh identifier, as the interpreter does.
+fn store_instance<T: Trait>(idata: Idata<T>) -> Result<T::InstanceId, &'static str> {
+    let instance_id = Module::<T>::next_instance_id()?;
+    <InstanceIdCount<T>>::put(instance_id);
+    <IdataById<T>>::insert(instance_id, idata);
+    Ok(instance_id)
+}
+
+/// Model whose start event is followed by a parallel split into a chain of `e` tasks, `q`
+/// single task branches and, unless `d` is zero, `d` nested sub-processes.
+fn execution_model<T: Trait>(
+    caller: &T::AccountId,
+    e: u32,
+    q: u32,
+    d: u32,
+) -> Result<T::InstanceId, &'static str> {
+    let parallel_split = ElementType::Gateway {
+        kind: GatewayKind::Parallel,
+        join: false,
+    };
+    let chain = 3..3 + e as u128;
+    let branches = chain.end..chain.end + q as u128;
+
+    let mut split_next: Vec<u128> = branches.clone().collect();
+    let mut elements = vec![start_event(vec![1])];
+    let mut links = vec![];
+    if let Some(child_flow) = nested_sub_processes::<T>(caller, d)? {
+        split_next.push(2);
+        elements.push(sub_process(2, vec![]));
+        links.push(link::<T>(2, child_flow));
+    }
+    if !chain.is_empty() {
+        split_next.push(chain.start);
+    }
+    elements.push(element(1, parallel_split, split_next));
+    elements.extend(chain.clone().map(|element_index| {
+        let next_elem = Some(element_index + 1).filter(|next| chain.contains(next));
+        task(element_index, next_elem.into_iter().collect())
+    }));
+    elements.extend(branches.map(|element_index| task(element_index, vec![])));
+    deploy::<T>(caller, elements, links)
+}
+
+/// Running root instance whose sub-process runs `d - 1` instances, returning the instances
+/// of the tree, the root one first.
+fn instance_tree<T: Trait>(
+    caller: &T::AccountId,
+    d: u32,
+) -> Result<Vec<T::InstanceId>, &'static str> {
+    let child_flow = nested_sub_processes::<T>(caller, 1)?.ok_or("no sub-process deployed")?;
+    let process_id = deploy::<T>(
+        caller,
+        vec![start_event(vec![1]), sub_process(1, vec![])],
+        vec![link::<T>(1, child_flow)],
+    )?;
+    let mut root = Idata::default();
+    root.set_parent(None, process_id, 0);
+    let root_id = store_instance::<T>(root)?;
+    let mut tree = vec![root_id];
+    for _ in 1..d {
+        let mut child = Idata::default();
+        child.set_parent(Some(root_id), child_flow, 1);
+        child.set_marking(BitSet::with_bit(1));
+        let child_id = store_instance::<T>(child)?;
+        <IdataById<T>>::mutate(root_id, |root| {
+            root.set_activity_marking(BitSet::with_bit(1));
+            root.add_child(1, child_id);
+        });
+        tree.push(child_id);
+    }
+    Ok(tree)
+}
+
+/// Completed root instance created by the caller, see `instance_tree`, each instance of the
+/// tree correlated by the same key.
+fn ended_instance_tree<T: Trait>(
+    caller: &T::AccountId,
+    d: u32,
+) -> Result<Vec<T::InstanceId>, &'static str> {
+    let tree = instance_tree::<T>(caller, d)?;
+    let deposit = T::InstanceDeposit::get();
+    <T as Trait>::Currency::make_free_balance_be(caller, BalanceOf::<T>::max_value());
+    Module::<T>::reserve_deposit(tree[0], caller.clone(), deposit)?;
+    <IdataById<T>>::mutate(tree[0], |idata| idata.set_status(InstanceStatus::Completed));
+    let correlation_key = T::Hashing::hash_of(&tree[0]);
+    for &instance_id in &tree {
+        <CorrelationKey<T>>::insert(instance_id, correlation_key);
+    }
+    <CorrelatedInstances<T>>::insert(correlation_key, tree.clone());
+    Ok(tree)
+}
+
+benchmarks! {
+    _ { }
+
+    set_element {
+        let n in 0 .. MAX_SUCCESSORS;
+        let b in 0 .. MAX_CONDITION_BYTES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = deploy::<T>(&caller, vec![start_event(vec![])], vec![])?;
+        let next_elem: Vec<u128> = (2..2 + n as u128).collect();
+        let post_condition = element(1, ElementType::Task(TaskKind::User), next_elem.clone())
+            .post_condition;
+    }: _(
+        RawOrigin::Signed(caller),
+        process_id,
+        1,
+        BitSet::with_bit(b as u128 * 8),
+        post_condition,
+        ElementType::Task(TaskKind::User),
+        [0; 32],
+        next_elem
+    )
+    verify {
+        assert!(Module::<T>::iflow_by_id(process_id).get_element_info(1).is_ok());
+    }
+
+    link_sub_process {
+        let a in 0 .. MAX_ATTACHED_EVENTS;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        // Longest chain of sub-processes the cycle check goes through
+        let child_flow = nested_sub_processes::<T>(&caller, MAX_SUB_PROCESSES - 1)?
+            .ok_or("no sub-process deployed")?;
+        let boundary = ElementType::Event {
+            position: EventPosition::Boundary,
+            kind: EventKind::Message,
+            interrupting: false,
+        };
+        let attached_events: Vec<u128> = (3..3 + a as u128).collect();
+        let mut elements = vec![start_event(vec![1]), sub_process(1, vec![2]), task(2, vec![])];
+        elements.extend(attached_events.iter().map(|event| element(*event, boundary, vec![])));
+        let process_id = deploy::<T>(&caller, elements, vec![])?;
+    }: _(RawOrigin::Signed(caller), process_id, 1, child_flow, attached_events, 1)
+    verify {
+        let iflow = Module::<T>::iflow_by_id(process_id);
+        assert_eq!(iflow.get_sub_process_instance(1).ok(), Some(child_flow));
+    }
+
+    set_factory_instance {
+        let s in 0 .. MAX_SELECTOR_BYTES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = deploy::<T>(&caller, vec![start_event(vec![])], vec![])?;
+        let instantiate_selector = vec![0; s as usize / 2];
+        let execute_script_selector = vec![0; s as usize - s as usize / 2];
+    }: _(
+        RawOrigin::Signed(caller),
+        process_id,
+        Default::default(),
+        instantiate_selector,
+        execute_script_selector
+    )
+    verify {
+        let iflow = Module::<T>::iflow_by_id(process_id);
+        assert_eq!(iflow.get_factory_instance().get_address(), &None);
+    }
+
+    continue_execution {
+        let e in 0 .. MAX_EXECUTED_ELEMENTS;
+        let q in 0 .. MAX_BRANCHES;
+        let d in 0 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = execution_model::<T>(&caller, e, q, d)?;
+        let mut idata = Idata::default();
+        idata.set_parent(None, process_id, 0);
+        idata.set_marking(BitSet::with_bit(1));
+        let instance_id = store_instance::<T>(idata)?;
+    }: _(RawOrigin::Signed(caller), instance_id, 1)
+    verify {
+        let idata = Module::<T>::idata_by_id(instance_id);
+        assert!(!idata.get_marking().contains(1));
+    }
+
+    create_root_instance {
+        let e in 0 .. MAX_EXECUTED_ELEMENTS;
+        let q in 0 .. MAX_BRANCHES;
+        let d in 0 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        <T as Trait>::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+        let process_id = execution_model::<T>(&caller, e, q, d)?;
+        // Root instance created first, before those of its sub-processes
+        let instance_id = Module::<T>::next_instance_id()?;
+    }: _(RawOrigin::Signed(caller), process_id)
+    verify {
+        assert_eq!(Module::<T>::idata_by_id(instance_id).get_flow_node(), process_id);
+        assert!(<InstanceDeposits<T>>::contains_key(instance_id));
+    }
+
+    deploy_process {
+        let l in 1 .. MAX_ELEMENTS;
+        let k in 0 .. MAX_SUB_PROCESSES;
+        let b in 0 .. MAX_CONDITION_BYTES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let child_flow = deploy::<T>(&caller, vec![start_event(vec![])], vec![])?;
+        // Start event followed by a chain of `k` sub-processes and then tasks
+        let mut elements: Vec<_> = (0..l as u128)
+            .map(|element_index| {
+                let next_elem = Some(element_index + 1).filter(|next| *next < l as u128);
+                let next_elem = next_elem.into_iter().collect();
+                match element_index {
+                    0 => start_event(next_elem),
+                    element_index if element_index <= k as u128 => {
+                        sub_process(element_index, next_elem)
+                    }
+                    element_index => task(element_index, next_elem),
+                }
+            })
+            .collect();
+        // Token on a sequence flow no element takes, only making the start event larger
+        elements[0].post_condition |= &BitSet::with_bit(b as u128 * 8);
+        let links = (1..l.min(k + 1) as u128)
+            .map(|parent_index| link::<T>(parent_index, child_flow))
+            .collect();
+    }: _(RawOrigin::Signed(caller), elements, links, Default::default(), vec![], vec![])
+    verify {
+        assert!(<IflowById<T>>::contains_key(Module::<T>::process_id_count()));
+    }
+
+    transfer_ownership {
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let new_owner: T::AccountId = account("new_owner", 0, SEED);
+        let process_id = deploy::<T>(&caller, vec![start_event(vec![])], vec![])?;
+    }: _(RawOrigin::Signed(caller), process_id, new_owner.clone())
+    verify {
+        assert_eq!(Module::<T>::process_owner(process_id), Some(new_owner));
+    }
+
+    set_participants {
+        let p in 0 .. MAX_PARTICIPANTS;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = deploy::<T>(&caller, vec![start_event(vec![])], vec![])?;
+        let mut idata = Idata::default();
+        idata.set_parent(None, process_id, 0);
+        let instance_id = store_instance::<T>(idata)?;
+        let participants: Vec<T::AccountId> =
+            (0..p).map(|index| account("participant", index, SEED)).collect();
+    }: _(RawOrigin::Signed(caller), instance_id, participants)
+    verify {
+        assert_eq!(Module::<T>::participants(instance_id).len(), p as usize);
+    }
+
+    set_timer {
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = deploy::<T>(
+            &caller,
+            vec![timer_event(0, EventPosition::Start, vec![])],
+            vec![]
+        )?;
+        // Full bucket of the timer queue but for the timer of the start event
+        let due = T::Time::now() + One::one();
+        let bucket = due - due % TIMER_BUCKET_SPAN.into();
+        let queue: Vec<_> = (1..MAX_TIMERS_PER_BUCKET as u128)
+            .map(|element_index| ScheduledTimer {
+                due,
+                instance_id: process_id,
+                element_index,
+                fired: 0,
+                start_event: true,
+            })
+            .collect();
+        <TimerQueue<T>>::insert(bucket, queue);
+    }: _(RawOrigin::Signed(caller), process_id, 0, TimerDefinition::Duration(One::one()))
+    verify {
+        assert_eq!(Module::<T>::timer_queue(bucket).len(), MAX_TIMERS_PER_BUCKET as usize);
+    }
+
+    set_correlation_key {
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = deploy::<T>(&caller, vec![start_event(vec![])], vec![])?;
+        let mut idata = Idata::default();
+        idata.set_parent(None, process_id, 0);
+        let instance_id = store_instance::<T>(idata)?;
+        let previous_key = T::Hashing::hash_of(&0u32);
+        let correlation_key = T::Hashing::hash_of(&1u32);
+        Module::<T>::set_correlation_key(
+            RawOrigin::Signed(caller.clone()).into(),
+            instance_id,
+            previous_key
+        )?;
+        // Other instances correlated by the key, up to the limit
+        let correlated = vec![Default::default(); MAX_CORRELATED_INSTANCES as usize - 1];
+        <CorrelatedInstances<T>>::insert(correlation_key, correlated);
+    }: _(RawOrigin::Signed(caller), instance_id, correlation_key)
+    verify {
+        assert_eq!(Module::<T>::correlation_key(instance_id), Some(correlation_key));
+    }
+
+    send_message {
+        let p in 0 .. MAX_MESSAGE_PAYLOAD;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        // Instance waiting on a receive task before a task
+        let mut receive_task = element(1, ElementType::Task(TaskKind::Receive), vec![2]);
+        receive_task.event_code = [1; 32];
+        let process_id = deploy::<T>(
+            &caller,
+            vec![start_event(vec![1]), receive_task, task(2, vec![])],
+            vec![]
+        )?;
+        let mut idata = Idata::default();
+        idata.set_parent(None, process_id, 0);
+        idata.set_marking(BitSet::with_bit(1));
+        let instance_id = store_instance::<T>(idata)?;
+        Module::<T>::execute_elements(&mut ExecutionMeter::default(), instance_id, 1)?;
+        let correlation_key = T::Hashing::hash_of(&0u32);
+        Module::<T>::set_correlation_key(
+            RawOrigin::Signed(caller.clone()).into(),
+            instance_id,
+            correlation_key
+        )?;
+    }: _(RawOrigin::Signed(caller), correlation_key, [1; 32], vec![0; p as usize])
+    verify {
+        assert!(Module::<T>::message_subscriptions(instance_id, [1; 32]).is_empty());
+    }
+
+    on_initialize {
+        let t in 0 .. MAX_TIMERS_PER_BLOCK;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        // Instances waiting on an intermediate timer, due right away, before a task
+        for _ in 0..t {
+            let process_id = deploy::<T>(
+                &caller,
+                vec![
+                    start_event(vec![1]),
+                    timer_event(1, EventPosition::IntermediateCatch, vec![2]),
+                    task(2, vec![]),
+                ],
+                vec![]
+            )?;
+            Module::<T>::set_timer(
+                RawOrigin::Signed(caller.clone()).into(),
+                process_id,
+                1,
+                TimerDefinition::Duration(Default::default())
+            )?;
+            let mut idata = Idata::default();
+            idata.set_parent(None, process_id, 0);
+            idata.set_marking(BitSet::with_bit(1));
+            let instance_id = store_instance::<T>(idata)?;
+            Module::<T>::execute_elements(&mut ExecutionMeter::default(), instance_id, 1)?;
+        }
+    }: {
+        Module::<T>::on_initialize(1u32.into());
+    }
+    verify {
+        assert!(<TimerQueue<T>>::iter().next().is_none());
+    }
+
+    poke_instance {
+        let e in 0 .. MAX_EXECUTED_ELEMENTS;
+        let q in 0 .. MAX_BRANCHES;
+        let d in 0 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let process_id = execution_model::<T>(&caller, e, q, d)?;
+        let mut idata = Idata::default();
+        idata.set_parent(None, process_id, 0);
+        idata.set_marking(BitSet::with_bit(1));
+        let instance_id = store_instance::<T>(idata)?;
+        // Most instances pending, the poked one last
+        let mut instances = vec![Default::default(); MAX_PENDING_INSTANCES as usize - 1];
+        instances.push(instance_id);
+        <PendingInstances<T>>::put(instances);
+        <PendingElements<T>>::insert(instance_id, vec![1]);
+    }: _(RawOrigin::Signed(caller), instance_id)
+    verify {
+        assert!(!<PendingElements<T>>::contains_key(instance_id));
+    }
+
+    resume_pending_instances {
+        let r in 0 .. MAX_RESUMED_PER_BLOCK;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        for _ in 0..r {
+            let process_id = execution_model::<T>(&caller, 1, 0, 0)?;
+            let mut idata = Idata::default();
+            idata.set_parent(None, process_id, 0);
+            idata.set_marking(BitSet::with_bit(1));
+            let instance_id = store_instance::<T>(idata)?;
+            Module::<T>::keep_pending(instance_id, vec![1]);
+        }
+    }: {
+        Module::<T>::on_initialize(1u32.into());
+    }
+    verify {
+        assert!(Module::<T>::pending_instances().is_empty());
+    }
+
+    suspend_instance {
+        let d in 1 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let tree = instance_tree::<T>(&caller, d)?;
+        // Most instances pending, those of the tree last
+        let mut instances = vec![Default::default(); MAX_PENDING_INSTANCES as usize - tree.len()];
+        instances.extend(tree.iter().copied());
+        <PendingInstances<T>>::put(instances);
+    }: _(RawOrigin::Signed(caller), tree[0])
+    verify {
+        assert_eq!(Module::<T>::idata_by_id(tree[0]).get_status(), InstanceStatus::Suspended);
+    }
+
+    resume_instance {
+        let d in 1 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let tree = instance_tree::<T>(&caller, d)?;
+        // Each instance of the tree pending once resumed, after most instances pending
+        let instances = vec![Default::default(); MAX_PENDING_INSTANCES as usize - tree.len()];
+        <PendingInstances<T>>::put(instances);
+        for &instance_id in &tree {
+            <PendingElements<T>>::insert(instance_id, vec![1]);
+        }
+        Module::<T>::suspend_instance(RawOrigin::Signed(caller.clone()).into(), tree[0])?;
+    }: _(RawOrigin::Signed(caller), tree[0])
+    verify {
+        assert_eq!(Module::<T>::pending_instances().len(), MAX_PENDING_INSTANCES as usize);
+    }
+
+    cancel_instance {
+        let d in 1 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let tree = instance_tree::<T>(&caller, d)?;
+    }: _(RawOrigin::Signed(caller), tree[0])
+    verify {
+        assert_eq!(Module::<T>::idata_by_id(tree[0]).get_status(), InstanceStatus::Terminated);
+    }
+
+    prune_instance {
+        let d in 1 .. MAX_SUB_PROCESSES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let tree = ended_instance_tree::<T>(&caller, d)?;
+        // Most root instances waiting to be pruned, the pruned one last
+        let mut instances = vec![Default::default(); MAX_ENDED_INSTANCES as usize - 1];
+        instances.push((Zero::zero(), tree[0]));
+        <EndedInstances<T>>::put(instances);
+    }: _(RawOrigin::Signed(caller), tree[0])
+    verify {
+        assert!(!<IdataById<T>>::contains_key(tree[0]));
+    }
+
+    prune_ended_instances {
+        let r in 0 .. MAX_PRUNED_PER_BLOCK;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let mut instances = vec![];
+        for _ in 0..r {
+            let tree = ended_instance_tree::<T>(&caller, 1)?;
+            instances.push((Zero::zero(), tree[0]));
+        }
+        <EndedInstances<T>>::put(instances);
+    }: {
+        Module::<T>::on_initialize(T::InstanceRetention::get());
+    }
+    verify {
+        assert!(Module::<T>::ended_instances().is_empty());
+    }
+
+    set_multi_instance {
+        let s in 0 .. MAX_SELECTOR_BYTES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let multi_instance_sub_process = ElementType::SubProcess {
+            kind: SubProcessKind::Embedded,
+            multi_instance: MultiInstance::Parallel,
+        };
+        let elements = vec![
+            start_event(vec![1]),
+            element(1, multi_instance_sub_process, vec![2]),
+            task(2, vec![]),
+        ];
+        let process_id = deploy::<T>(&caller, elements, vec![])?;
+        let multi_instance = MultiInstanceDefinition {
+            cardinality_selector: vec![0; s as usize / 2],
+            completion_selector: vec![0; s as usize - s as usize / 2],
+        };
+    }: _(RawOrigin::Signed(caller), process_id, 1, multi_instance.clone())
+    verify {
+        let iflow = Module::<T>::iflow_by_id(process_id);
+        assert_eq!(iflow.get_multi_instance(1), Some(&multi_instance));
+    }
+
+    set_loop {
+        let s in 0 .. MAX_SELECTOR_BYTES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let loop_sub_process = ElementType::SubProcess {
+            kind: SubProcessKind::Embedded,
+            multi_instance: MultiInstance::StandardLoop { test_before: true },
+        };
+        let elements = vec![
+            start_event(vec![1]),
+            element(1, loop_sub_process, vec![2]),
+            task(2, vec![]),
+        ];
+        let process_id = deploy::<T>(&caller, elements, vec![])?;
+        let standard_loop = LoopDefinition {
+            condition_selector: vec![0; s as usize],
+            maximum: Some(MAX_LOOP_ITERATIONS),
+        };
+    }: _(RawOrigin::Signed(caller), process_id, 1, standard_loop.clone())
+    verify {
+        let iflow = Module::<T>::iflow_by_id(process_id);
+        assert_eq!(iflow.get_loop(1), Some(&standard_loop));
+    }
+
+    set_compensation {
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let compensation = ElementType::Event {
+            position: EventPosition::Boundary,
+            kind: EventKind::Compensation,
+            interrupting: false,
+        };
+        let elements = vec![
+            start_event(vec![1]),
+            task(1, vec![]),
+            element(2, compensation, vec![3]),
+            task(3, vec![]),
+        ];
+        let process_id = deploy::<T>(&caller, elements, vec![])?;
+    }: _(RawOrigin::Signed(caller), process_id, 1, 2)
+    verify {
+        let iflow = Module::<T>::iflow_by_id(process_id);
+        assert_eq!(iflow.get_compensation(1), Some(2));
+    }
+
+    set_event_payload {
+        let s in 0 .. MAX_SELECTOR_BYTES;
+        let caller: T::AccountId = account("caller", 0, SEED);
+        let error_end = ElementType::Event {
+            position: EventPosition::End,
+            kind: EventKind::Error,
+            interrupting: false,
+        };
+        let elements = vec![start_event(vec![1]), element(1, error_end, vec![])];
+        let process_id = deploy::<T>(&caller, elements, vec![])?;
+        let payload_selector = vec![0; s as usize];
+    }: _(RawOrigin::Signed(caller), process_id, 1, payload_selector.clone())
+    verify {
+        let iflow = Module::<T>::iflow_by_id(process_id);
+        assert_eq!(iflow.get_event_payload(1), Some(&payload_selector[..]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{new_test_ext, Test};
+    use frame_support::assert_ok;
+
+    #[test]
+    fn benchmarks_run_within_their_limits() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(test_benchmark_set_element::<Test>());
+            assert_ok!(test_benchmark_link_sub_process::<Test>());
+            assert_ok!(test_benchmark_set_factory_instance::<Test>());
+            assert_ok!(test_benchmark_continue_execution::<Test>());
+            assert_ok!(test_benchmark_create_root_instance::<Test>());
+            assert_ok!(test_benchmark_deploy_process::<Test>());
+            assert_ok!(test_benchmark_transfer_ownership::<Test>());
+            assert_ok!(test_benchmark_set_participants::<Test>());
+            assert_ok!(test_benchmark_set_timer::<Test>());
+            assert_ok!(test_benchmark_on_initialize::<Test>());
+            assert_ok!(test_benchmark_set_correlation_key::<Test>());
+            assert_ok!(test_benchmark_send_message::<Test>());
+            assert_ok!(test_benchmark_poke_instance::<Test>());
+            assert_ok!(test_benchmark_resume_pending_instances::<Test>());
+            assert_ok!(test_benchmark_suspend_instance::<Test>());
+            assert_ok!(test_benchmark_resume_instance::<Test>());
+            assert_ok!(test_benchmark_cancel_instance::<Test>());
+            assert_ok!(test_benchmark_prune_instance::<Test>());
+            assert_ok!(test_benchmark_prune_ended_instances::<Test>());
+            assert_ok!(test_benchmark_set_multi_instance::<Test>());
+            assert_ok!(test_benchmark_set_loop::<Test>());
+            assert_ok!(test_benchmark_set_compensation::<Test>());
+            assert_ok!(test_benchmark_set_event_payload::<Test>());
+        });
+    }
+}
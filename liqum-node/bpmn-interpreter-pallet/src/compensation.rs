@@ -0,0 +1,47 @@
+//! Compensation of the activities an instance completed, by the handlers their compensation
+//! boundary events lead to, see `set_compensation`.
+
+use crate::{ExecutionMeter, IdataById, Iflow, Module, RawEvent, Trait};
+use frame_support::{dispatch::DispatchResult, StorageMap};
+
+impl<T: Trait> Module<T> {
+    /// Records the activity completed, to be compensated if it has a compensation handler.
+    pub(crate) fn record_compensable(
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) {
+        if iflow.get_compensation(element_index).is_some() {
+            <IdataById<T>>::mutate(instance_id, |idata| idata.add_compensable(element_index));
+        }
+    }
+
+    /// Runs the compensation handlers of the activities the instance completed, the most
+    /// recently completed first. Each activity is compensated once.
+    pub(crate) fn compensate(
+        meter: &mut ExecutionMeter,
+        instance_id: T::InstanceId,
+    ) -> DispatchResult {
+        let idata = Self::ensure_idata_instance_exists(instance_id)?;
+        let iflow = Self::ensure_iflow_instance_exists(idata.get_flow_node())?;
+        while let Some(activity) =
+            <IdataById<T>>::mutate(instance_id, |idata| idata.take_compensable())
+        {
+            let boundary_event = match iflow.get_compensation(activity) {
+                Some(boundary_event) => boundary_event,
+                None => continue,
+            };
+            // The boundary event catches the compensation, its token enabling the handler
+            let post_condition = iflow.get_post_condition(boundary_event);
+            let handler = iflow.get_first_ady_element(boundary_event)?;
+            Self::complete_element(instance_id, boundary_event, &post_condition);
+            <IdataById<T>>::mutate(instance_id, |idata| {
+                let marking = idata.get_marking() | &post_condition;
+                idata.set_marking(marking)
+            });
+            Self::execute_elements(meter, instance_id, handler)?;
+            Self::deposit_event(RawEvent::ActivityCompensated(instance_id, activity));
+        }
+        Ok(())
+    }
+}
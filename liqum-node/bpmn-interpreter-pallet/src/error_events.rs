@@ -0,0 +1,70 @@
+//! Error and escalation events, caught by the events of their kind with the same code or with
+//! none, along with the payload the data & scripts contract gives them, see
+//! `set_event_payload`.
+
+use crate::{types::*, Iflow, Module, ScriptExecutor, Trait};
+use frame_support::dispatch::{DispatchError, DispatchResult};
+use sp_std::prelude::*;
+
+/// Event code of the error and escalation events catching any code.
+pub const CATCH_ALL: [u8; 32] = [0; 32];
+
+impl<T: Trait> Module<T> {
+    /// Catch events of the process model matching the error or escalation thrown, those with
+    /// its code first and then those catching any code, each group in the order of the event
+    /// list.
+    pub(crate) fn catching_events(
+        iflow: &Iflow<T>,
+        event_code: [u8; 32],
+        event_info: u128,
+    ) -> Result<Vec<u128>, DispatchError> {
+        let kind = event_info & (ERROR | ESCALATION);
+        let mut catching = Vec::new();
+        let mut catching_any = Vec::new();
+        for &event in iflow.get_event_list() {
+            let catch_event_info = iflow.get_type_info(event);
+            if has(catch_event_info, THROW) || !has_any(catch_event_info, kind) {
+                continue;
+            }
+            let catch_code = iflow.get_event_code(event)?;
+            if catch_code == event_code {
+                catching.push(event);
+            } else if catch_code == CATCH_ALL {
+                catching_any.push(event);
+            }
+        }
+        catching.extend(catching_any);
+        Ok(catching)
+    }
+
+    /// Payload of the error or escalation the event throws, empty unless the data & scripts
+    /// contract gives one.
+    pub(crate) fn thrown_payload(
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) -> Result<Vec<u8>, DispatchError> {
+        match iflow.get_event_payload(element_index) {
+            Some(selector) if !selector.is_empty() => {
+                T::ScriptExecutor::thrown_payload(instance_id, element_index)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Writes the payload the event caught into the data & scripts contract, for the handler
+    /// the event leads to.
+    pub(crate) fn write_caught_payload(
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+        payload: &[u8],
+    ) -> DispatchResult {
+        match iflow.get_event_payload(element_index) {
+            Some(selector) if !selector.is_empty() => {
+                T::ScriptExecutor::write_caught_payload(instance_id, element_index, payload)
+            }
+            _ => Ok(()),
+        }
+    }
+}
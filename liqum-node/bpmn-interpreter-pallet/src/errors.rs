@@ -1,6 +1,97 @@
-pub const INSTANCE_ID_NOT_FOUND: &str =
-    "Instance, associated with given instance id does not found";
-pub const SUBPROCESS_TO_LINK_NOT_FOUND: &str =
-    "Subprocess to link does not found in the data structure";
-pub const INSTANTIATION_ERROR: &str = "Error occured in the middle of contract instantiation";
-pub const DECODING_ERROR: &str = "Execute script result decoding error occured";
+use crate::{Module, Trait};
+use frame_support::decl_error;
+
+decl_error! {
+    /// Errors of the bpmn interpreter pallet.
+    pub enum Error for Module<T: Trait> {
+        /// No process model is stored under the given instance id
+        IflowNotFound,
+        /// No process instance is stored under the given instance id
+        IdataNotFound,
+        /// Element index is not defined in the process model
+        ElementNotFound,
+        /// Element index defined more than once in the process model
+        DuplicateElement,
+        /// Process model does not define a start event
+        StartEventNotFound,
+        /// Subprocess to link is not a sub-process or call activity of the process model
+        SubProcessToLinkNotFound,
+        /// Attached element is not an event of the process model
+        InvalidAttachedEvent,
+        /// Element type flags are not a valid combination
+        InvalidElementType,
+        /// Element is already defined with another type
+        ElementTypeMismatch,
+        /// Element or sequence flow index exceeds the supported maximum
+        IndexOutOfBounds,
+        /// No id left to allocate to a process model or instance
+        InstanceIdOverflow,
+        /// Id already used by a process model or instance
+        InstanceIdTaken,
+        /// The root process instance has no parent to instantiate a sub-process from
+        RootCaseHasNoParent,
+        /// Error occured in the middle of contract instantiation
+        InstantiationFailed,
+        /// Call of the data & scripts contract failed
+        ScriptCallFailed,
+        /// Output of the data & scripts contract could not be decoded
+        DecodingFailed,
+        /// Caller is not allowed to perform the operation
+        Unauthorized,
+        /// Element has no outgoing sequence flow to continue the execution with
+        NextElementNotFound,
+        /// Event is not attached to a sub-process of the process model
+        EventNotAttached,
+        /// Sub-process or call activity is not linked to a process model
+        SubProcessNotLinked,
+        /// Sub-process would (indirectly) instantiate its own parent process
+        CyclicSubProcess,
+        /// No instance of the sub-process was created by the process instance
+        ChildInstanceNotFound,
+        /// More sub-process instances completed than were started
+        InstanceCountUnderflow,
+        /// Call would do more work than the weight charged for it covers
+        WeightLimitExceeded,
+        /// More participants than `MAX_PARTICIPANTS` assigned to a process instance
+        TooManyParticipants,
+        /// Timer set on an element that is not a timer event, or never due
+        InvalidTimer,
+        /// Timer event reached before its timer was set
+        TimerNotSet,
+        /// More timers than `MAX_TIMERS_PER_BUCKET` due in the same bucket of the timer queue
+        TooManyTimers,
+        /// More instances than `MAX_CORRELATED_INSTANCES` correlated by the same key
+        TooManyCorrelatedInstances,
+        /// Message payload longer than `MAX_MESSAGE_PAYLOAD`
+        MessagePayloadTooLong,
+        /// No instance correlated by the key waits for the message, or lets the caller send it
+        MessageNotCorrelated,
+        /// Instance has no elements left pending to resume its execution with
+        NoPendingElements,
+        /// Instance is not running, it was suspended or ended
+        InstanceNotRunning,
+        /// Instance is not suspended
+        InstanceNotSuspended,
+        /// Instance is a sub-process instance, managed along with its root instance
+        NotRootInstance,
+        /// Caller cannot reserve the deposit for a root instance
+        InsufficientDeposit,
+        /// Instance is still running or suspended
+        InstanceNotEnded,
+        /// Element is not a multi-instance sub-process
+        InvalidMultiInstance,
+        /// Element is not a standard loop activity, or its maximum is zero or above
+        /// `MAX_LOOP_ITERATIONS`
+        InvalidLoop,
+        /// Event-based gateway not followed only by intermediate message, signal or timer
+        /// catch events
+        InvalidEventBasedGateway,
+        /// Element is not an activity, or the event not a compensation boundary event leading
+        /// to an activity
+        InvalidCompensation,
+        /// Element is not an error or escalation event
+        InvalidEventPayload,
+        /// More instances than `MAX_SUB_PROCESSES` of a multi-instance sub-process
+        TooManyInstances,
+    }
+}
@@ -0,0 +1,79 @@
+//! Event-based gateways, whose token is taken by the first of the intermediate catch events
+//! they lead to, the others no longer waiting.
+
+use crate::{
+    types::*, BitSet, ExecutionMeter, Idata, IdataById, Iflow, MessageSubscriptions, Module,
+    RawEvent, Trait,
+};
+use frame_support::{dispatch::DispatchResult, StorageDoubleMap, StorageMap};
+
+impl<T: Trait> Module<T> {
+    /// Makes the instance wait on each event the gateway leads to, the gateway included in the
+    /// started activities until one of them is caught.
+    pub(crate) fn arm_event_based_gateway(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        iflow: &Iflow<T>,
+        gateway_index: u128,
+        started_activities: &mut BitSet,
+    ) -> DispatchResult {
+        for &event in iflow.get_ady_elements(gateway_index)? {
+            let event_info = iflow.get_type_info(event);
+            if has(event_info, TIMER) {
+                Self::arm_timer(meter, parent_case, iflow, event)?;
+            } else if has(event_info, MESSAGE) {
+                Self::subscribe_message(meter, parent_case, iflow, event)?;
+            } else {
+                // Signals are caught when broadcast, see `broadcast_signal`
+                meter.execute();
+            }
+            Self::deposit_event(RawEvent::ElementStarted(parent_case, event));
+            started_activities.insert(event);
+        }
+        started_activities.insert(gateway_index);
+        Ok(())
+    }
+
+    /// Completes the event-based gateway the event caught follows, if any, disarming the
+    /// other events it leads to.
+    pub(crate) fn resolve_event_based_gateway(
+        parent_case: T::InstanceId,
+        idata: &Idata<T>,
+        iflow: &Iflow<T>,
+        event: u128,
+    ) -> DispatchResult {
+        let started_activities = idata.get_started_activities();
+        let gateway_index = match started_activities.iter().find(|&element_index| {
+            has(iflow.get_type_info(element_index), GATEWAY | EVENT_BASED)
+                && iflow
+                    .get_ady_elements(element_index)
+                    .map_or(false, |next| next.contains(&event))
+        }) {
+            Some(gateway_index) => gateway_index,
+            None => return Ok(()),
+        };
+
+        let siblings = iflow.get_ady_elements(gateway_index)?;
+        for &sibling in siblings.iter().filter(|&&sibling| sibling != event) {
+            let sibling_info = iflow.get_type_info(sibling);
+            if has(sibling_info, TIMER) {
+                Self::disarm_timer(parent_case, sibling);
+            } else if has(sibling_info, MESSAGE) {
+                let event_code = iflow.get_event_code(sibling)?;
+                <MessageSubscriptions<T>>::mutate(parent_case, event_code, |elements| {
+                    elements.retain(|waiting| *waiting != sibling)
+                });
+            }
+        }
+        Self::complete_element(parent_case, gateway_index, &iflow.get_pre_condition(event));
+        <IdataById<T>>::mutate(parent_case, |idata| {
+            let mut started_activities = idata.get_started_activities().clone();
+            for &sibling in siblings.iter().filter(|&&sibling| sibling != event) {
+                started_activities.remove(sibling);
+            }
+            started_activities.remove(gateway_index);
+            idata.set_activity_marking(started_activities);
+        });
+        Ok(())
+    }
+}
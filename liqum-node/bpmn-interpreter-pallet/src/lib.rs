@@ -2,25 +2,105 @@
 
 use codec::{Codec, Decode, Encode};
 use frame_support::{
-    decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure, Parameter,
+    decl_event, decl_module, decl_storage,
+    dispatch::{DispatchError, DispatchResult, DispatchResultWithPostInfo},
+    ensure,
+    traits::{Currency, EnsureOrigin, Get, ReservableCurrency, Time},
+    transactional,
+    weights::Weight,
+    Parameter,
 };
 use frame_system::{self as system, ensure_signed, RawOrigin};
-use sp_runtime::traits::{CheckedAdd, MaybeSerialize, Member};
-use sp_std::{collections::btree_map::BTreeMap, prelude::*};
+use sp_runtime::{
+    traits::{CheckedAdd, MaybeSerialize, Member, One, Zero},
+    RuntimeDebug,
+};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet, vec_deque::VecDeque},
+    iter,
+    prelude::*,
+};
 
+mod api;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+mod compensation;
+mod error_events;
 mod errors;
+mod event_based_gateway;
+mod lifecycle;
+mod loops;
+mod messages;
+mod migration;
+#[cfg(test)]
+mod mock;
+mod multi_instance;
+mod or_join;
+mod pending;
+mod permissions;
+mod pruning;
+#[cfg(test)]
+mod scenarios;
+mod scripts;
+#[cfg(test)]
+mod tests;
+mod timers;
+pub mod types;
+pub mod weights;
+pub use bitset::{BitSet, MAX_BIT_INDEX};
+pub use bpmn_interpreter_rpc_runtime_api::InstanceStatus;
 use contracts::{CodeHash, ContractAddressFor};
-use errors::*;
+pub use errors::Error;
+pub use permissions::{ExecutionPermission, OwnerParticipantOrContract};
+pub use scripts::{ContractScripts, ScriptExecutor};
+pub use timers::ScheduledTimer;
+use types::*;
 
 const ENDOWMENT: u32 = 1000;
 const GAS: u32 = 500_000;
+/// Most sub-process instances a call of the interpreter can create or traverse, and most
+/// process models reachable from a linked sub-process
+pub const MAX_SUB_PROCESSES: u32 = 25;
+/// Most participants assigned to a process instance
+pub const MAX_PARTICIPANTS: u32 = 100;
+/// Moments, milliseconds with the timestamp pallet, spanned by each bucket of the timer queue
+pub const TIMER_BUCKET_SPAN: u32 = 6_000;
+/// Most timers due in the same bucket of the timer queue
+pub const MAX_TIMERS_PER_BUCKET: u32 = 1_000;
+/// Most buckets of the timer queue `on_initialize` moves past in a block
+pub const MAX_TIMER_BUCKETS_PER_BLOCK: u32 = 100;
+/// Most timers fired by `on_initialize` in a block, the others wait for the next blocks
+pub const MAX_TIMERS_PER_BLOCK: u32 = 10;
+/// Most instances with pending elements resumed by `on_initialize`, the others can be poked
+pub const MAX_PENDING_INSTANCES: u32 = 1_000;
+/// Most instances resumed by `on_initialize` in a block, the others wait for the next blocks
+pub const MAX_RESUMED_PER_BLOCK: u32 = 5;
+/// Most times `on_initialize` fails to resume an instance before leaving it for `poke_instance`
+pub const MAX_RESUME_ATTEMPTS: u32 = 3;
+/// Most process instances correlated by the same key
+pub const MAX_CORRELATED_INSTANCES: u32 = 10;
+/// Longest payload of a message sent with `send_message`
+pub const MAX_MESSAGE_PAYLOAD: u32 = 1_024;
+/// Most ended root instances waiting to be pruned by `on_initialize`, the others can be pruned
+/// with `prune_instance`
+pub const MAX_ENDED_INSTANCES: u32 = 1_000;
+/// Most ended root instances pruned by `on_initialize` in a block, the others wait for the next
+/// blocks
+pub const MAX_PRUNED_PER_BLOCK: u32 = 5;
+/// Most iterations of a standard loop activity, whatever its loop condition
+pub const MAX_LOOP_ITERATIONS: u32 = 100;
+
+/// Moments timers are due at, from the clock of the contracts pallet.
+pub type MomentOf<T> = <<T as contracts::Trait>::Time as Time>::Moment;
+pub type BalanceOf<T> =
+    <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, Clone, PartialEq)]
 pub struct Iflow<T: Trait> {
     start_evt: u128,
-    /// elemIndex => [preC, postC, type]
-    cond_table: BTreeMap<u128, [u128; 3]>,
+    /// elemIndex => (preC, postC, type)
+    cond_table: BTreeMap<u128, (BitSet, BitSet, u128)>,
     /// Element Index => List of elements that can be enabled with the completion of the key element
     next_elem: BTreeMap<u128, Vec<u128>>,
     /// List of Indexes of the subprocesses
@@ -34,6 +114,17 @@ pub struct Iflow<T: Trait> {
     parent_references: BTreeMap<u128, T::InstanceId>,
     instance_count: BTreeMap<u128, u128>,
     factory: Ifactory<T>,
+    /// Timer Event Index => When the timer is due
+    timers: BTreeMap<u128, TimerDefinition<MomentOf<T>>>,
+    /// Sub-process Index => Multi-instance characteristics decided at runtime
+    multi_instances: BTreeMap<u128, MultiInstanceDefinition>,
+    /// Sub-process Index => Loop condition decided at runtime
+    loops: BTreeMap<u128, LoopDefinition>,
+    /// Activity Index => Compensation boundary event, leading to the compensation handler
+    compensations: BTreeMap<u128, u128>,
+    /// Error or Escalation Event Index => Selector of the data & scripts contract giving the
+    /// payload thrown, or taking the payload caught
+    event_payloads: BTreeMap<u128, Vec<u8>>,
 }
 
 impl<T: Trait> Default for Iflow<T> {
@@ -49,72 +140,223 @@ impl<T: Trait> Default for Iflow<T> {
             parent_references: BTreeMap::new(),
             instance_count: BTreeMap::new(),
             factory: Ifactory::<T>::default(),
+            timers: BTreeMap::new(),
+            multi_instances: BTreeMap::new(),
+            loops: BTreeMap::new(),
+            compensations: BTreeMap::new(),
+            event_payloads: BTreeMap::new(),
         }
     }
 }
 
 impl<T: Trait> Iflow<T> {
-    fn get_pre_condition(&self, element_index: u128) -> u128 {
-        if let Some(cond_table) = self.cond_table.get(&element_index) {
-            cond_table[0]
+    fn get_pre_condition(&self, element_index: u128) -> BitSet {
+        if let Some((pre_condition, _, _)) = self.cond_table.get(&element_index) {
+            pre_condition.clone()
         } else {
-            0
+            BitSet::new()
         }
     }
 
-    fn get_post_condition(&self, element_index: u128) -> u128 {
-        if let Some(cond_table) = self.cond_table.get(&element_index) {
-            cond_table[1]
+    fn get_post_condition(&self, element_index: u128) -> BitSet {
+        if let Some((_, post_condition, _)) = self.cond_table.get(&element_index) {
+            post_condition.clone()
         } else {
-            0
+            BitSet::new()
         }
     }
 
     fn get_type_info(&self, element_index: u128) -> u128 {
-        if let Some(cond_table) = self.cond_table.get(&element_index) {
-            cond_table[2]
+        if let Some((_, _, type_info)) = self.cond_table.get(&element_index) {
+            *type_info
         } else {
             0
         }
     }
 
-    fn get_element_info(&self, element_index: u128) -> ([u128; 3], &[u128]) {
-        (
-            self.cond_table[&element_index],
-            &self.next_elem[&element_index],
+    fn get_element_info(
+        &self,
+        element_index: u128,
+    ) -> Result<(&(BitSet, BitSet, u128), &[u128]), Error<T>> {
+        match (
+            self.cond_table.get(&element_index),
+            self.next_elem.get(&element_index),
+        ) {
+            (Some(element_info), Some(next_elem)) => Ok((element_info, next_elem)),
+            _ => Err(Error::<T>::ElementNotFound),
+        }
+    }
+
+    fn is_or_join_enabled(
+        &self,
+        element_index: u128,
+        marking: &BitSet,
+        started_activities: &BitSet,
+    ) -> bool {
+        or_join::is_enabled(
+            &self.cond_table,
+            &self.next_elem,
+            element_index,
+            marking,
+            started_activities,
+        )
+    }
+
+    fn get_enabled_or_joins(&self, marking: &BitSet, started_activities: &BitSet) -> Vec<u128> {
+        or_join::enabled_joins(
+            &self.cond_table,
+            &self.next_elem,
+            marking,
+            started_activities,
         )
     }
 
+    /// Whether the pre condition of the element is satisfied by the marking.
+    fn is_enabled(
+        &self,
+        element_index: u128,
+        marking: &BitSet,
+        started_activities: &BitSet,
+    ) -> bool {
+        let (pre_condition, _, type_info) = match self.cond_table.get(&element_index) {
+            Some(element_info) => element_info,
+            None => return false,
+        };
+        match *type_info {
+            // AND Join
+            type_info if has(type_info, AND_JOIN) => marking.is_superset(pre_condition),
+            // OR Join, enabled once no other token can still reach an empty incoming edge
+            type_info if has(type_info, OR_JOIN) => {
+                self.is_or_join_enabled(element_index, marking, started_activities)
+            }
+            // Activity || Intermediate/End Event || Gateway != AND/OR Join
+            type_info
+                if (has(type_info, ACTIVITY)
+                    || (has(type_info, EVENT) && has_any(type_info, INTERMEDIATE | END))
+                    || has(type_info, GATEWAY)) =>
+            {
+                marking.intersects(pre_condition)
+            }
+            _ => false,
+        }
+    }
+
+    fn get_enabled_elements(&self, marking: &BitSet, started_activities: &BitSet) -> Vec<u128> {
+        self.cond_table
+            .keys()
+            .filter(|element_index| self.is_enabled(**element_index, marking, started_activities))
+            .copied()
+            .collect()
+    }
+
     fn get_first_elem(&self) -> u128 {
         self.start_evt
     }
 
-    fn get_ady_elements(&self, element_index: u128) -> &[u128] {
-        &self.next_elem[&element_index]
+    fn get_ady_elements(&self, element_index: u128) -> Result<&[u128], Error<T>> {
+        self.next_elem
+            .get(&element_index)
+            .map(Vec::as_slice)
+            .ok_or(Error::<T>::ElementNotFound)
+    }
+
+    fn get_first_ady_element(&self, element_index: u128) -> Result<u128, Error<T>> {
+        self.get_ady_elements(element_index)?
+            .first()
+            .copied()
+            .ok_or(Error::<T>::NextElementNotFound)
+    }
+
+    fn get_attached_to(&self, element_index: u128) -> Result<u128, Error<T>> {
+        self.attached_to
+            .get(&element_index)
+            .copied()
+            .ok_or(Error::<T>::EventNotAttached)
+    }
+
+    /// Events attached to the activity, i.e. its boundary events.
+    fn get_attached_events(&self, element_index: u128) -> impl Iterator<Item = u128> + '_ {
+        self.attached_to
+            .iter()
+            .filter(move |(_, attached_to)| **attached_to == element_index)
+            .map(|(event, _)| *event)
     }
 
-    fn get_attached_to(&self, element_index: u128) -> u128 {
-        self.attached_to[&element_index]
+    fn get_sub_process_instance(&self, element_index: u128) -> Result<T::InstanceId, Error<T>> {
+        self.parent_references
+            .get(&element_index)
+            .copied()
+            .ok_or(Error::<T>::SubProcessNotLinked)
     }
 
-    fn get_sub_process_instance(&self, element_index: u128) -> T::InstanceId {
-        self.parent_references[&element_index]
+    fn get_sub_process_instances(&self) -> impl Iterator<Item = &T::InstanceId> {
+        self.parent_references.values()
     }
 
     fn get_sub_process_list(&self) -> &[u128] {
         &self.subprocesses
     }
 
-    fn get_event_code(&self, element_index: u128) -> [u8; 32] {
-        self.event_code[&element_index]
+    fn get_event_code(&self, element_index: u128) -> Result<[u8; 32], Error<T>> {
+        self.event_code
+            .get(&element_index)
+            .copied()
+            .ok_or(Error::<T>::ElementNotFound)
     }
 
     fn get_event_list(&self) -> &[u128] {
         &self.events
     }
 
-    fn get_instance_count(&self, element_index: u128) -> u128 {
-        self.instance_count[&element_index]
+    fn get_instance_count(&self, element_index: u128) -> Result<u128, Error<T>> {
+        self.instance_count
+            .get(&element_index)
+            .copied()
+            .ok_or(Error::<T>::SubProcessNotLinked)
+    }
+
+    fn get_timer(&self, element_index: u128) -> Result<TimerDefinition<MomentOf<T>>, Error<T>> {
+        self.timers
+            .get(&element_index)
+            .copied()
+            .ok_or(Error::<T>::TimerNotSet)
+    }
+
+    fn set_timer(&mut self, element_index: u128, timer: TimerDefinition<MomentOf<T>>) {
+        self.timers.insert(element_index, timer);
+    }
+
+    fn get_multi_instance(&self, element_index: u128) -> Option<&MultiInstanceDefinition> {
+        self.multi_instances.get(&element_index)
+    }
+
+    fn set_multi_instance(&mut self, element_index: u128, multi_instance: MultiInstanceDefinition) {
+        self.multi_instances.insert(element_index, multi_instance);
+    }
+
+    fn get_loop(&self, element_index: u128) -> Option<&LoopDefinition> {
+        self.loops.get(&element_index)
+    }
+
+    fn set_loop(&mut self, element_index: u128, standard_loop: LoopDefinition) {
+        self.loops.insert(element_index, standard_loop);
+    }
+
+    fn get_compensation(&self, element_index: u128) -> Option<u128> {
+        self.compensations.get(&element_index).copied()
+    }
+
+    fn set_compensation(&mut self, element_index: u128, boundary_event: u128) {
+        self.compensations.insert(element_index, boundary_event);
+        self.attached_to.insert(boundary_event, element_index);
+    }
+
+    fn get_event_payload(&self, element_index: u128) -> Option<&[u8]> {
+        self.event_payloads.get(&element_index).map(Vec::as_slice)
+    }
+
+    fn set_event_payload(&mut self, element_index: u128, payload_selector: Vec<u8>) {
+        self.event_payloads.insert(element_index, payload_selector);
     }
 
     fn get_factory_instance_mut(&mut self) -> &mut Ifactory<T> {
@@ -132,24 +374,27 @@ impl<T: Trait> Iflow<T> {
     fn set_element(
         &mut self,
         element_index: u128,
-        pre_condition: u128,
-        post_condition: u128,
+        pre_condition: BitSet,
+        post_condition: BitSet,
         type_info: u128,
         event_code: [u8; 32],
-        _next_elem: Vec<u128>,
+        next_elem: Vec<u128>,
     ) {
-        if type_info & 4 == 4 {
+        if has(type_info, EVENT) {
             self.events.push(element_index);
-            if type_info & 36 == 36 {
+            if has(type_info, EVENT | START) {
                 self.start_evt = element_index;
             }
             self.event_code.insert(element_index, event_code);
-        } else if type_info & 33 == 33 {
+        } else if has(type_info, ACTIVITY | SUB_PROCESS) {
             self.subprocesses.push(element_index);
+        } else if has(type_info, ACTIVITY | TASK | RECEIVE_TASK) {
+            // Receive tasks wait for the message named by their event code
+            self.event_code.insert(element_index, event_code);
         }
         self.cond_table
-            .insert(element_index, [pre_condition, post_condition, type_info]);
-        self.next_elem.insert(element_index, _next_elem);
+            .insert(element_index, (pre_condition, post_condition, type_info));
+        self.next_elem.insert(element_index, next_elem);
     }
 
     fn link_sub_process(
@@ -161,7 +406,7 @@ impl<T: Trait> Iflow<T> {
     ) {
         self.parent_references.insert(parent_index, child_flow_inst);
         for attached_event in attached_events.into_iter() {
-            if self.get_type_info(parent_index) & 4 == 4 {
+            if has(self.get_type_info(attached_event), EVENT) {
                 self.attached_to.insert(attached_event, parent_index);
             }
         }
@@ -172,35 +417,43 @@ impl<T: Trait> Iflow<T> {
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, Clone, PartialEq)]
 pub struct Idata<T: Trait> {
-    tokens_on_edges: u128,
-    started_activities: u128,
+    tokens_on_edges: BitSet,
+    started_activities: BitSet,
     idata_parent: Option<T::InstanceId>,
     iflow_node: T::InstanceId,
     index_in_parent: u128,
     children: BTreeMap<u128, Vec<T::InstanceId>>,
     instance_count: BTreeMap<u128, u128>,
+    status: InstanceStatus,
+    /// Index of the instance among those of its multi-instance activity, from 0
+    loop_counter: u128,
+    /// Activities completed that have a compensation handler, in completion order
+    compensable: Vec<u128>,
 }
 
 impl<T: Trait> Default for Idata<T> {
     fn default() -> Self {
         Self {
-            tokens_on_edges: 0,
-            started_activities: 0,
+            tokens_on_edges: BitSet::new(),
+            started_activities: BitSet::new(),
             idata_parent: None,
             iflow_node: T::InstanceId::default(),
             index_in_parent: 0,
             children: BTreeMap::default(),
             instance_count: BTreeMap::default(),
+            status: InstanceStatus::Running,
+            loop_counter: 0,
+            compensable: vec![],
         }
     }
 }
 
 impl<T: Trait> Idata<T> {
-    fn set_marking(&mut self, n_marking: u128) {
+    fn set_marking(&mut self, n_marking: BitSet) {
         self.tokens_on_edges = n_marking
     }
 
-    fn set_activity_marking(&mut self, n_marking: u128) {
+    fn set_activity_marking(&mut self, n_marking: BitSet) {
         self.started_activities = n_marking
     }
 
@@ -221,21 +474,6 @@ impl<T: Trait> Idata<T> {
         } else {
             self.children.insert(element_index, vec![child_id]);
         }
-        self.increment_instance_count(element_index)
-    }
-
-    fn increment_instance_count(&mut self, element_index: u128) {
-        if let Some(instance_count) = self.instance_count.get_mut(&element_index) {
-            *instance_count += 1;
-        } else {
-            self.instance_count.insert(element_index, 1);
-        }
-    }
-
-    fn decrement_instance_count(&mut self, element_index: u128) {
-        if let Some(instance_count) = self.instance_count.get_mut(&element_index) {
-            *instance_count -= 1;
-        }
     }
 
     fn set_instance_count(&mut self, element_index: u128, new_instance_count: u128) {
@@ -251,8 +489,14 @@ impl<T: Trait> Idata<T> {
         self.index_in_parent
     }
 
-    fn get_child_process_instances(&self, element_index: u128) -> &[T::InstanceId] {
-        &self.children[&element_index]
+    fn get_child_process_instances(
+        &self,
+        element_index: u128,
+    ) -> Result<&[T::InstanceId], Error<T>> {
+        self.children
+            .get(&element_index)
+            .map(Vec::as_slice)
+            .ok_or(Error::<T>::ChildInstanceNotFound)
     }
 
     fn get_flow_node(&self) -> T::InstanceId {
@@ -263,21 +507,46 @@ impl<T: Trait> Idata<T> {
         self.idata_parent
     }
 
-    fn get_started_activities(&self) -> u128 {
-        self.started_activities
+    fn get_started_activities(&self) -> &BitSet {
+        &self.started_activities
+    }
+
+    fn get_marking(&self) -> &BitSet {
+        &self.tokens_on_edges
+    }
+
+    fn get_instance_count(&self, element_index: u128) -> Result<u128, Error<T>> {
+        self.instance_count
+            .get(&element_index)
+            .copied()
+            .ok_or(Error::<T>::ChildInstanceNotFound)
+    }
+
+    pub fn get_status(&self) -> InstanceStatus {
+        self.status
     }
 
-    fn get_marking(&self) -> u128 {
-        self.tokens_on_edges
+    fn set_status(&mut self, status: InstanceStatus) {
+        self.status = status
     }
 
-    fn get_instance_count(&self, element_index: u128) -> u128 {
-        self.instance_count[&element_index]
+    fn get_loop_counter(&self) -> u128 {
+        self.loop_counter
     }
 
-    fn continue_execution(&self, element_index: u128) -> Result<(), &'static str> {
-        // Call bpmn interpreter execution on given index
-        Module::<T>::execute_elements(self.get_flow_node(), element_index)
+    fn set_loop_counter(&mut self, loop_counter: u128) {
+        self.loop_counter = loop_counter
+    }
+
+    fn add_compensable(&mut self, element_index: u128) {
+        if !self.compensable.contains(&element_index) {
+            self.compensable.push(element_index);
+        }
+    }
+
+    /// Most recently completed activity left to compensate.
+    fn take_compensable(&mut self) -> Option<u128> {
+        self.compensable.pop()
     }
 }
 
@@ -332,13 +601,13 @@ impl<T: Trait> Ifactory<T> {
         &self.execute_script_selector
     }
 
-    fn new_instance(&mut self, intance_id: T::InstanceId) -> Result<T::AccountId, &'static str> {
-        
+    /// Data & scripts contract of the instance. The contract is instantiated for the first
+    /// instance of the process model only, the next ones share it, and it is never terminated.
+    fn new_instance(&mut self, intance_id: T::InstanceId) -> Result<T::AccountId, DispatchError> {
         // Initialize new instance of data & scripts contract
         if let Some(address) = &self.address {
             Ok(address.clone())
         } else {
-
             let encoded_instance_id = u128::encode(&intance_id.into());
             let input_data = [self.get_instantiate_selector(), &encoded_instance_id[..]].concat();
 
@@ -358,7 +627,7 @@ impl<T: Trait> Ifactory<T> {
                     input_data
                 )
                 .is_ok(),
-                INSTANTIATION_ERROR
+                Error::<T>::InstantiationFailed
             );
 
             self.address = Some(contract_address.clone());
@@ -368,6 +637,78 @@ impl<T: Trait> Ifactory<T> {
     }
 }
 
+/// Element of a process model registered with `deploy_process`, see `set_element`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ElementDefinition {
+    pub element_index: u128,
+    pub pre_condition: BitSet,
+    pub post_condition: BitSet,
+    pub element_type: ElementType,
+    pub event_code: [u8; 32],
+    pub next_elem: Vec<u128>,
+}
+
+/// Sub-process or call activity of a process model registered with `deploy_process`,
+/// see `link_sub_process`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SubProcessLink<InstanceId> {
+    pub parent_index: u128,
+    /// Process model of the sub-process, deployed beforehand
+    pub child_flow: InstanceId,
+    /// Events attached to the sub-process, i.e. its boundary events
+    pub attached_events: Vec<u128>,
+    pub count_instances: u128,
+}
+
+/// Work done by one call of the interpreter, bounded by `Trait::MaxExecutionSteps` and
+/// `MAX_SUB_PROCESSES` so that it never exceeds the weight charged upfront. Elements left to
+/// queue once out of steps are kept pending for the next calls. Running out of instances
+/// elsewhere fails the call, whose dispatchables are transactional so that nothing it wrote
+/// before is kept.
+#[derive(Default)]
+pub struct ExecutionMeter {
+    /// Elements put in the execution queue
+    queued: u32,
+    /// Queued elements found enabled and executed
+    executed: u32,
+    /// Sub-process instances created, killed or reached by a signal
+    instances: u32,
+}
+
+impl ExecutionMeter {
+    /// Counts an element queued, unless the call is out of steps.
+    fn queue<T: Trait>(&mut self) -> bool {
+        if self.queued >= T::MaxExecutionSteps::get() {
+            return false;
+        }
+        self.queued += 1;
+        true
+    }
+
+    fn execute(&mut self) {
+        self.executed += 1;
+    }
+
+    /// Whether the call can still create, kill or reach sub-process instances.
+    fn has_instances_left(&self) -> bool {
+        self.instances < MAX_SUB_PROCESSES
+    }
+
+    /// Whether the call is out of steps or instances, and should not take on more work.
+    fn is_exhausted<T: Trait>(&self) -> bool {
+        self.queued >= T::MaxExecutionSteps::get() || !self.has_instances_left()
+    }
+
+    fn visit_instance<T: Trait>(&mut self) -> Result<(), Error<T>> {
+        ensure!(
+            self.instances < MAX_SUB_PROCESSES,
+            Error::<T>::WeightLimitExceeded
+        );
+        self.instances += 1;
+        Ok(())
+    }
+}
+
 /// The pallet's configuration trait.
 pub trait Trait: frame_system::Trait + contracts::Trait {
     /// The overarching event type.
@@ -379,12 +720,55 @@ pub trait Trait: frame_system::Trait + contracts::Trait {
     type InstanceId: Parameter
         + Member
         + CheckedAdd
+        + One
         + Codec
         + Default
         + Copy
         + Into<u128>
         + MaybeSerialize
         + PartialEq;
+
+    /// Origin allowed to mutate any process model and manage any of its instances, besides its
+    /// owner.
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+    /// Decides who may continue the execution of a process instance.
+    type ExecutionPermission: ExecutionPermission<Self::AccountId, Self::InstanceId>;
+
+    /// Runs the scripts of script tasks and of exclusive and inclusive split gateways, and the
+    /// other calls of the data & scripts contract, see `ContractScripts`.
+    type ScriptExecutor: ScriptExecutor<Self::InstanceId>;
+
+    /// Most elements a call of the interpreter can queue, bounding the weight charged for it.
+    type MaxExecutionSteps: Get<u32>;
+
+    /// Currency the deposits for the process instances are reserved in.
+    type Currency: ReservableCurrency<Self::AccountId>;
+
+    /// Deposit reserved from the account creating a root instance, refunded once the instance
+    /// and its sub-process instances are pruned. It does not cover the data & scripts contract,
+    /// shared by the instances of the process model.
+    type InstanceDeposit: Get<BalanceOf<Self>>;
+
+    /// Blocks an ended root instance is kept for before `on_initialize` prunes it.
+    type InstanceRetention: Get<Self::BlockNumber>;
+}
+
+/// Storage layout releases of the pallet, used to run the storage migrations.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    /// Layout of the original interpreter: markings, pre and post conditions encoded as `u128`
+    /// bitmasks, process models and instances stored under identifiers chosen by the caller
+    V1BitMask,
+    /// Markings, pre and post conditions encoded as `BitSet`, process models and instances
+    /// numbered by counters of their own
+    V2BitSet,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1BitMask
+    }
 }
 
 // This pallet's storage items.
@@ -394,7 +778,76 @@ decl_storage! {
 
         pub IdataById get(fn idata_by_id): map hasher(blake2_128_concat) T::InstanceId => Idata<T>;
 
+        /// Last identifier given to a process model.
+        ProcessIdCount get(fn process_id_count): T::InstanceId;
+
+        /// Last identifier given to a process instance.
         InstanceIdCount get(fn instance_id_count): T::InstanceId;
+
+        /// Account allowed to mutate the process model, the deployer unless transferred.
+        pub ProcessOwner get(fn process_owner):
+            map hasher(blake2_128_concat) T::InstanceId => Option<T::AccountId>;
+
+        /// Accounts assigned to the process instance, allowed to continue its execution.
+        pub Participants get(fn participants):
+            map hasher(blake2_128_concat) T::InstanceId => Vec<T::AccountId>;
+
+        /// Moment the timer event of the instance is due at.
+        pub ArmedTimers get(fn armed_timer):
+            double_map hasher(blake2_128_concat) T::InstanceId, hasher(blake2_128_concat) u128
+            => Option<MomentOf<T>>;
+
+        /// Moment the timer start event of the process model is due at.
+        pub ArmedStartTimers get(fn armed_start_timer):
+            double_map hasher(blake2_128_concat) T::InstanceId, hasher(blake2_128_concat) u128
+            => Option<MomentOf<T>>;
+
+        /// Armed timers by the bucket of `TIMER_BUCKET_SPAN` moments they are due in, fired by
+        /// `on_initialize`.
+        pub TimerQueue get(fn timer_queue):
+            map hasher(twox_64_concat) MomentOf<T>
+            => Vec<ScheduledTimer<T::InstanceId, MomentOf<T>>>;
+
+        /// First bucket of the timer queue that may hold timers, the ones before it being empty.
+        pub NextTimerBucket get(fn next_timer_bucket): Option<MomentOf<T>>;
+
+        /// Key correlating messages to the process instance.
+        pub CorrelationKey get(fn correlation_key):
+            map hasher(blake2_128_concat) T::InstanceId => Option<T::Hash>;
+
+        /// Process instances messages with the correlation key are delivered to.
+        pub CorrelatedInstances get(fn correlated_instances):
+            map hasher(blake2_128_concat) T::Hash => Vec<T::InstanceId>;
+
+        /// Receive tasks, intermediate and boundary events of the instance waiting for the
+        /// message with the event code.
+        pub MessageSubscriptions get(fn message_subscriptions):
+            double_map hasher(blake2_128_concat) T::InstanceId, hasher(blake2_128_concat) [u8; 32]
+            => Vec<u128>;
+
+        /// Elements queued for the instance once the call executing it was out of steps,
+        /// executed when it is resumed.
+        pub PendingElements get(fn pending_elements):
+            map hasher(blake2_128_concat) T::InstanceId => Vec<u128>;
+
+        /// Instances with pending elements, in the order `on_initialize` resumes them. Suspended
+        /// instances are left out until resumed.
+        pub PendingInstances get(fn pending_instances): Vec<T::InstanceId>;
+
+        /// Times in a row `on_initialize` failed to resume the pending instance.
+        pub ResumeAttempts get(fn resume_attempts):
+            map hasher(blake2_128_concat) T::InstanceId => u32;
+
+        /// Account that created the root instance and the deposit reserved from it.
+        pub InstanceDeposits get(fn instance_deposit):
+            map hasher(blake2_128_concat) T::InstanceId => Option<(T::AccountId, BalanceOf<T>)>;
+
+        /// Root instances ended and the block they ended at, in the order `on_initialize`
+        /// prunes them.
+        pub EndedInstances get(fn ended_instances): Vec<(T::BlockNumber, T::InstanceId)>;
+
+        /// Storage layout of the pallet, new chains start with the latest one.
+        StorageVersion build(|_: &GenesisConfig| Releases::V2BitSet): Releases;
     }
 }
 
@@ -402,58 +855,91 @@ decl_storage! {
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 
+        type Error = Error<T>;
+
+        /// Most elements a call of the interpreter can queue.
+        const MaxExecutionSteps: u32 = T::MaxExecutionSteps::get();
+
+        /// Deposit reserved for each root instance created.
+        const InstanceDeposit: BalanceOf<T> = T::InstanceDeposit::get();
+
+        /// Blocks ended root instances are kept for.
+        const InstanceRetention: T::BlockNumber = T::InstanceRetention::get();
+
         // Initializing events
         fn deposit_event() = default;
 
-        #[weight = 10_000]
+        fn on_runtime_upgrade() -> Weight {
+            migration::migrate::<T>()
+        }
+
+        /// Timers are compared with the timestamp of the previous block, which the current one
+        /// has not set yet, so they fire at most a block late. Instances with pending elements
+        /// are resumed afterwards, and the root instances ended long enough ago pruned.
+        fn on_initialize(n: T::BlockNumber) -> Weight {
+            // Timers and pending instances share the work of one call of the interpreter
+            let mut meter = ExecutionMeter::default();
+            let (fired, passed) = Self::fire_due_timers(&mut meter);
+            let resumed = Self::resume_pending_instances(&mut meter);
+            weights::on_initialize::<T>(
+                fired,
+                passed,
+                resumed,
+                meter.executed,
+                meter.queued,
+                meter.instances,
+            )
+            .saturating_add(Self::prune_ended_instances(n))
+        }
+
+        #[weight = weights::set_element::<T>(
+            next_elem.len() as u32,
+            weights::condition_bytes(pre_condition, post_condition)
+        )]
         pub fn set_element(
             origin,
             iflow_index: T::InstanceId,
             element_index: u128,
-            pre_condition: u128,
-            post_condition: u128,
-            type_info: u128,
+            pre_condition: BitSet,
+            post_condition: BitSet,
+            element_type: ElementType,
             event_code: [u8; 32],
-            _next_elem: Vec<u128>
+            next_elem: Vec<u128>
         ) -> DispatchResult {
-            ensure_signed(origin)?;
-            let iflow = Self::ensure_iflow_instance_exists(iflow_index)?;
-
-            let _type_info = iflow.get_type_info(element_index);
-            if  _type_info != 0 {
-                ensure!(_type_info == type_info, "Should be equal");
+            Self::ensure_process_owner_or_admin(origin, iflow_index)?;
+            let mut iflow = Self::ensure_iflow_instance_exists(iflow_index)?;
+
+            ensure!(element_type.is_valid(), Error::<T>::InvalidElementType);
+            let type_info = element_type.type_info();
+            let stored_type_info = iflow.get_type_info(element_index);
+            if stored_type_info != 0 {
+                ensure!(stored_type_info == type_info, Error::<T>::ElementTypeMismatch);
             }
+            // Element indexes are stored in the started activities, edges in the markings
+            ensure!(element_index < MAX_BIT_INDEX, Error::<T>::IndexOutOfBounds);
+            ensure!(
+                pre_condition.max_index().unwrap_or(0) < MAX_BIT_INDEX
+                    && post_condition.max_index().unwrap_or(0) < MAX_BIT_INDEX,
+                Error::<T>::IndexOutOfBounds
+            );
 
             //
             // == MUTATION SAFE ==
             //
 
-            if <IflowById<T>>::contains_key(iflow_index) {
-                <IflowById<T>>::mutate(iflow_index, |inner_iflow|
-                    inner_iflow.set_element(
-                        element_index,
-                        pre_condition,
-                        post_condition,
-                        type_info,
-                        event_code,
-                        _next_elem)
-                );
-            } else {
-                let mut iflow = Iflow::default();
-                iflow.set_element(
-                    element_index,
-                    pre_condition,
-                    post_condition,
-                    type_info,
-                    event_code,
-                    _next_elem
-                );
-                <IflowById<T>>::insert(iflow_index, iflow);
-            }
+            iflow.set_element(
+                element_index,
+                pre_condition,
+                post_condition,
+                type_info,
+                event_code,
+                next_elem
+            );
+            <IflowById<T>>::insert(iflow_index, iflow);
             Ok(())
         }
 
-        #[weight = 10_000]
+        #[weight = weights::link_sub_process::<T>(attached_events.len() as u32)]
         pub fn link_sub_process(
             origin,
             iflow_index: T::InstanceId,
@@ -462,10 +948,16 @@ decl_module! {
             attached_events: Vec<u128>,
             count_instances: u128,
         ) -> DispatchResult {
-            ensure_signed(origin)?;
+            Self::ensure_process_owner_or_admin(origin, iflow_index)?;
 
             let iflow = Self::ensure_iflow_instance_exists(iflow_index)?;
             Self::ensure_subprocess_to_link_in_data_structure(&iflow, parent_index)?;
+            Self::ensure_iflow_instance_exists(child_flow_inst)?;
+            Self::ensure_sub_process_is_not_cyclic(iflow_index, child_flow_inst)?;
+            ensure!(
+                count_instances <= MAX_SUB_PROCESSES as u128,
+                Error::<T>::TooManyInstances
+            );
 
             //
             // == MUTATION SAFE ==
@@ -481,7 +973,9 @@ decl_module! {
             Ok(())
         }
 
-        #[weight = 10_000]
+        #[weight = weights::set_factory_instance::<T>(
+            (instantiate_selector.len() + execute_script_selector.len()) as u32
+        )]
         pub fn set_factory_instance(
             origin,
             instance_id: T::InstanceId,
@@ -489,7 +983,8 @@ decl_module! {
             instantiate_selector: Vec<u8>,
             execute_script_selector: Vec<u8>
         ) -> DispatchResult {
-            ensure_signed(origin)?;
+            Self::ensure_process_owner_or_admin(origin, instance_id)?;
+            Self::ensure_iflow_instance_exists(instance_id)?;
             let factory = Ifactory::new(data_hash, instantiate_selector, execute_script_selector);
 
             //
@@ -501,273 +996,1030 @@ decl_module! {
             Ok(())
         }
 
-        #[weight = 10_000]
-        pub fn continue_execution(origin, instance_id: T::InstanceId, element_index: u128) -> DispatchResult {
-            ensure_signed(origin)?;
+        /// Charged for the most work the interpreter may do, the unused weight is refunded.
+        #[weight = weights::continue_execution::<T>(
+            T::MaxExecutionSteps::get(),
+            T::MaxExecutionSteps::get(),
+            MAX_SUB_PROCESSES
+        )]
+        #[transactional]
+        pub fn continue_execution(
+            origin,
+            instance_id: T::InstanceId,
+            element_index: u128
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
             let idata = Self::ensure_idata_instance_exists(instance_id)?;
+            ensure!(
+                T::ExecutionPermission::can_continue(&who, instance_id, element_index),
+                Error::<T>::Unauthorized
+            );
+            ensure!(
+                idata.get_status() == InstanceStatus::Running,
+                Error::<T>::InstanceNotRunning
+            );
 
             //
             // == MUTATION SAFE ==
             //
 
-            idata.continue_execution(element_index)?;
-            Ok(())
+            // Call bpmn interpreter execution on given index
+            let mut meter = ExecutionMeter::default();
+            Self::execute_elements(&mut meter, instance_id, element_index)?;
+            Ok(Some(weights::continue_execution::<T>(
+                meter.executed,
+                meter.queued,
+                meter.instances,
+            )).into())
         }
 
-        /// Instantiation of Root-Process
-        #[weight = 10_000]
-        pub fn create_root_instance(origin, parent_case: T::InstanceId) -> DispatchResult {
+        /// Instantiation of Root-Process under a fresh instance id, reserving
+        /// `Trait::InstanceDeposit` from the caller until the instance is pruned.
+        #[weight = weights::create_root_instance::<T>(
+            T::MaxExecutionSteps::get(),
+            T::MaxExecutionSteps::get(),
+            MAX_SUB_PROCESSES
+        )]
+        #[transactional]
+        pub fn create_root_instance(origin, parent_case: T::InstanceId) -> DispatchResultWithPostInfo {
+
+            let who = ensure_signed(origin)?;
+            let deposit = T::InstanceDeposit::get();
+            ensure!(
+                <T as Trait>::Currency::can_reserve(&who, deposit),
+                Error::<T>::InsufficientDeposit
+            );
 
-            ensure_signed(origin)?;
+            let mut meter = ExecutionMeter::default();
+            let instance_id = Self::start_root_instance(&mut meter, parent_case)?;
+            Self::reserve_deposit(instance_id, who, deposit)?;
 
-            let mut iflow = Self::ensure_iflow_instance_exists(parent_case)?;
+            Ok(Some(weights::create_root_instance::<T>(
+                meter.executed,
+                meter.queued,
+                meter.instances,
+            )).into())
+        }
+
+        /// Registers a whole process model under a fresh process id, once validated.
+        #[weight = weights::deploy_process::<T>(
+            elements.len() as u32,
+            links.len() as u32,
+            elements
+                .iter()
+                .map(|element| {
+                    weights::condition_bytes(&element.pre_condition, &element.post_condition)
+                })
+                .fold(0, u32::saturating_add)
+        )]
+        pub fn deploy_process(
+            origin,
+            elements: Vec<ElementDefinition>,
+            links: Vec<SubProcessLink<T::InstanceId>>,
+            data_hash: T::Hash,
+            instantiate_selector: Vec<u8>,
+            execute_script_selector: Vec<u8>
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            Self::ensure_process_model_is_valid(&elements, &links)?;
+
+            let process_id = Self::process_id_count()
+                .checked_add(&One::one())
+                .ok_or(Error::<T>::InstanceIdOverflow)?;
+            ensure!(!<IflowById<T>>::contains_key(process_id), Error::<T>::InstanceIdTaken);
 
-            let contract_id = iflow.get_factory_instance_mut().new_instance(parent_case)?;
+            let mut iflow = Iflow::default();
+            for element in elements {
+                iflow.set_element(
+                    element.element_index,
+                    element.pre_condition,
+                    element.post_condition,
+                    element.element_type.type_info(),
+                    element.event_code,
+                    element.next_elem,
+                );
+            }
+            for link in links {
+                iflow.link_sub_process(
+                    link.parent_index,
+                    link.child_flow,
+                    link.attached_events,
+                    link.count_instances,
+                );
+            }
+            iflow.set_factory_instance(
+                Ifactory::new(data_hash, instantiate_selector, execute_script_selector)
+            );
 
             //
             // == MUTATION SAFE ==
             //
 
-            <IflowById<T>>::insert(parent_case, iflow.clone());
-
+            <ProcessIdCount<T>>::put(process_id);
+            <IflowById<T>>::insert(process_id, iflow);
+            <ProcessOwner<T>>::insert(process_id, &who);
+            Self::deposit_event(RawEvent::ProcessDeployed(process_id, who));
+            Ok(())
+        }
 
-            let mut idata = Idata::default();
+        /// Hands the process model over to `new_owner`.
+        #[weight = weights::transfer_ownership::<T>()]
+        pub fn transfer_ownership(
+            origin,
+            process_id: T::InstanceId,
+            new_owner: T::AccountId
+        ) -> DispatchResult {
+            Self::ensure_process_owner_or_admin(origin, process_id)?;
+            Self::ensure_iflow_instance_exists(process_id)?;
 
-            idata.set_parent(None, parent_case, 0);
+            //
+            // == MUTATION SAFE ==
+            //
 
-            <IdataById<T>>::insert(parent_case, idata);
+            let previous_owner = <ProcessOwner<T>>::get(process_id);
+            <ProcessOwner<T>>::insert(process_id, &new_owner);
+            Self::deposit_event(RawEvent::OwnershipTransferred(process_id, previous_owner, new_owner));
+            Ok(())
+        }
 
-            Self::deposit_event(RawEvent::NewCaseCreated(contract_id));
+        /// Replaces the participants of the process instance, set by the owner of its model.
+        #[weight = weights::set_participants::<T>(participants.len() as u32)]
+        pub fn set_participants(
+            origin,
+            instance_id: T::InstanceId,
+            participants: Vec<T::AccountId>
+        ) -> DispatchResult {
+            let idata = Self::ensure_idata_instance_exists(instance_id)?;
+            Self::ensure_process_owner_or_admin(origin, idata.get_flow_node())?;
+            ensure!(
+                participants.len() <= MAX_PARTICIPANTS as usize,
+                Error::<T>::TooManyParticipants
+            );
 
-            Self::execution_required(parent_case, &iflow)?;
+            //
+            // == MUTATION SAFE ==
+            //
 
+            <Participants<T>>::insert(instance_id, participants);
+            Self::deposit_event(RawEvent::ParticipantsSet(instance_id));
             Ok(())
         }
-    }
-}
-
-impl<T: Trait> Module<T> {
-    /// BPMN Interpreter logic
 
-    /// Instantiation of a sub-process by its parent
-    pub fn create_instance(
-        element_index: u128,
-        parent_case: T::InstanceId,
-    ) -> Result<(), &'static str> {
-        ensure!(
-            parent_case != T::InstanceId::default(),
-            "Parent case should not be root"
-        );
+        /// Sets when the timer event of the process model is due, arming timer start events
+        /// right away.
+        #[weight = weights::set_timer::<T>()]
+        pub fn set_timer(
+            origin,
+            process_id: T::InstanceId,
+            element_index: u128,
+            timer: TimerDefinition<MomentOf<T>>
+        ) -> DispatchResult {
+            Self::ensure_process_owner_or_admin(origin, process_id)?;
+            let mut iflow = Self::ensure_iflow_instance_exists(process_id)?;
+            let type_info = iflow.get_type_info(element_index);
+            ensure!(has(type_info, EVENT | TIMER) && timer.is_valid(), Error::<T>::InvalidTimer);
+            let is_start_event = has(type_info, START);
+            if is_start_event {
+                Self::ensure_timer_bucket_has_room(timer.first_due(T::Time::now()))?;
+            }
 
-        let idata = Self::ensure_idata_instance_exists(parent_case)?;
+            //
+            // == MUTATION SAFE ==
+            //
 
-        let parent_flow_id = idata.get_flow_node();
-        let parent_flow = Self::ensure_iflow_instance_exists(parent_flow_id)?;
+            iflow.set_timer(element_index, timer);
+            <IflowById<T>>::insert(process_id, iflow);
+            if is_start_event {
+                Self::schedule_timer(ScheduledTimer {
+                    due: timer.first_due(T::Time::now()),
+                    instance_id: process_id,
+                    element_index,
+                    fired: 0,
+                    start_event: true,
+                })?;
+            }
+            Self::deposit_event(RawEvent::TimerSet(process_id, element_index));
+            Ok(())
+        }
 
-        let child_flow_id = parent_flow.get_sub_process_instance(element_index);
-        let mut child_flow = Self::ensure_iflow_instance_exists(child_flow_id)?;
+        /// Correlates the messages sent with the key to the process instance, set by the owner
+        /// of its model.
+        #[weight = weights::set_correlation_key::<T>()]
+        pub fn set_correlation_key(
+            origin,
+            instance_id: T::InstanceId,
+            correlation_key: T::Hash
+        ) -> DispatchResult {
+            let idata = Self::ensure_idata_instance_exists(instance_id)?;
+            Self::ensure_process_owner_or_admin(origin, idata.get_flow_node())?;
+            let previous_key = Self::correlation_key(instance_id);
+            if previous_key == Some(correlation_key) {
+                return Ok(());
+            }
+            ensure!(
+                Self::correlated_instances(correlation_key).len()
+                    < MAX_CORRELATED_INSTANCES as usize,
+                Error::<T>::TooManyCorrelatedInstances
+            );
 
-        let contract_id = child_flow
-            .get_factory_instance_mut()
-            .new_instance(child_flow_id)?;
+            //
+            // == MUTATION SAFE ==
+            //
 
-        //
-        // == MUTATION SAFE ==
-        //
+            if let Some(previous_key) = previous_key {
+                <CorrelatedInstances<T>>::mutate(previous_key, |instances| {
+                    instances.retain(|correlated| *correlated != instance_id)
+                });
+            }
+            <CorrelatedInstances<T>>::mutate(correlation_key, |instances| {
+                instances.push(instance_id)
+            });
+            <CorrelationKey<T>>::insert(instance_id, correlation_key);
+            Self::deposit_event(RawEvent::CorrelationKeySet(instance_id, correlation_key));
+            Ok(())
+        }
 
-        <IflowById<T>>::insert(child_flow_id, child_flow.clone());
+        /// Delivers the message to the process instances correlated by the key that wait for
+        /// it, the caller must be allowed to continue their execution. Charged for the most work
+        /// the interpreter may do, the unused weight is refunded.
+        #[weight = weights::send_message::<T>(
+            payload.len() as u32,
+            T::MaxExecutionSteps::get(),
+            T::MaxExecutionSteps::get(),
+            MAX_SUB_PROCESSES
+        )]
+        #[transactional]
+        pub fn send_message(
+            origin,
+            correlation_key: T::Hash,
+            event_code: [u8; 32],
+            payload: Vec<u8>
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            ensure!(
+                payload.len() <= MAX_MESSAGE_PAYLOAD as usize,
+                Error::<T>::MessagePayloadTooLong
+            );
 
-        <IdataById<T>>::mutate(parent_case, |inner_data| {
-            inner_data.set_parent(Some(parent_case), child_flow_id, element_index);
-            inner_data.add_child(element_index, child_flow_id)
-        });
+            let mut meter = ExecutionMeter::default();
+            let delivered = Self::correlate_message(
+                &mut meter,
+                correlation_key,
+                event_code,
+                &payload,
+                |instance_id, element_index| {
+                    T::ExecutionPermission::can_continue(&who, instance_id, element_index)
+                },
+            )?;
+            ensure!(delivered > 0, Error::<T>::MessageNotCorrelated);
+            Ok(Some(weights::send_message::<T>(
+                payload.len() as u32,
+                meter.executed,
+                meter.queued,
+                meter.instances,
+            )).into())
+        }
 
-        Self::deposit_event(RawEvent::NewCaseCreated(contract_id));
+        /// Resumes the execution of an instance left with pending elements, anyone may pay for
+        /// it. Charged for the most work the interpreter may do, the unused weight is refunded.
+        #[weight = weights::poke_instance::<T>(
+            T::MaxExecutionSteps::get(),
+            T::MaxExecutionSteps::get(),
+            MAX_SUB_PROCESSES
+        )]
+        #[transactional]
+        pub fn poke_instance(origin, instance_id: T::InstanceId) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+            ensure!(
+                <PendingElements<T>>::contains_key(instance_id),
+                Error::<T>::NoPendingElements
+            );
+            ensure!(
+                Self::ensure_idata_instance_exists(instance_id)?.get_status()
+                    == InstanceStatus::Running,
+                Error::<T>::InstanceNotRunning
+            );
 
-        Self::execution_required(child_flow_id, &child_flow)?;
+            let mut meter = ExecutionMeter::default();
+            Self::resume_execution(&mut meter, instance_id)?;
+            Ok(Some(weights::poke_instance::<T>(
+                meter.executed,
+                meter.queued,
+                meter.instances,
+            )).into())
+        }
 
-        Ok(())
-    }
+        /// Suspends a running root instance and its sub-process instances, by the owner of its
+        /// process model or the admin origin. Their execution is kept pending until resumed.
+        #[weight = weights::suspend_instance::<T>(MAX_SUB_PROCESSES)]
+        pub fn suspend_instance(origin, instance_id: T::InstanceId) -> DispatchResultWithPostInfo {
+            let idata = Self::ensure_root_instance(instance_id)?;
+            Self::ensure_process_owner_or_admin(origin, idata.get_flow_node())?;
+            ensure!(
+                idata.get_status() == InstanceStatus::Running,
+                Error::<T>::InstanceNotRunning
+            );
+            let tree = Self::instance_tree(instance_id)?;
 
-    fn execution_required(
-        child_flow_id: T::InstanceId,
-        child_flow: &Iflow<T>,
-    ) -> Result<(), &'static str> {
-        Self::ensure_idata_instance_exists(child_flow_id)?;
+            //
+            // == MUTATION SAFE ==
+            //
 
-        let first_elem = child_flow.get_first_elem();
-        <IdataById<T>>::mutate(child_flow_id, |idata| {
-            let post_condition = child_flow.get_post_condition(first_elem);
-            idata.set_marking(post_condition);
-        });
-        let next = child_flow.get_ady_elements(first_elem);
-        if !next.is_empty() {
-            Self::execute_elements(child_flow_id, next[0])?;
+            Self::suspend_tree(&tree);
+            Self::deposit_event(RawEvent::InstanceSuspended(instance_id));
+            Ok(Some(weights::suspend_instance::<T>(tree.len() as u32)).into())
         }
-        Ok(())
-    }
 
-    /// This function only receive THROW EVENTS (throw event verification made in function executeElement)
-    fn throw_event(
-        parent_case: T::InstanceId,
-        idata: &Idata<T>,
-        event_code: [u8; 32],
+        /// Resumes a suspended root instance and its sub-process instances, by the owner of its
+        /// process model or the admin origin. Their pending elements are resumed by
+        /// `on_initialize` or `poke_instance`.
+        #[weight = weights::resume_instance::<T>(MAX_SUB_PROCESSES)]
+        pub fn resume_instance(origin, instance_id: T::InstanceId) -> DispatchResultWithPostInfo {
+            let idata = Self::ensure_root_instance(instance_id)?;
+            Self::ensure_process_owner_or_admin(origin, idata.get_flow_node())?;
+            ensure!(
+                idata.get_status() == InstanceStatus::Suspended,
+                Error::<T>::InstanceNotSuspended
+            );
+            let tree = Self::instance_tree(instance_id)?;
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            Self::resume_tree(&tree);
+            Self::deposit_event(RawEvent::InstanceResumed(instance_id));
+            Ok(Some(weights::resume_instance::<T>(tree.len() as u32)).into())
+        }
+
+        /// Cancels a running or suspended root instance, by the owner of its process model or
+        /// the admin origin. Its sub-process instances are terminated.
+        #[weight = weights::cancel_instance::<T>(MAX_SUB_PROCESSES)]
+        #[transactional]
+        pub fn cancel_instance(origin, instance_id: T::InstanceId) -> DispatchResultWithPostInfo {
+            let idata = Self::ensure_root_instance(instance_id)?;
+            Self::ensure_process_owner_or_admin(origin, idata.get_flow_node())?;
+            ensure!(
+                idata.get_status() == InstanceStatus::Running
+                    || idata.get_status() == InstanceStatus::Suspended,
+                Error::<T>::InstanceNotRunning
+            );
+            // Killing visits at most the instances of the tree, within the limit of the meter
+            let tree = Self::instance_tree(instance_id)?;
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            let mut meter = ExecutionMeter::default();
+            Self::kill_process(&mut meter, instance_id)?;
+            <IdataById<T>>::mutate(instance_id, |idata| {
+                idata.set_status(InstanceStatus::Terminated)
+            });
+            Self::prune_later(instance_id);
+            Self::deposit_event(RawEvent::InstanceCancelled(instance_id));
+            Ok(Some(weights::cancel_instance::<T>(tree.len() as u32)).into())
+        }
+
+        /// Removes an ended root instance and its sub-process instances from storage, anyone
+        /// may pay for it. The deposit reserved for it is refunded to the account that created
+        /// it. The data & scripts contract is shared by the instances of the process model, it
+        /// is left as is.
+        #[weight = weights::prune_instance::<T>(MAX_SUB_PROCESSES)]
+        pub fn prune_instance(origin, instance_id: T::InstanceId) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+            let idata = Self::ensure_root_instance(instance_id)?;
+            ensure!(
+                idata.get_status() != InstanceStatus::Running
+                    && idata.get_status() != InstanceStatus::Suspended,
+                Error::<T>::InstanceNotEnded
+            );
+            let tree = Self::instance_tree(instance_id)?;
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            Self::prune_tree(&tree);
+            Ok(Some(weights::prune_instance::<T>(tree.len() as u32)).into())
+        }
+
+        /// Sets how the data & scripts contract decides the number of instances of the
+        /// multi-instance sub-process and when it completes.
+        #[weight = weights::set_multi_instance::<T>(
+            (multi_instance.cardinality_selector.len()
+                + multi_instance.completion_selector.len()) as u32
+        )]
+        pub fn set_multi_instance(
+            origin,
+            process_id: T::InstanceId,
+            element_index: u128,
+            multi_instance: MultiInstanceDefinition
+        ) -> DispatchResult {
+            Self::ensure_process_owner_or_admin(origin, process_id)?;
+            let mut iflow = Self::ensure_iflow_instance_exists(process_id)?;
+            let type_info = iflow.get_type_info(element_index);
+            ensure!(
+                has(type_info, ACTIVITY)
+                    && has_any(type_info, PARALLEL_MULTI_INSTANCE | SEQUENTIAL_MULTI_INSTANCE),
+                Error::<T>::InvalidMultiInstance
+            );
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            iflow.set_multi_instance(element_index, multi_instance);
+            <IflowById<T>>::insert(process_id, iflow);
+            Self::deposit_event(RawEvent::MultiInstanceSet(process_id, element_index));
+            Ok(())
+        }
+
+        /// Sets how the data & scripts contract decides whether the standard loop activity runs
+        /// another iteration, and the most iterations it runs.
+        #[weight = weights::set_loop::<T>(standard_loop.condition_selector.len() as u32)]
+        pub fn set_loop(
+            origin,
+            process_id: T::InstanceId,
+            element_index: u128,
+            standard_loop: LoopDefinition
+        ) -> DispatchResult {
+            Self::ensure_process_owner_or_admin(origin, process_id)?;
+            let mut iflow = Self::ensure_iflow_instance_exists(process_id)?;
+            ensure!(
+                has(iflow.get_type_info(element_index), ACTIVITY | STANDARD_LOOP),
+                Error::<T>::InvalidLoop
+            );
+            ensure!(
+                standard_loop
+                    .maximum
+                    .map_or(true, |maximum| maximum > 0 && maximum <= MAX_LOOP_ITERATIONS),
+                Error::<T>::InvalidLoop
+            );
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            iflow.set_loop(element_index, standard_loop);
+            <IflowById<T>>::insert(process_id, iflow);
+            Self::deposit_event(RawEvent::LoopSet(process_id, element_index));
+            Ok(())
+        }
+
+        /// Attaches the compensation boundary event to the activity, the element it leads to
+        /// being the compensation handler of the activity.
+        #[weight = weights::set_compensation::<T>()]
+        pub fn set_compensation(
+            origin,
+            process_id: T::InstanceId,
+            element_index: u128,
+            boundary_event: u128
+        ) -> DispatchResult {
+            Self::ensure_process_owner_or_admin(origin, process_id)?;
+            let mut iflow = Self::ensure_iflow_instance_exists(process_id)?;
+            let type_info = iflow.get_type_info(element_index);
+            ensure!(
+                has(type_info, ACTIVITY) && !has(type_info, EVENT_SUB_PROCESS),
+                Error::<T>::InvalidCompensation
+            );
+            ensure!(
+                has(iflow.get_type_info(boundary_event), EVENT | BOUNDARY | COMPENSATION),
+                Error::<T>::InvalidCompensation
+            );
+            let handler = iflow
+                .get_first_ady_element(boundary_event)
+                .map_err(|_| Error::<T>::InvalidCompensation)?;
+            ensure!(
+                has(iflow.get_type_info(handler), ACTIVITY),
+                Error::<T>::InvalidCompensation
+            );
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            iflow.set_compensation(element_index, boundary_event);
+            <IflowById<T>>::insert(process_id, iflow);
+            Self::deposit_event(RawEvent::CompensationSet(process_id, element_index));
+            Ok(())
+        }
+
+        /// Sets the selector of the data & scripts contract giving the payload the error or
+        /// escalation event throws, or taking the payload it catches. Throw events call it with
+        /// the instance id and element index, expecting the payload as a `Vec<u8>`, catch events
+        /// add the payload caught and expect nothing, so each needs a message of its own.
+        #[weight = weights::set_event_payload::<T>(payload_selector.len() as u32)]
+        pub fn set_event_payload(
+            origin,
+            process_id: T::InstanceId,
+            element_index: u128,
+            payload_selector: Vec<u8>
+        ) -> DispatchResult {
+            Self::ensure_process_owner_or_admin(origin, process_id)?;
+            let mut iflow = Self::ensure_iflow_instance_exists(process_id)?;
+            let type_info = iflow.get_type_info(element_index);
+            ensure!(
+                has(type_info, EVENT) && has_any(type_info, ERROR | ESCALATION),
+                Error::<T>::InvalidEventPayload
+            );
+
+            //
+            // == MUTATION SAFE ==
+            //
+
+            iflow.set_event_payload(element_index, payload_selector);
+            <IflowById<T>>::insert(process_id, iflow);
+            Self::deposit_event(RawEvent::EventPayloadSet(process_id, element_index));
+            Ok(())
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// BPMN Interpreter logic
+
+    /// Instantiation of a Root-Process, executed up to the elements waiting for a trigger.
+    /// Returns the id of the new instance.
+    fn start_root_instance(
+        meter: &mut ExecutionMeter,
+        process_id: T::InstanceId,
+    ) -> Result<T::InstanceId, DispatchError> {
+        let mut iflow = Self::ensure_iflow_instance_exists(process_id)?;
+        let instance_id = Self::next_instance_id()?;
+
+        let contract_id = iflow.get_factory_instance_mut().new_instance(instance_id)?;
+
+        //
+        // == MUTATION SAFE ==
+        //
+
+        <InstanceIdCount<T>>::put(instance_id);
+        <IflowById<T>>::insert(process_id, iflow.clone());
+
+        let mut idata = Idata::default();
+
+        idata.set_parent(None, process_id, 0);
+
+        <IdataById<T>>::insert(instance_id, idata);
+
+        Self::deposit_event(RawEvent::NewCaseCreated(contract_id));
+        Self::deposit_event(RawEvent::InstanceCreated(instance_id, process_id));
+
+        Self::execution_required(meter, instance_id, &iflow)?;
+        Ok(instance_id)
+    }
+
+    /// Instantiation of a sub-process by its parent, `loop_counter` numbering the instances of
+    /// multi-instance sub-processes
+    pub fn create_instance(
+        meter: &mut ExecutionMeter,
+        element_index: u128,
+        parent_case: T::InstanceId,
+        loop_counter: u128,
+    ) -> DispatchResult {
+        ensure!(
+            parent_case != T::InstanceId::default(),
+            Error::<T>::RootCaseHasNoParent
+        );
+        meter.visit_instance::<T>()?;
+
+        let idata = Self::ensure_idata_instance_exists(parent_case)?;
+
+        let parent_flow_id = idata.get_flow_node();
+        let parent_flow = Self::ensure_iflow_instance_exists(parent_flow_id)?;
+
+        let child_flow_id = parent_flow.get_sub_process_instance(element_index)?;
+        let mut child_flow = Self::ensure_iflow_instance_exists(child_flow_id)?;
+        let child_id = Self::next_instance_id()?;
+
+        let contract_id = child_flow
+            .get_factory_instance_mut()
+            .new_instance(child_id)?;
+
+        //
+        // == MUTATION SAFE ==
+        //
+
+        <InstanceIdCount<T>>::put(child_id);
+        <IflowById<T>>::insert(child_flow_id, child_flow.clone());
+
+        let mut child_data = Idata::default();
+        child_data.set_parent(Some(parent_case), child_flow_id, element_index);
+        child_data.set_loop_counter(loop_counter);
+        <IdataById<T>>::insert(child_id, child_data);
+        <IdataById<T>>::mutate(parent_case, |inner_data| {
+            inner_data.add_child(element_index, child_id)
+        });
+
+        Self::deposit_event(RawEvent::NewCaseCreated(contract_id));
+        Self::deposit_event(RawEvent::InstanceCreated(child_id, child_flow_id));
+
+        Self::execution_required(meter, child_id, &child_flow)?;
+
+        Ok(())
+    }
+
+    /// Starts the sub-process of the instance, creating `created` of the `instance_count`
+    /// instances it completes after. Instances completing right away continue the execution of
+    /// the parent from storage, so its state is stored beforehand and returned reloaded, unless
+    /// the parent no longer runs.
+    fn start_sub_process(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        parent_state: [BitSet; 2],
+        element_index: u128,
+        instance_count: u128,
+        created: u128,
+    ) -> Result<Option<[BitSet; 2]>, DispatchError> {
+        let [marking, mut started_activities] = parent_state;
+        started_activities.insert(element_index);
+        <IdataById<T>>::mutate(parent_case, |idata| {
+            idata.set_marking(marking);
+            idata.set_activity_marking(started_activities);
+            idata.set_instance_count(element_index, instance_count);
+        });
+        for loop_counter in 0..created {
+            // Unless the instances created before completed the activity already
+            let idata = Self::ensure_idata_instance_exists(parent_case)?;
+            if !idata.get_started_activities().contains(element_index) {
+                break;
+            }
+            Self::create_instance(meter, element_index, parent_case, loop_counter)?;
+        }
+        let idata = Self::ensure_idata_instance_exists(parent_case)?;
+        if idata.get_status() != InstanceStatus::Running {
+            return Ok(None);
+        }
+        Ok(Some([
+            idata.get_marking().clone(),
+            idata.get_started_activities().clone(),
+        ]))
+    }
+
+    /// Id of the next process instance, taken once stored as `InstanceIdCount`.
+    fn next_instance_id() -> Result<T::InstanceId, Error<T>> {
+        let instance_id = Self::instance_id_count()
+            .checked_add(&One::one())
+            .ok_or(Error::<T>::InstanceIdOverflow)?;
+        ensure!(
+            !<IdataById<T>>::contains_key(instance_id),
+            Error::<T>::InstanceIdTaken
+        );
+        Ok(instance_id)
+    }
+
+    fn execution_required(
+        meter: &mut ExecutionMeter,
+        instance_id: T::InstanceId,
+        child_flow: &Iflow<T>,
+    ) -> DispatchResult {
+        Self::ensure_idata_instance_exists(instance_id)?;
+
+        let first_elem = child_flow.get_first_elem();
+        let post_condition = child_flow.get_post_condition(first_elem);
+        Self::complete_element(instance_id, first_elem, &post_condition);
+        <IdataById<T>>::mutate(instance_id, |idata| idata.set_marking(post_condition));
+        let next = child_flow.get_ady_elements(first_elem)?;
+        if !next.is_empty() {
+            Self::execute_elements(meter, instance_id, next[0])?;
+        }
+        Ok(())
+    }
+
+    /// This function only receive THROW EVENTS (throw event verification made in function executeElement)
+    fn throw_event(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        idata: &Idata<T>,
+        event_code: [u8; 32],
         event_info: u128,
-    ) -> Result<(), &'static str> {
-        let mut parent_state: [u128; 2] = [0; 2];
-        parent_state[0] = idata.get_marking();
-        parent_state[0] = idata.get_started_activities();
+        payload: &[u8],
+    ) -> DispatchResult {
+        let parent_state = [idata.get_marking(), idata.get_started_activities()];
         match event_info {
-            event_info if event_info & 4096 == 4096 => {
+            event_info if has(event_info, COMPENSATION) => {
+                // Compensation (BIT 17), undoing the activities completed by the instance
+                Self::compensate(meter, parent_case)?;
+                if has(event_info, END) {
+                    // Then ending the instance as a default end event would
+                    let idata = Self::ensure_idata_instance_exists(parent_case)?;
+                    Self::throw_event(
+                        meter,
+                        parent_case,
+                        &idata,
+                        event_code,
+                        EVENT | THROW | END | DEFAULT_EVENT,
+                        payload,
+                    )?;
+                }
+            }
+            event_info if has(event_info, MESSAGE) => {
                 // Message (BIT 15), to publish a Message in the Event Log
                 Self::deposit_event(RawEvent::MessageSent(event_code.to_vec()));
-            }
-            event_info if event_info & 5632 == 5632 => {
-                // 9- End, 10- Default, 12- Message
-                // If there are not tokens to consume nor started activities in any subprocess
-                if parent_state[0] | parent_state[1] == 0 {
-                    // Sub-process ended, thus continue execution on parent
-                    Self::try_catch_event(parent_case, idata, event_code, event_info, true)?;
+                if let Some(correlation_key) = Self::correlation_key(parent_case) {
+                    // And to deliver it to the other instances correlated by the same key
+                    Self::correlate_message(
+                        meter,
+                        correlation_key,
+                        event_code,
+                        &[],
+                        |instance_id, _| instance_id != parent_case,
+                    )?;
+                }
+                if has(event_info, END) {
+                    // Then ending the instance as a default end event would
+                    let idata = Self::ensure_idata_instance_exists(parent_case)?;
+                    Self::throw_event(
+                        meter,
+                        parent_case,
+                        &idata,
+                        event_code,
+                        EVENT | THROW | END | DEFAULT_EVENT,
+                        payload,
+                    )?;
                 }
             }
             event_info => {
-                if event_info & 2048 == 2048 {
+                let instance_completed = parent_state[0].is_empty() && parent_state[1].is_empty();
+                if has(event_info, TERMINATE) {
                     // Terminate Event (BIT 11), only END EVENT from standard,
                     // Terminate the execution in the current Sub-process and each children
-                    Self::kill_process(parent_case)?;
+                    Self::kill_process(meter, parent_case)?;
+                    Self::end_instance(parent_case, InstanceStatus::Terminated);
                 }
                 Self::try_catch_event(
+                    meter,
                     parent_case,
                     idata,
                     event_code,
                     event_info,
-                    parent_state[0] | parent_state[1] == 0,
+                    instance_completed,
+                    payload,
                 )?;
+                if has(event_info, END) && instance_completed {
+                    // Unless the event ended it otherwise
+                    Self::end_instance(parent_case, InstanceStatus::Completed);
+                }
             }
         }
         Ok(())
     }
 
     fn try_catch_event(
+        meter: &mut ExecutionMeter,
         parent_case: T::InstanceId,
         idata: &Idata<T>,
         event_code: [u8; 32],
         event_info: u128,
         instance_completed: bool,
-    ) -> Result<(), &'static str> {
+        payload: &[u8],
+    ) -> DispatchResult {
         if let Some(catch_case) = idata.get_idata_parent() {
             let mut catch_case_data = Self::ensure_idata_instance_exists(catch_case)?;
-            let child_flow = idata.get_flow_node();
-            let child_flow_instance = Self::ensure_iflow_instance_exists(child_flow)?;
-            let mut parent_state: [u128; 2] = [0; 2];
-            parent_state[0] = catch_case_data.get_marking();
-            parent_state[1] = catch_case_data.get_started_activities();
+            // Process model of the parent, which holds the sub-process and its events
+            let catch_flow = Self::ensure_iflow_instance_exists(catch_case_data.get_flow_node())?;
+            let parent_state = [
+                catch_case_data.get_marking().clone(),
+                catch_case_data.get_started_activities().clone(),
+            ];
             let sub_process_index = idata.get_index_in_parent();
-            let run_inst_count = if instance_completed {
+            let sub_process_info = catch_flow.get_type_info(sub_process_index);
+            let mut run_inst_count = if instance_completed {
+                let run_inst_count = catch_case_data
+                    .get_instance_count(sub_process_index)?
+                    .checked_sub(1)
+                    .ok_or(Error::<T>::InstanceCountUnderflow)?;
                 <IdataById<T>>::mutate(catch_case, |catch_case_data| {
-                    catch_case_data.decrement_instance_count(sub_process_index)
+                    catch_case_data.set_instance_count(sub_process_index, run_inst_count)
                 });
-                catch_case_data.get_instance_count(sub_process_index) - 1
+                run_inst_count
             } else {
-                catch_case_data.get_instance_count(sub_process_index)
+                catch_case_data.get_instance_count(sub_process_index)?
             };
+            if instance_completed
+                && run_inst_count > 0
+                && has_any(event_info, DEFAULT_EVENT | TERMINATE | MESSAGE)
+                && Self::is_multi_instance_complete(
+                    catch_case,
+                    &catch_flow,
+                    sub_process_index,
+                    idata.get_loop_counter(),
+                )?
+            {
+                // Completion condition of the multi-instance activity met before its last instance
+                Self::cancel_remaining_instances(
+                    meter,
+                    catch_case,
+                    sub_process_index,
+                    parent_case,
+                )?;
+                run_inst_count = 0;
+            }
+            let loop_continued = instance_completed
+                && run_inst_count == 0
+                && has(sub_process_info, STANDARD_LOOP)
+                && has_any(event_info, DEFAULT_EVENT | TERMINATE | MESSAGE)
+                && Self::is_loop_continued(
+                    catch_case,
+                    &catch_flow,
+                    sub_process_index,
+                    idata.get_loop_counter().saturating_add(1),
+                )?;
+            if loop_continued {
+                // Another iteration of the standard loop, the activity keeps running
+                <IdataById<T>>::mutate(catch_case, |catch_case_data| {
+                    catch_case_data.set_instance_count(sub_process_index, 1)
+                });
+                run_inst_count = 1;
+            }
             if run_inst_count == 0 {
                 // Update the corresponding sub-process, call activity as completed
                 <IdataById<T>>::mutate(catch_case, |catch_case| {
-                    catch_case
-                        .set_activity_marking(parent_state[1] & !(1 << 1 << sub_process_index))
+                    let mut started_activities = parent_state[1].clone();
+                    started_activities.remove(sub_process_index);
+                    catch_case.set_activity_marking(started_activities)
                 });
             }
-            let sub_process_info = child_flow_instance.get_instance_count(sub_process_index);
-            if event_info & 7168 != 0 {
+            if has_any(event_info, DEFAULT_EVENT | TERMINATE | MESSAGE) {
                 // If receiving 10- Default, 11- Terminate or 12- Message
-                if run_inst_count == 0 && sub_process_info & 4096 != 4096 {
+                if run_inst_count == 0 && !has(sub_process_info, EVENT_SUB_PROCESS) {
                     // No Instances of the sub-process propagating the event and The sub-process isn't an event-sub-process (BIT 12)
-                    let post_condition = child_flow_instance.get_post_condition(sub_process_index);
+                    let post_condition = catch_flow.get_post_condition(sub_process_index);
+                    Self::complete_element(catch_case, sub_process_index, &post_condition);
+                    Self::record_compensable(catch_case, &catch_flow, sub_process_index);
                     <IdataById<T>>::mutate(catch_case, |catch_case| {
-                        catch_case.set_marking(parent_state[0] & !post_condition)
+                        catch_case.set_marking(&parent_state[0] | &post_condition)
                     });
-                    let first_ady_element =
-                        child_flow_instance.get_ady_elements(sub_process_info)[0];
-                    Self::execute_elements(catch_case, first_ady_element)?;
-                } else if sub_process_info & 128 == 128 {
-                    // Multi-Instance Sequential (BIT 7), with pending instances to be started.
-                    Self::create_instance(sub_process_index, parent_case)?;
+                    let first_ady_element = catch_flow.get_first_ady_element(sub_process_index)?;
+                    Self::execute_elements(meter, catch_case, first_ady_element)?;
+                } else if has(sub_process_info, SEQUENTIAL_MULTI_INSTANCE) || loop_continued {
+                    // Multi-Instance Sequential (BIT 7) or Standard Loop (BIT 8), with pending instances to be started.
+                    let loop_counter = idata.get_loop_counter().saturating_add(1);
+                    Self::create_instance(meter, sub_process_index, catch_case, loop_counter)?;
                 }
             } else {
                 // Signal, Error or Escalation
                 // Signals are only handled from the Root-Process by Broadcast, thus the propagation must reach the Root-Process.
-                if event_info & 32768 == 32768 {
+                if has(event_info, SIGNAL) {
                     // Propagating the Signal to the Root-Process
+                    let mut root_case = catch_case;
                     while let Some(parent_case) = catch_case_data.get_idata_parent() {
                         catch_case_data = Self::ensure_idata_instance_exists(parent_case)?;
+                        root_case = parent_case;
                     }
-                    Self::broadcast_signal(parent_case)?;
+                    Self::broadcast_signal(meter, root_case, event_code)?;
                     return Ok(());
                 }
-                let events = child_flow_instance.get_event_list();
-
                 // The event can be catched only once, unless it is a signal where a broadcast must happen.
                 // Precondition: Event-subprocess must appear before boundary events on the event list.
-                for event in events {
-                    let ev_code = child_flow_instance.get_event_code(*event);
-                    if event_code == ev_code {
-                        // Verifiying there is a match with the throw-cath events.
-                        let catch_event_info = child_flow_instance.get_type_info(*event);
-                        let attached_to = child_flow_instance.get_attached_to(*event);
-
-                        if catch_event_info & 6 == 6 {
-                            // Start event-sub-process (BIT 6)
-                            if catch_event_info & 16 == 16 {
-                                // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
-                                // Before starting the event subprocess, the parent is killed
-                                Self::kill_process(catch_case)?;
-                            }
-
-                            // Starting event sub-process
-                            Self::create_instance(attached_to, parent_case)?;
-
-                            // Marking the event-sub-process as started
-                            <IdataById<T>>::mutate(catch_case, |catch_case| {
-                                catch_case
-                                    .set_activity_marking(parent_state[1] | (1 << attached_to))
-                            });
-                            return Ok(());
-                        } else if catch_event_info & 256 == 256 && attached_to == sub_process_index
-                        {
-                            // Boundary (BIT 6) of the subproces propagating the event
-                            if catch_event_info & 16 == 16 {
-                                // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
-                                Self::kill_process(parent_case)?;
-                            }
-
-                            // The subprocess propagating the event must be interrupted
-                            let post_condition = child_flow_instance.get_post_condition(*event);
-                            let first_ady_element = child_flow_instance.get_ady_elements(*event)[0];
-
-                            // Update the marking with the output of the boundary event
-                            <IdataById<T>>::mutate(catch_case, |catch_case| {
-                                catch_case.set_marking(parent_state[0] & !post_condition)
-                            });
-                            Self::execute_elements(catch_case, first_ady_element)?;
-                            return Ok(());
+                for event in Self::catching_events(&catch_flow, event_code, event_info)? {
+                    let catch_event_info = catch_flow.get_type_info(event);
+
+                    if has(catch_event_info, EVENT_SUB_PROCESS_START) {
+                        let attached_to = catch_flow.get_attached_to(event)?;
+                        // Start event-sub-process (BIT 6)
+                        if has(catch_event_info, INTERRUPTING) {
+                            // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
+                            // Before starting the event subprocess, the parent is killed
+                            Self::kill_process(meter, catch_case)?;
                         }
+
+                        // Starting event sub-process, with the payload caught
+                        Self::write_caught_payload(catch_case, &catch_flow, event, payload)?;
+                        Self::deposit_event(RawEvent::ElementStarted(catch_case, attached_to));
+                        Self::create_instance(meter, attached_to, catch_case, 0)?;
+
+                        // Marking the event-sub-process as started
+                        <IdataById<T>>::mutate(catch_case, |catch_case| {
+                            catch_case.set_activity_marking(
+                                &parent_state[1] | &BitSet::with_bit(attached_to),
+                            )
+                        });
+                        return Ok(());
+                    } else if has(catch_event_info, BOUNDARY)
+                        && catch_flow.get_attached_to(event)? == sub_process_index
+                    {
+                        // Boundary (BIT 6) of the subproces propagating the event
+                        if has(catch_event_info, INTERRUPTING) {
+                            // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
+                            Self::kill_process(meter, parent_case)?;
+                            Self::end_instance(parent_case, InstanceStatus::Terminated);
+                        }
+
+                        // The subprocess propagating the event must be interrupted
+                        let post_condition = catch_flow.get_post_condition(event);
+                        let first_ady_element = catch_flow.get_first_ady_element(event)?;
+
+                        // Update the marking with the output of the boundary event, the
+                        // handler it leads to given the payload caught
+                        Self::write_caught_payload(catch_case, &catch_flow, event, payload)?;
+                        Self::complete_element(catch_case, event, &post_condition);
+                        <IdataById<T>>::mutate(catch_case, |catch_case| {
+                            catch_case.set_marking(&parent_state[0] | &post_condition)
+                        });
+                        Self::execute_elements(meter, catch_case, first_ady_element)?;
+                        return Ok(());
                     }
                 }
                 // If the event was not caught the propagation continues to the parent unless it's the root process
-                Self::throw_event(catch_case, &catch_case_data, event_code, event_info)?;
+                Self::throw_event(
+                    meter,
+                    catch_case,
+                    &catch_case_data,
+                    event_code,
+                    event_info,
+                    payload,
+                )?;
             }
         } else {
             // No Parent exist, root node
-            if event_info & 8192 == 8192 {
+            if has(event_info, SIGNAL) {
+                // Broadcasting the Signal from the Root-Process
+                Self::broadcast_signal(meter, parent_case, event_code)?;
+            } else if has(event_info, ERROR) {
                 // Error event (BIT 13), only END EVENT from standard, in the root process.
-                Self::kill_process(parent_case)?;
+                Self::kill_process(meter, parent_case)?;
+                Self::end_instance(parent_case, InstanceStatus::Failed);
+                Self::deposit_event(RawEvent::InstanceFailed(parent_case, event_code));
             }
         }
         Ok(())
     }
 
-    fn kill_process(parent_case: T::InstanceId) -> Result<(), &'static str> {
+    /// Catches the intermediate or boundary event, or completes the receive task, the instance
+    /// waits on: the token moves on to the outgoing sequence flow, leaving the activity of a
+    /// boundary event and cancelling it if the event is interrupting. Returns whether the event
+    /// was caught, boundary events are not once their activity completed.
+    fn catch_waiting_event(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        idata: &Idata<T>,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) -> Result<bool, DispatchError> {
+        let event_info = iflow.get_type_info(element_index);
+        if !has(event_info, EVENT | BOUNDARY) {
+            if !idata.get_started_activities().contains(element_index) {
+                return Ok(false);
+            }
+            // Event of an event-based gateway, the others it leads to no longer wait
+            Self::resolve_event_based_gateway(parent_case, idata, iflow, element_index)?;
+        } else {
+            let attached_to = iflow.get_attached_to(element_index)?;
+            if !idata.get_started_activities().contains(attached_to) {
+                return Ok(false);
+            }
+            if has(event_info, INTERRUPTING) {
+                // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
+                let child_process_instances = idata.get_child_process_instances(attached_to)?;
+                Self::kill_processes(meter, child_process_instances)?;
+                <IdataById<T>>::mutate(parent_case, |idata| {
+                    let mut started_activities = idata.get_started_activities().clone();
+                    started_activities.remove(attached_to);
+                    idata.set_activity_marking(started_activities);
+                    idata.set_instance_count(attached_to, 0);
+                });
+            }
+        }
+
+        // Update the marking with the output of the element, which no longer waits
+        let post_condition = iflow.get_post_condition(element_index);
+        let first_ady_element = iflow.get_first_ady_element(element_index)?;
+        Self::complete_element(parent_case, element_index, &post_condition);
+        Self::record_compensable(parent_case, iflow, element_index);
+        <IdataById<T>>::mutate(parent_case, |idata| {
+            let marking = idata.get_marking() | &post_condition;
+            let mut started_activities = idata.get_started_activities().clone();
+            started_activities.remove(element_index);
+            idata.set_marking(marking);
+            idata.set_activity_marking(started_activities);
+        });
+        Self::execute_elements(meter, parent_case, first_ady_element)?;
+        Ok(true)
+    }
+
+    /// Arms the boundary timers and subscribes the boundary message events of the activity the
+    /// instance just started.
+    fn arm_boundary_events(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) -> DispatchResult {
+        for event in iflow.get_attached_events(element_index) {
+            let event_info = iflow.get_type_info(event);
+            if has(event_info, EVENT | BOUNDARY | TIMER) {
+                Self::arm_timer(meter, parent_case, iflow, event)?;
+            } else if has(event_info, EVENT | BOUNDARY | MESSAGE) {
+                Self::subscribe_message(meter, parent_case, iflow, event)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn kill_process(meter: &mut ExecutionMeter, parent_case: T::InstanceId) -> DispatchResult {
+        meter.visit_instance::<T>()?;
         let catch_case_data = Self::ensure_idata_instance_exists(parent_case)?;
         let started_activities = catch_case_data.get_started_activities();
         let child_flow_index = catch_case_data.get_flow_node();
@@ -775,291 +2027,611 @@ impl<T: Trait> Module<T> {
         let children = child_flow_instance.get_sub_process_list();
 
         <IdataById<T>>::mutate(parent_case, |parent_case_data| {
-            parent_case_data.set_marking(0);
-            parent_case_data.set_activity_marking(0);
+            parent_case_data.set_marking(BitSet::new());
+            parent_case_data.set_activity_marking(BitSet::new());
         });
+        Self::disarm_timers(parent_case);
+        <MessageSubscriptions<T>>::remove_prefix(parent_case);
+        <PendingElements<T>>::remove(parent_case);
+        <ResumeAttempts<T>>::remove(parent_case);
 
         for child in children {
-            if started_activities & (1 << child) != 0 {
-                let child_proc_instance = catch_case_data.get_child_process_instances(*child);
-                Self::kill_processes(child_proc_instance)?;
+            if started_activities.contains(*child) {
+                let child_proc_instance = catch_case_data.get_child_process_instances(*child)?;
+                Self::kill_processes(meter, child_proc_instance)?;
             }
         }
         Ok(())
     }
 
-    fn kill_processes(parent_cases: &[T::InstanceId]) -> Result<(), &'static str> {
+    fn kill_processes(
+        meter: &mut ExecutionMeter,
+        parent_cases: &[T::InstanceId],
+    ) -> DispatchResult {
         for &parent_case in parent_cases {
-            Self::kill_process(parent_case)?;
+            Self::kill_process(meter, parent_case)?;
+            Self::end_instance(parent_case, InstanceStatus::Terminated);
         }
         Ok(())
     }
 
-    fn broadcast_signal(parent_case: T::InstanceId) -> Result<(), &'static str> {
+    /// Broadcasts the signal named by the event code to the instance and its running
+    /// sub-process instances, at each of their signal catch events waiting for it.
+    fn broadcast_signal(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        event_code: [u8; 32],
+    ) -> DispatchResult {
+        meter.visit_instance::<T>()?;
         let parent_case_instance = Self::ensure_idata_instance_exists(parent_case)?;
         let child_flow_index = parent_case_instance.get_flow_node();
         let child_flow_instance = Self::ensure_iflow_instance_exists(child_flow_index)?;
 
-        let events = child_flow_instance.get_event_list();
-        for &event in events {
+        for &event in child_flow_instance.get_event_list() {
             let event_info = child_flow_instance.get_type_info(event);
+            // Event Catch Signal (BITs 2, 3 [0-catch, 1-throw], 15) named by the event code
+            if event_info & (THROW_EVENT | SIGNAL) != EVENT | SIGNAL
+                || child_flow_instance.get_event_code(event)? != event_code
+            {
+                continue;
+            }
+            // Catching an event before may have moved the tokens or ended the instance
+            let parent_case_instance = Self::ensure_idata_instance_exists(parent_case)?;
+            if parent_case_instance.get_status() != InstanceStatus::Running {
+                return Ok(());
+            }
 
-            if event_info & 32780 == 32772 {
-                // Event Catch Signal (BITs 2, 3 [0-catch, 1-throw], 15)
-                let catch_event_info = child_flow_instance.get_type_info(event);
-                let attached_to = child_flow_instance.get_attached_to(event);
-
-                if catch_event_info & 6 == 6 {
-                    // Start event-sub-process (BIT 6)
-                    if catch_event_info & 16 == 16 {
-                        // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
-                        // Before starting the event subprocess, the current process-instance is killed
-                        Self::kill_process(parent_case)?;
-                        Self::create_instance(attached_to, parent_case)?;
-                        <IdataById<T>>::mutate(parent_case, |parent_case_instance| {
-                            parent_case_instance.set_activity_marking(1 << attached_to);
-                        });
-                    } else if catch_event_info & 256 == 256 {
-                        // Boundary (BIT 6) of the subproces propagating the event
-                        if catch_event_info & 16 == 16 {
-                            // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
-                            // The subprocess propagating the event must be interrupted
-                            let child_process_instances =
-                                parent_case_instance.get_child_process_instances(attached_to);
-                            Self::kill_processes(child_process_instances)?;
-                        }
-                        let marking = parent_case_instance.get_marking();
-                        let post_condition = child_flow_instance.get_post_condition(event);
-
-                        // Update the marking with the output of the boundary event
-                        <IdataById<T>>::mutate(parent_case, |parent_case_data| {
-                            parent_case_data.set_marking(marking & !post_condition);
-                        });
-                        let first_ady_element = child_flow_instance.get_ady_elements(event)[0];
-
-                        // Continue the execution of possible internal elements
-                        Self::execute_elements(parent_case, first_ady_element)?;
-                    } else if event_info & 160 == 160 {
-                        // Start (not Event Subprocess) OR Intermediate Event
-                        let marking = parent_case_instance.get_marking();
-                        let post_condition = child_flow_instance.get_post_condition(event);
-                        let pre_condition = child_flow_instance.get_pre_condition(event);
-                        let first_ady_element = child_flow_instance.get_ady_elements(event)[0];
-
-                        <IdataById<T>>::mutate(parent_case, |parent_case_data| {
-                            parent_case_data.set_marking(marking & !pre_condition | post_condition);
-                        });
-
-                        // Continue the execution of possible internal elements
-                        Self::execute_elements(parent_case, first_ady_element)?;
-                    }
+            if has(event_info, EVENT_SUB_PROCESS_START) {
+                let attached_to = child_flow_instance.get_attached_to(event)?;
+                // Start event-sub-process (BIT 6)
+                let mut started_activities = parent_case_instance.get_started_activities().clone();
+                if has(event_info, INTERRUPTING) {
+                    // Interrupting (BIT 4 must be 1, 0 if non-interrupting)
+                    // Before starting the event subprocess, the instance is killed
+                    Self::kill_process(meter, parent_case)?;
+                    started_activities = BitSet::new();
                 }
+                Self::deposit_event(RawEvent::ElementStarted(parent_case, attached_to));
+                Self::create_instance(meter, attached_to, parent_case, 0)?;
+                started_activities.insert(attached_to);
+                <IdataById<T>>::mutate(parent_case, |parent_case_instance| {
+                    parent_case_instance.set_activity_marking(started_activities);
+                });
+            } else if has_any(event_info, BOUNDARY | INTERMEDIATE) {
+                // Boundary event of a running activity or intermediate catch event the instance
+                // waits on, if it does
+                Self::catch_waiting_event(
+                    meter,
+                    parent_case,
+                    &parent_case_instance,
+                    &child_flow_instance,
+                    event,
+                )?;
+            } else if has(event_info, START) {
+                // Start (not Event Subprocess) Event
+                let marking = parent_case_instance.get_marking();
+                let post_condition = child_flow_instance.get_post_condition(event);
+                let pre_condition = child_flow_instance.get_pre_condition(event);
+                let first_ady_element = child_flow_instance.get_first_ady_element(event)?;
+
+                <IdataById<T>>::mutate(parent_case, |parent_case_data| {
+                    parent_case_data.set_marking(&(marking - &pre_condition) | &post_condition);
+                });
+
+                // Continue the execution of possible internal elements
+                Self::execute_elements(meter, parent_case, first_ady_element)?;
             }
-            let children = child_flow_instance.get_sub_process_list();
-            let started_activities = parent_case_instance.get_started_activities();
-            for &child in children {
-                if started_activities & (1 << child) != 0 {
-                    let child_proc_instances =
-                        parent_case_instance.get_child_process_instances(child);
-                    Self::broadcast_signals(child_proc_instances)?;
-                }
+        }
+
+        let parent_case_instance = Self::ensure_idata_instance_exists(parent_case)?;
+        if parent_case_instance.get_status() != InstanceStatus::Running {
+            return Ok(());
+        }
+        let started_activities = parent_case_instance.get_started_activities();
+        for &child in child_flow_instance.get_sub_process_list() {
+            if started_activities.contains(child) {
+                let child_proc_instances =
+                    parent_case_instance.get_child_process_instances(child)?;
+                Self::broadcast_signals(meter, child_proc_instances, event_code)?;
             }
         }
         Ok(())
     }
 
-    fn broadcast_signals(parent_cases: &[T::InstanceId]) -> Result<(), &'static str> {
+    fn broadcast_signals(
+        meter: &mut ExecutionMeter,
+        parent_cases: &[T::InstanceId],
+        event_code: [u8; 32],
+    ) -> DispatchResult {
         for &parent_case in parent_cases {
-            Self::broadcast_signal(parent_case)?;
+            Self::broadcast_signal(meter, parent_case, event_code)?;
         }
         Ok(())
     }
 
+    /// Calls the data & scripts contract of the process model with the input, returning its
+    /// output, or `None` if the model has no contract.
+    fn call_data_contract(
+        iflow: &Iflow<T>,
+        input_data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, DispatchError> {
+        let address = match iflow.get_factory_instance().get_address() {
+            Some(address) => address.clone(),
+            None => return Ok(None),
+        };
+        // Called from the account contracts are deployed from, see `Ifactory::new_instance`
+        <contracts::Module<T>>::bare_call(
+            T::AccountId::default(),
+            address,
+            Zero::zero(),
+            GAS.into(),
+            input_data,
+        )
+        .map(|result| Some(result.data))
+        .map_err(|_| Error::<T>::ScriptCallFailed.into())
+    }
+
     fn execute_elements(
+        meter: &mut ExecutionMeter,
         parent_case: T::InstanceId,
-        mut element_index: u128,
-    ) -> Result<(), &'static str> {
+        element_index: u128,
+    ) -> DispatchResult {
+        Self::execute_queue(meter, parent_case, vec![element_index])
+    }
+
+    /// Executes the elements enabled among the candidates and those they lead to, in queue
+    /// order. Candidates left once the call is out of steps are kept pending for the instance.
+    fn execute_queue(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        candidates: Vec<u128>,
+    ) -> DispatchResult {
         let idata = Self::ensure_idata_instance_exists(parent_case)?;
+        if idata.get_status() == InstanceStatus::Suspended {
+            Self::keep_suspended(parent_case, candidates);
+            return Ok(());
+        }
         let child_flow_index = idata.get_flow_node();
         let child_flow = Self::ensure_iflow_instance_exists(child_flow_index)?;
         // 0- tokensOnEdges
         // 1- startedActivities
-        let mut parent_state: [u128; 2] = [0; 2];
-        parent_state[0] = idata.get_marking();
-        parent_state[1] = idata.get_started_activities();
-
-        // Execution queue and pointers to the first & last element (i.e. basic circular queue implementation)
-        let mut queue: [u128; 100] = [0; 100];
-        let mut i: usize = 0;
-        let mut count: usize = 0;
-        queue[count] = element_index;
-        count += 1;
-        while i < count {
-            element_index = queue[i];
-            i += 1;
-            let ([pre_condition, post_condition, type_info], next) =
-                child_flow.get_element_info(element_index);
-
-            // Verifying Preconditions (i.e. Is the element enabled?)
-            match type_info {
-                type_info if type_info & 42 == 42 => {
-                    // else if (AND Join)
-                    if parent_state[0] & pre_condition != pre_condition {
-                        continue;
-                    }
-                    parent_state[0] &= !pre_condition;
-                }
-                type_info if type_info & 74 == 74 => {
-                    // else if (OR Join)
-                    ///// OR Join Implementation //////
-                }
-                type_info
-                    if (type_info & 1 == 1
-                        || (type_info & 4 == 4 && type_info & 640 != 0)
-                        || type_info & 2 == 2) =>
+        let mut parent_state = [
+            idata.get_marking().clone(),
+            idata.get_started_activities().clone(),
+        ];
+
+        // Execution queue, growing up to the elements the meter lets the call queue, followed
+        // by the elements left pending
+        let mut queue = VecDeque::new();
+        let mut pending = Vec::new();
+        for element_index in candidates {
+            Self::enqueue(meter, &mut queue, &mut pending, element_index);
+        }
+        loop {
+            if queue.is_empty() && pending.is_empty() {
+                // Inclusive joins left waiting may have been released by tokens taking another path
+                for join_index in
+                    child_flow.get_enabled_or_joins(&parent_state[0], &parent_state[1])
                 {
-                    // If (Activity || Intermediate/End Event || Gateway != AND/OR Join)
-                    if parent_state[0] & pre_condition == 0 {
-                        continue;
-                    }
-                    // Removing tokens from input arcs
-                    parent_state[0] &= !pre_condition;
-                }
-                _ => {
-                    continue;
+                    Self::enqueue(meter, &mut queue, &mut pending, join_index);
                 }
             }
+            let element_index = match queue.pop_front() {
+                Some(element_index) => element_index,
+                None => break,
+            };
+            let ((pre_condition, post_condition, type_info), next) =
+                child_flow.get_element_info(element_index)?;
+            let type_info = *type_info;
+
+            // Verifying Preconditions (i.e. Is the element enabled?)
+            if !child_flow.is_enabled(element_index, &parent_state[0], &parent_state[1]) {
+                continue;
+            }
+            if !meter.has_instances_left()
+                && has(type_info, ACTIVITY)
+                && has_any(type_info, SUB_PROCESS | CALL_ACTIVITY)
+            {
+                // Out of sub-process instances, the activity and the elements queued after it
+                // are left pending for the next calls
+                pending = iter::once(element_index)
+                    .chain(queue.drain(..))
+                    .chain(pending.drain(..))
+                    .collect();
+                break;
+            }
+            // Removing tokens from input arcs
+            parent_state[0] -= pre_condition;
+            meter.execute();
+            Self::deposit_event(RawEvent::ElementStarted(parent_case, element_index));
 
             // Executing current element (If enabled)
             match type_info {
-                type_info if type_info & 65 == 65 => {
+                type_info if has(type_info, ACTIVITY | PARALLEL_MULTI_INSTANCE) => {
                     // (0- Activity, 6- Parallel Multi-Instance)
-                    let child_instances = child_flow.get_instance_count(element_index);
-                    for _ in 0..child_instances {
-                        Self::create_instance(element_index, parent_case)?;
+                    let child_instances =
+                        Self::multi_instance_cardinality(parent_case, &child_flow, element_index)?;
+                    if child_instances == 0 {
+                        // No instance to run, the activity completes right away
+                        Self::complete_element(parent_case, element_index, post_condition);
+                        Self::record_compensable(parent_case, &child_flow, element_index);
+                        parent_state[0] |= post_condition;
+                    } else {
+                        parent_state = match Self::start_sub_process(
+                            meter,
+                            parent_case,
+                            parent_state,
+                            element_index,
+                            child_instances,
+                            child_instances,
+                        )? {
+                            Some(parent_state) => parent_state,
+                            // Ended by one of the sub-process instances
+                            None => return Ok(()),
+                        };
+                        if parent_state[1].contains(element_index) {
+                            Self::arm_boundary_events(
+                                meter,
+                                parent_case,
+                                &child_flow,
+                                element_index,
+                            )?;
+                        }
                     }
-                    parent_state[1] |= 1 << element_index;
                 }
                 type_info
-                    if (type_info & 129 == 129
-                        || (type_info & 1 == 1
-                            && type_info & 48 != 0
-                            && type_info & 4096 == 0)) =>
+                    if (has(type_info, ACTIVITY | SEQUENTIAL_MULTI_INSTANCE)
+                        || (has(type_info, ACTIVITY)
+                            && has_any(type_info, CALL_ACTIVITY | SUB_PROCESS)
+                            && !has(type_info, EVENT_SUB_PROCESS))) =>
                 {
                     // If (0- Activity, 7- Sequential Multi-Instance) ||
                     // Sub-process(0- Activity, 5- Sub-process) or Call-Activity(0- Activity, 4- Call-Activity)
                     // but NOT Event Sub-process(12- Event Subprocess)
-                    Self::create_instance(element_index, parent_case)?;
-                    <IdataById<T>>::mutate(parent_case, |idata| {
-                        let instance_count = child_flow.get_instance_count(element_index);
-                        idata.set_instance_count(element_index, instance_count);
-                    });
-
-                    parent_state[1] |= 1 << element_index;
+                    let instance_count = if has(type_info, ACTIVITY | STANDARD_LOOP) {
+                        // One iteration at a time, unless the loop condition tested first fails
+                        let skipped = has(type_info, LOOP_TEST_BEFORE)
+                            && !Self::is_loop_continued(
+                                parent_case,
+                                &child_flow,
+                                element_index,
+                                0,
+                            )?;
+                        if skipped {
+                            0
+                        } else {
+                            1
+                        }
+                    } else {
+                        Self::multi_instance_cardinality(parent_case, &child_flow, element_index)?
+                    };
+                    if instance_count == 0 {
+                        // No instance to run, the activity completes right away
+                        Self::complete_element(parent_case, element_index, post_condition);
+                        Self::record_compensable(parent_case, &child_flow, element_index);
+                        parent_state[0] |= post_condition;
+                    } else {
+                        parent_state = match Self::start_sub_process(
+                            meter,
+                            parent_case,
+                            parent_state,
+                            element_index,
+                            instance_count,
+                            1,
+                        )? {
+                            Some(parent_state) => parent_state,
+                            // Ended by one of the sub-process instances
+                            None => return Ok(()),
+                        };
+                        if parent_state[1].contains(element_index) {
+                            Self::arm_boundary_events(
+                                meter,
+                                parent_case,
+                                &child_flow,
+                                element_index,
+                            )?;
+                        }
+                    }
                 }
                 type_info
-                    if (type_info & 4105 == 4105
-                        || (type_info & 10 == 2 && type_info & 80 != 0)) =>
+                    if (has(type_info, SCRIPT)
+                        || (type_info & (GATEWAY | JOIN) == GATEWAY
+                            && has_any(type_info, EXCLUSIVE | INCLUSIVE))) =>
                 {
                     // (0- Activity, 3- Task, 12- Script) ||
                     // Exclusive(XOR) Split (1- Gateway, 3- Split(0), 4- Exclusive) ||
                     // Inclusive(OR) Split (1- Gateway, 3- Split(0), 6- Inclusive)
-                    let factory = child_flow.get_factory_instance();
-
-                    let origin = T::Origin::from(RawOrigin::Root);
-                    let account_id = ensure_signed(origin)?;
-
-                    if let Some(address) = factory.get_address() {
-                        match <contracts::Module<T>>::bare_call(
-                            account_id,
-                            address.clone(),
-                            ENDOWMENT.into(),
-                            GAS.into(),
-                            factory.get_execute_script_selector().to_vec(),
-                        ) {
-                            Ok(result) => {
-                                parent_state[0] |= u128::decode(&mut &result.data[..])
-                                    .map_err(|_| DECODING_ERROR)?;
-                            }
-                            Err(e) => return Err(e.reason.into()),
-                        }
-                    }
+                    let tokens = T::ScriptExecutor::execute_script(
+                        parent_case,
+                        element_index,
+                        &parent_state[0],
+                    )?;
+                    Self::complete_element(parent_case, element_index, &tokens);
+                    Self::record_compensable(parent_case, &child_flow, element_index);
+                    parent_state[0] |= &tokens;
                 }
-                type_info
-                    if ((type_info & 9 == 9 && type_info & 27657 != 0) || type_info & 2 == 2) =>
-                {
+                type_info if has(type_info, ACTIVITY | TASK | RECEIVE_TASK) => {
+                    // (0- Activity, 3- Task, 14- Receive), the token waits for the message
+                    Self::subscribe_message(meter, parent_case, &child_flow, element_index)?;
+                    parent_state[1].insert(element_index);
+                }
+                type_info if has(type_info, GATEWAY | EVENT_BASED) => {
+                    // (1- Gateway, 7- Event-based), the token waits for the first of the events
+                    // it leads to
+                    Self::arm_event_based_gateway(
+                        meter,
+                        parent_case,
+                        &child_flow,
+                        element_index,
+                        &mut parent_state[1],
+                    )?;
+                }
+                type_info if (has(type_info, ACTIVITY | TASK) || has(type_info, GATEWAY)) => {
                     // If (User(11), Service(13), Receive(14) or Default(10) Task || Gateways(1) not XOR/OR Split)
                     // The execution of User/Service/Receive is triggered off-chain,
                     // Thus the starting point would be the data contract which executes any script/data-update related to the task.
+                    Self::complete_element(parent_case, element_index, post_condition);
+                    Self::record_compensable(parent_case, &child_flow, element_index);
                     parent_state[0] |= post_condition;
                 }
-                type_info if type_info & 12 == 12 => {
+                type_info if has(type_info, THROW_EVENT) => {
                     // If (2- Event, 3- Throw(1))
                     <IdataById<T>>::mutate(parent_case, |idata| {
-                        idata.set_marking(parent_state[0]);
-                        idata.set_activity_marking(parent_state[1]);
+                        idata.set_marking(parent_state[0].clone());
+                        idata.set_activity_marking(parent_state[1].clone());
                     });
-                    let event_code = child_flow.get_event_code(element_index);
-                    Self::throw_event(parent_case, &idata, event_code, type_info)?;
-                    let marking = idata.get_marking();
-                    let started_activities = idata.get_started_activities();
-                    if marking | started_activities == 0 {
-                        // By throwing the event, a kill was performed so the current instance was terminated
+                    if has(type_info, INTERMEDIATE) {
+                        Self::complete_element(parent_case, element_index, post_condition);
+                    } else {
+                        Self::complete_element(parent_case, element_index, &BitSet::new());
+                    }
+                    let event_code = child_flow.get_event_code(element_index)?;
+                    let payload = Self::thrown_payload(parent_case, &child_flow, element_index)?;
+                    let idata = Self::ensure_idata_instance_exists(parent_case)?;
+                    Self::throw_event(meter, parent_case, &idata, event_code, type_info, &payload)?;
+                    let idata = Self::ensure_idata_instance_exists(parent_case)?;
+                    if idata.get_status() != InstanceStatus::Running {
+                        // By throwing the event the current instance ended, or was terminated
                         return Ok(());
                     }
-                    parent_state[0] = marking;
-                    parent_state[1] = started_activities;
-                    if type_info & 128 == 128 {
+                    parent_state[0] = idata.get_marking().clone();
+                    parent_state[1] = idata.get_started_activities().clone();
+                    if has(type_info, INTERMEDIATE) {
                         // If Intermediate event (BIT 7)
                         parent_state[0] |= post_condition;
                     }
                 }
+                type_info if has(type_info, EVENT | INTERMEDIATE | TIMER) => {
+                    // If (2- Event, 7- Intermediate, 16- Timer), the token waits for the timer
+                    Self::arm_timer(meter, parent_case, &child_flow, element_index)?;
+                    parent_state[1].insert(element_index);
+                }
+                type_info if has(type_info, EVENT | INTERMEDIATE | MESSAGE) => {
+                    // If (2- Event, 7- Intermediate, 12- Message), the token waits for the message
+                    Self::subscribe_message(meter, parent_case, &child_flow, element_index)?;
+                    parent_state[1].insert(element_index);
+                }
+                type_info if has(type_info, EVENT | INTERMEDIATE | SIGNAL) => {
+                    // If (2- Event, 7- Intermediate, 15- Signal), the token waits for the signal
+                    // to be broadcast
+                    parent_state[1].insert(element_index);
+                }
                 _ => (),
             }
 
             // Adding the possible candidates to be executed to the queue.
             // The enablement of the element is checked at the moment it gets out of the queue.
             for next_elem in next {
-                queue[count] = *next_elem;
-                count = (count + 1) % 100;
+                Self::enqueue(meter, &mut queue, &mut pending, *next_elem);
             }
         }
 
         // Updating the state (storage) after the execution of each internal element.
+        let [marking, started_activities] = parent_state;
         <IdataById<T>>::mutate(parent_case, |idata| {
-            idata.set_marking(parent_state[0]);
-            idata.set_activity_marking(parent_state[1]);
+            idata.set_marking(marking);
+            idata.set_activity_marking(started_activities);
         });
+        Self::keep_pending(parent_case, pending);
         Ok(())
     }
 
-    fn ensure_iflow_instance_exists(instance_id: T::InstanceId) -> Result<Iflow<T>, &'static str> {
+    /// Records the element of the instance completing, and the sequence flows it put tokens on.
+    fn complete_element(instance_id: T::InstanceId, element_index: u128, tokens: &BitSet) {
+        Self::deposit_event(RawEvent::ElementCompleted(instance_id, element_index));
+        if !tokens.is_empty() {
+            Self::deposit_event(RawEvent::TokenMoved(
+                instance_id,
+                element_index,
+                tokens.clone(),
+            ));
+        }
+    }
+
+    /// Ends the instance with the status, unless it already ended. Root instances are pruned
+    /// once `Trait::InstanceRetention` blocks passed.
+    fn end_instance(instance_id: T::InstanceId, status: InstanceStatus) {
+        let ended = <IdataById<T>>::mutate(instance_id, |idata| match idata.get_status() {
+            InstanceStatus::Running | InstanceStatus::Suspended => {
+                idata.set_status(status);
+                Some(idata.get_idata_parent().is_none())
+            }
+            _ => None,
+        });
+        match ended {
+            Some(true) => Self::prune_later(instance_id),
+            Some(false) => (),
+            None => return,
+        }
+        match status {
+            InstanceStatus::Completed => {
+                Self::deposit_event(RawEvent::InstanceCompleted(instance_id))
+            }
+            _ => Self::deposit_event(RawEvent::InstanceTerminated(instance_id)),
+        }
+    }
+
+    fn enqueue(
+        meter: &mut ExecutionMeter,
+        queue: &mut VecDeque<u128>,
+        pending: &mut Vec<u128>,
+        element_index: u128,
+    ) {
+        if meter.queue::<T>() {
+            queue.push_back(element_index);
+        } else {
+            pending.push(element_index);
+        }
+    }
+
+    /// Ensures the origin is the admin origin or signed by the owner of the process model.
+    fn ensure_process_owner_or_admin(
+        origin: T::Origin,
+        process_id: T::InstanceId,
+    ) -> DispatchResult {
+        match T::AdminOrigin::try_origin(origin) {
+            Ok(_) => Ok(()),
+            Err(origin) => {
+                let who = ensure_signed(origin)?;
+                ensure!(
+                    Self::process_owner(process_id) == Some(who),
+                    Error::<T>::Unauthorized
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn ensure_iflow_instance_exists(instance_id: T::InstanceId) -> Result<Iflow<T>, Error<T>> {
         if <IflowById<T>>::contains_key(instance_id) {
             Ok(Self::iflow_by_id(instance_id))
         } else {
-            Err(INSTANCE_ID_NOT_FOUND)
+            Err(Error::<T>::IflowNotFound)
         }
     }
 
-    fn ensure_idata_instance_exists(instance_id: T::InstanceId) -> Result<Idata<T>, &'static str> {
+    fn ensure_idata_instance_exists(instance_id: T::InstanceId) -> Result<Idata<T>, Error<T>> {
         if <IdataById<T>>::contains_key(instance_id) {
             Ok(Self::idata_by_id(instance_id))
         } else {
-            Err(INSTANCE_ID_NOT_FOUND)
+            Err(Error::<T>::IdataNotFound)
+        }
+    }
+
+    fn ensure_process_model_is_valid(
+        elements: &[ElementDefinition],
+        links: &[SubProcessLink<T::InstanceId>],
+    ) -> DispatchResult {
+        let mut type_infos = BTreeMap::new();
+        for element in elements {
+            ensure!(
+                element.element_type.is_valid(),
+                Error::<T>::InvalidElementType
+            );
+            // Element indexes are stored in the started activities, edges in the markings
+            ensure!(
+                element.element_index < MAX_BIT_INDEX
+                    && element.pre_condition.max_index().unwrap_or(0) < MAX_BIT_INDEX
+                    && element.post_condition.max_index().unwrap_or(0) < MAX_BIT_INDEX,
+                Error::<T>::IndexOutOfBounds
+            );
+            ensure!(
+                type_infos
+                    .insert(element.element_index, element.element_type.type_info())
+                    .is_none(),
+                Error::<T>::DuplicateElement
+            );
+        }
+        ensure!(
+            type_infos
+                .values()
+                .any(|type_info| has(*type_info, EVENT | START)),
+            Error::<T>::StartEventNotFound
+        );
+        ensure!(
+            elements.iter().all(|element| element
+                .next_elem
+                .iter()
+                .all(|next| type_infos.contains_key(next))),
+            Error::<T>::ElementNotFound
+        );
+        // Event-based gateways lead to intermediate catch events only, of the kinds they arm
+        ensure!(
+            elements
+                .iter()
+                .filter(|element| has(type_infos[&element.element_index], GATEWAY | EVENT_BASED))
+                .all(|element| !element.next_elem.is_empty()
+                    && element.next_elem.iter().all(|next| {
+                        let type_info = type_infos[next];
+                        type_info & (THROW_EVENT | INTERMEDIATE) == EVENT | INTERMEDIATE
+                            && has_any(type_info, MESSAGE | SIGNAL | TIMER)
+                    })),
+            Error::<T>::InvalidEventBasedGateway
+        );
+
+        let mut linked = BTreeSet::new();
+        for link in links {
+            let parent_type_info = type_infos
+                .get(&link.parent_index)
+                .ok_or(Error::<T>::SubProcessToLinkNotFound)?;
+            ensure!(
+                has(*parent_type_info, ACTIVITY)
+                    && has_any(*parent_type_info, CALL_ACTIVITY | SUB_PROCESS)
+                    && linked.insert(link.parent_index),
+                Error::<T>::SubProcessToLinkNotFound
+            );
+            ensure!(
+                <IflowById<T>>::contains_key(link.child_flow),
+                Error::<T>::IflowNotFound
+            );
+            ensure!(
+                link.attached_events.iter().all(|event| type_infos
+                    .get(event)
+                    .map_or(false, |type_info| has(*type_info, EVENT))),
+                Error::<T>::InvalidAttachedEvent
+            );
+            ensure!(
+                link.count_instances <= MAX_SUB_PROCESSES as u128,
+                Error::<T>::TooManyInstances
+            );
         }
+        Ok(())
     }
 
     fn ensure_subprocess_to_link_in_data_structure(
         iflow: &Iflow<T>,
         parent_index: u128,
     ) -> DispatchResult {
-        //BITs (0, 5) Veryfing the subprocess to link is already in the data structure
+        //BITs (0, 4 or 5) Veryfing the subprocess to link is already in the data structure
+        let type_info = iflow.get_type_info(parent_index);
         ensure!(
-            iflow.get_type_info(parent_index) & 33 != 33,
-            SUBPROCESS_TO_LINK_NOT_FOUND
+            has(type_info, ACTIVITY) && has_any(type_info, CALL_ACTIVITY | SUB_PROCESS),
+            Error::<T>::SubProcessToLinkNotFound
         );
         Ok(())
     }
+
+    /// Ensures `iflow_index` is not a sub-process of `child_flow`, so that instantiating
+    /// sub-processes always terminates.
+    fn ensure_sub_process_is_not_cyclic(
+        iflow_index: T::InstanceId,
+        child_flow: T::InstanceId,
+    ) -> DispatchResult {
+        let mut visited = Vec::new();
+        let mut pending = vec![child_flow];
+        while let Some(flow) = pending.pop() {
+            ensure!(flow != iflow_index, Error::<T>::CyclicSubProcess);
+            if !visited.contains(&flow) {
+                ensure!(
+                    visited.len() < MAX_SUB_PROCESSES as usize,
+                    Error::<T>::WeightLimitExceeded
+                );
+                visited.push(flow);
+                pending.extend(Self::iflow_by_id(flow).get_sub_process_instances());
+            }
+        }
+        Ok(())
+    }
 }
 
 decl_event!(
@@ -1072,5 +2644,64 @@ decl_event!(
         FactorySet(InstanceId, Hash),
         NewCaseCreated(AccountId),
         MessageSent(Vec<u8>),
+        ProcessDeployed(InstanceId, AccountId),
+        /// Process model, previous and new owner
+        OwnershipTransferred(InstanceId, Option<AccountId>, AccountId),
+        ParticipantsSet(InstanceId),
+        /// Process model and timer event
+        TimerSet(InstanceId, u128),
+        /// Instance, or process model for timer start events, and timer event
+        TimerFired(InstanceId, u128),
+        /// Instance, or process model for timer start events, timer event and the error the
+        /// execution failed with
+        TimerFailed(InstanceId, u128, DispatchError),
+        CorrelationKeySet(InstanceId, Hash),
+        /// Instance, element that received the message and its payload
+        MessageReceived(InstanceId, u128, Vec<u8>),
+        /// Instance still progressing, with the number of elements left pending once the call
+        /// executing it was out of steps
+        ExecutionPending(InstanceId, u32),
+        /// Instance with pending elements and the error resuming it by `on_initialize` failed with
+        ExecutionResumeFailed(InstanceId, DispatchError),
+        /// Instance with pending elements left for `poke_instance` to resume, as `on_initialize`
+        /// failed to resume it `MAX_RESUME_ATTEMPTS` times
+        ExecutionResumeAbandoned(InstanceId),
+        /// Instance with pending elements left for `poke_instance` to resume, as
+        /// `on_initialize` already resumes `MAX_PENDING_INSTANCES` instances
+        PendingInstancesFull(InstanceId),
+        /// Instance whose every token reached an end event
+        InstanceCompleted(InstanceId),
+        /// Instance ended by a terminate end event, an interrupting event, the end of its parent
+        /// or an error no event caught, see its status
+        InstanceTerminated(InstanceId),
+        /// Instance and element that took the tokens of its incoming sequence flows
+        ElementStarted(InstanceId, u128),
+        /// Instance and element that completed, or caught its event
+        ElementCompleted(InstanceId, u128),
+        /// Instance, element the tokens come from and sequence flows they were put on
+        TokenMoved(InstanceId, u128, BitSet),
+        /// Root instance suspended along with its sub-process instances
+        InstanceSuspended(InstanceId),
+        /// Root instance resumed along with its sub-process instances
+        InstanceResumed(InstanceId),
+        /// Root instance cancelled, its sub-process instances are terminated
+        InstanceCancelled(InstanceId),
+        /// Instance removed from storage once its root instance was pruned, with the status
+        /// and the marking it ended with
+        InstanceArchived(InstanceId, InstanceStatus, BitSet),
+        /// Instance created and the process model it executes
+        InstanceCreated(InstanceId, InstanceId),
+        /// Process model and multi-instance sub-process
+        MultiInstanceSet(InstanceId, u128),
+        /// Process model and standard loop activity
+        LoopSet(InstanceId, u128),
+        /// Process model and activity given a compensation handler
+        CompensationSet(InstanceId, u128),
+        /// Instance and activity whose compensation handler ran
+        ActivityCompensated(InstanceId, u128),
+        /// Process model and error or escalation event given a payload selector
+        EventPayloadSet(InstanceId, u128),
+        /// Root instance failed, and the code of the error no event caught
+        InstanceFailed(InstanceId, [u8; 32]),
     }
 );
@@ -0,0 +1,78 @@
+//! Root instances suspended, resumed or cancelled along with their sub-process instances by
+//! the owner of their process model or the admin origin.
+
+use crate::{
+    Error, ExecutionMeter, Idata, IdataById, InstanceStatus, Module, PendingElements,
+    PendingInstances, Trait,
+};
+use frame_support::{dispatch::DispatchError, ensure, StorageMap, StorageValue};
+use sp_std::prelude::*;
+
+impl<T: Trait> Module<T> {
+    /// Ensures the instance exists and has no parent instance.
+    pub(crate) fn ensure_root_instance(instance_id: T::InstanceId) -> Result<Idata<T>, Error<T>> {
+        let idata = Self::ensure_idata_instance_exists(instance_id)?;
+        ensure!(
+            idata.get_idata_parent().is_none(),
+            Error::<T>::NotRootInstance
+        );
+        Ok(idata)
+    }
+
+    /// Root instance followed by the sub-process instances it created, directly or not,
+    /// visiting at most `MAX_SUB_PROCESSES` instances.
+    pub(crate) fn instance_tree(root: T::InstanceId) -> Result<Vec<T::InstanceId>, DispatchError> {
+        let mut meter = ExecutionMeter::default();
+        let mut tree = vec![root];
+        let mut visited = 0;
+        while visited < tree.len() {
+            meter.visit_instance::<T>()?;
+            let idata = Self::ensure_idata_instance_exists(tree[visited])?;
+            visited += 1;
+            for &child in idata.children.values().flatten() {
+                if !tree.contains(&child) {
+                    tree.push(child);
+                }
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Suspends the running instances of the tree, leaving them out of the instances
+    /// `on_initialize` resumes.
+    pub(crate) fn suspend_tree(tree: &[T::InstanceId]) {
+        for &instance_id in tree {
+            <IdataById<T>>::mutate(instance_id, |idata| {
+                if idata.get_status() == InstanceStatus::Running {
+                    idata.set_status(InstanceStatus::Suspended);
+                }
+            });
+        }
+        <PendingInstances<T>>::mutate(|instances| instances.retain(|id| !tree.contains(id)));
+    }
+
+    /// Resumes the suspended instances of the tree, those with pending elements are resumed by
+    /// `on_initialize`.
+    pub(crate) fn resume_tree(tree: &[T::InstanceId]) {
+        for &instance_id in tree {
+            let resumed = <IdataById<T>>::mutate(instance_id, |idata| {
+                let suspended = idata.get_status() == InstanceStatus::Suspended;
+                if suspended {
+                    idata.set_status(InstanceStatus::Running);
+                }
+                suspended
+            });
+            if resumed && <PendingElements<T>>::contains_key(instance_id) {
+                Self::list_pending(instance_id);
+            }
+        }
+    }
+
+    /// Keeps the elements the suspended instance was to execute pending until it is resumed.
+    pub(crate) fn keep_suspended(instance_id: T::InstanceId, elements: Vec<u128>) {
+        if elements.is_empty() {
+            return;
+        }
+        Self::add_pending(instance_id, elements);
+    }
+}
@@ -0,0 +1,29 @@
+//! Standard loop activities, whose iterations run while the data & scripts contract tells so,
+//! see `set_loop`.
+
+use crate::{Iflow, Module, ScriptExecutor, Trait, MAX_LOOP_ITERATIONS};
+use frame_support::dispatch::DispatchError;
+
+impl<T: Trait> Module<T> {
+    /// Whether the standard loop activity runs another iteration once `iterations` ran, never
+    /// beyond its maximum nor without a `LoopDefinition`.
+    pub(crate) fn is_loop_continued(
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+        iterations: u128,
+    ) -> Result<bool, DispatchError> {
+        let standard_loop = match iflow.get_loop(element_index) {
+            Some(standard_loop) => standard_loop,
+            None => return Ok(false),
+        };
+        let maximum = standard_loop.maximum.unwrap_or(MAX_LOOP_ITERATIONS);
+        if iterations >= maximum as u128 {
+            return Ok(false);
+        }
+        if standard_loop.condition_selector.is_empty() {
+            return Ok(true);
+        }
+        T::ScriptExecutor::is_loop_continued(instance_id, element_index, iterations)
+    }
+}
@@ -0,0 +1,102 @@
+//! Messages correlated to the process instances waiting for them.
+
+use crate::{ExecutionMeter, Iflow, MessageSubscriptions, Module, RawEvent, Trait};
+use frame_support::{
+    dispatch::{DispatchError, DispatchResult},
+    StorageDoubleMap,
+};
+
+impl<T: Trait> Module<T> {
+    /// Makes the instance wait on the element for the message named by its event code,
+    /// charged as an executed element.
+    pub(crate) fn subscribe_message(
+        meter: &mut ExecutionMeter,
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) -> DispatchResult {
+        let event_code = iflow.get_event_code(element_index)?;
+        meter.execute();
+        <MessageSubscriptions<T>>::mutate(instance_id, event_code, |elements| {
+            if !elements.contains(&element_index) {
+                elements.push(element_index);
+            }
+        });
+        Ok(())
+    }
+
+    /// Delivers the message to each instance correlated by the key, at the first element of the
+    /// instance or of its running sub-process instances that waits for it and that
+    /// `is_receiver` accepts. Returns the number of instances the message was delivered to.
+    pub(crate) fn correlate_message(
+        meter: &mut ExecutionMeter,
+        correlation_key: T::Hash,
+        event_code: [u8; 32],
+        payload: &[u8],
+        is_receiver: impl Fn(T::InstanceId, u128) -> bool,
+    ) -> Result<u32, DispatchError> {
+        let mut delivered = 0;
+        for instance_id in Self::correlated_instances(correlation_key) {
+            if Self::deliver_message(
+                meter,
+                instance_id,
+                instance_id,
+                event_code,
+                payload,
+                &is_receiver,
+            )? {
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn deliver_message(
+        meter: &mut ExecutionMeter,
+        correlated_instance: T::InstanceId,
+        parent_case: T::InstanceId,
+        event_code: [u8; 32],
+        payload: &[u8],
+        is_receiver: &impl Fn(T::InstanceId, u128) -> bool,
+    ) -> Result<bool, DispatchError> {
+        meter.visit_instance::<T>()?;
+        let idata = Self::ensure_idata_instance_exists(parent_case)?;
+        let iflow = Self::ensure_iflow_instance_exists(idata.get_flow_node())?;
+
+        for element_index in Self::message_subscriptions(parent_case, event_code) {
+            if !is_receiver(correlated_instance, element_index) {
+                continue;
+            }
+            <MessageSubscriptions<T>>::mutate(parent_case, event_code, |elements| {
+                elements.retain(|waiting| *waiting != element_index)
+            });
+            if Self::catch_waiting_event(meter, parent_case, &idata, &iflow, element_index)? {
+                Self::deposit_event(RawEvent::MessageReceived(
+                    parent_case,
+                    element_index,
+                    payload.to_vec(),
+                ));
+                return Ok(true);
+            }
+        }
+
+        let started_activities = idata.get_started_activities();
+        for &child in iflow.get_sub_process_list() {
+            if started_activities.contains(child) {
+                for &child_case in idata.get_child_process_instances(child)? {
+                    if Self::deliver_message(
+                        meter,
+                        correlated_instance,
+                        child_case,
+                        event_code,
+                        payload,
+                        is_receiver,
+                    )? {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,137 @@
+//! Storage migrations of the pallet.
+
+use super::*;
+use core::cell::Cell;
+use frame_support::{storage::IterableStorageMap, traits::Get, StorageValue};
+
+/// `Iflow` as stored by the original interpreter, pre and post conditions encoded as `u128`
+/// bitmasks.
+#[derive(Decode)]
+struct LegacyIflow<T: Trait> {
+    start_evt: u128,
+    cond_table: BTreeMap<u128, [u128; 3]>,
+    next_elem: BTreeMap<u128, Vec<u128>>,
+    subprocesses: Vec<u128>,
+    events: Vec<u128>,
+    attached_to: BTreeMap<u128, u128>,
+    event_code: BTreeMap<u128, [u8; 32]>,
+    parent_references: BTreeMap<u128, T::InstanceId>,
+    instance_count: BTreeMap<u128, u128>,
+    factory: Ifactory<T>,
+}
+
+/// `Idata` as stored by the original interpreter, markings encoded as `u128` bitmasks.
+#[derive(Decode)]
+struct LegacyIdata<T: Trait> {
+    tokens_on_edges: u128,
+    started_activities: u128,
+    idata_parent: Option<T::InstanceId>,
+    iflow_node: T::InstanceId,
+    index_in_parent: u128,
+    children: BTreeMap<u128, Vec<T::InstanceId>>,
+    instance_count: BTreeMap<u128, u128>,
+}
+
+impl<T: Trait> From<LegacyIflow<T>> for Iflow<T> {
+    fn from(iflow: LegacyIflow<T>) -> Self {
+        Self {
+            start_evt: iflow.start_evt,
+            cond_table: iflow
+                .cond_table
+                .into_iter()
+                .map(|(index, [pre_condition, post_condition, type_info])| {
+                    (
+                        index,
+                        (pre_condition.into(), post_condition.into(), type_info),
+                    )
+                })
+                .collect(),
+            next_elem: iflow.next_elem,
+            subprocesses: iflow.subprocesses,
+            events: iflow.events,
+            attached_to: iflow.attached_to,
+            event_code: iflow.event_code,
+            parent_references: iflow.parent_references,
+            instance_count: iflow.instance_count,
+            factory: iflow.factory,
+            timers: BTreeMap::new(),
+            multi_instances: BTreeMap::new(),
+            loops: BTreeMap::new(),
+            compensations: BTreeMap::new(),
+            event_payloads: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Trait> From<LegacyIdata<T>> for Idata<T> {
+    /// Instances are completed once nothing is left to execute, running otherwise.
+    fn from(idata: LegacyIdata<T>) -> Self {
+        let status = if idata.tokens_on_edges == 0 && idata.started_activities == 0 {
+            InstanceStatus::Completed
+        } else {
+            InstanceStatus::Running
+        };
+        Self {
+            tokens_on_edges: idata.tokens_on_edges.into(),
+            started_activities: idata.started_activities.into(),
+            idata_parent: idata.idata_parent,
+            iflow_node: idata.iflow_node,
+            index_in_parent: idata.index_in_parent,
+            children: idata.children,
+            instance_count: idata.instance_count,
+            status,
+            loop_counter: 0,
+            compensable: vec![],
+        }
+    }
+}
+
+/// Migrates the storage of the original interpreter to the current layout, returning the
+/// weight consumed.
+///
+/// Process models and instances are translated, and their counters seeded from the highest
+/// identifier in use: the original interpreter stored both under identifiers chosen by the
+/// caller. It recorded no deployer either, so migrated models have no `ProcessOwner`: only
+/// `Trait::AdminOrigin` can mutate them, or hand them over with `transfer_ownership`.
+pub fn migrate<T: Trait>() -> Weight {
+    let weight = T::DbWeight::get().reads(1);
+    if StorageVersion::get() != Releases::V1BitMask {
+        return weight;
+    }
+
+    let (iflows, last_process_id) = translate::<T, IflowById<T>, LegacyIflow<T>, _>();
+    let (idatas, last_instance_id) = translate::<T, IdataById<T>, LegacyIdata<T>, _>();
+    let shared_count = Module::<T>::instance_id_count();
+    <ProcessIdCount<T>>::put(highest::<T>(shared_count, last_process_id));
+    <InstanceIdCount<T>>::put(highest::<T>(shared_count, last_instance_id));
+    StorageVersion::put(Releases::V2BitSet);
+
+    let translated = iflows + idatas;
+    weight.saturating_add(T::DbWeight::get().reads_writes(1 + translated, 3 + translated))
+}
+
+/// Translates every value of the map from the `Old` layout, returning how many were
+/// translated and the highest key among them.
+fn translate<T, Map, Old, Value>() -> (u64, Option<T::InstanceId>)
+where
+    T: Trait,
+    Map: IterableStorageMap<T::InstanceId, Value>,
+    Old: Decode + Into<Value>,
+    Value: codec::FullCodec,
+{
+    let translated = Cell::new(0u64);
+    let last_id = Cell::new(None);
+    Map::translate(|id, old: Old| {
+        translated.set(translated.get() + 1);
+        last_id.set(Some(highest::<T>(id, last_id.get())));
+        Some(old.into())
+    });
+    (translated.get(), last_id.get())
+}
+
+fn highest<T: Trait>(id: T::InstanceId, other: Option<T::InstanceId>) -> T::InstanceId {
+    match other {
+        Some(other) if other.into() > id.into() => other,
+        _ => id,
+    }
+}
@@ -0,0 +1,341 @@
+//! Test runtime of the pallet: system, balances, timestamp and contracts, its scripts answered
+//! by `MockScripts`.
+
+use crate::{
+    BitSet, Error, GenesisConfig, Module, OwnerParticipantOrContract, ScriptExecutor, Trait,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+    dispatch::{DispatchError, DispatchResult},
+    impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types,
+    weights::{IdentityFee, Weight},
+};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, Hash, IdentityLookup},
+    Perbill,
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, VecDeque},
+};
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+
+mod bpmn_interpreter {
+    pub use crate::Event;
+}
+
+impl_outer_origin! {
+    pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        system<T>,
+        balances<T>,
+        contracts<T>,
+        bpmn_interpreter<T>,
+    }
+}
+
+impl_outer_dispatch! {
+    pub enum Call for Test where origin: Origin {
+        balances::Balances,
+        contracts::Contracts,
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Test;
+
+/// Address of a contract derived from its code, input data and instantiator, shared by
+/// contracts and the pallet.
+pub struct ContractAddressFor;
+
+impl contracts::ContractAddressFor<H256, u64> for ContractAddressFor {
+    fn contract_address_for(code_hash: &H256, data: &[u8], origin: &u64) -> u64 {
+        let hash = BlakeTwo256::hash_of(&(code_hash, data, origin));
+        let mut address = [0; 8];
+        address.copy_from_slice(&hash.as_bytes()[..8]);
+        u64::from_le_bytes(address)
+    }
+}
+
+pub struct TrieIdGenerator;
+
+impl contracts::TrieIdGenerator<u64> for TrieIdGenerator {
+    fn trie_id(account_id: &u64) -> contracts::TrieId {
+        account_id.to_le_bytes().to_vec()
+    }
+}
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub const MaximumBlockWeight: Weight = 1024;
+    pub const MaximumBlockLength: u32 = 2 * 1024;
+    pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = TestEvent;
+    type BlockHashCount = BlockHashCount;
+    type MaximumBlockWeight = MaximumBlockWeight;
+    type DbWeight = ();
+    type BlockExecutionWeight = ();
+    type ExtrinsicBaseWeight = ();
+    type MaximumExtrinsicWeight = MaximumBlockWeight;
+    type MaximumBlockLength = MaximumBlockLength;
+    type AvailableBlockRatio = AvailableBlockRatio;
+    type Version = ();
+    type ModuleToIndex = ();
+    type AccountData = balances::AccountData<u64>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type Event = TestEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+}
+
+parameter_types! {
+    pub const MinimumPeriod: u64 = 1;
+}
+
+impl timestamp::Trait for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = MinimumPeriod;
+}
+
+parameter_types! {
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl transaction_payment::Trait for Test {
+    type Currency = Balances;
+    type OnTransactionPayment = ();
+    type TransactionByteFee = TransactionByteFee;
+    type WeightToFee = IdentityFee<u64>;
+    type FeeMultiplierUpdate = ();
+}
+
+parameter_types! {
+    pub const TombstoneDeposit: u64 = 16;
+    pub const RentByteFee: u64 = 4;
+    pub const RentDepositOffset: u64 = 10_000;
+    pub const SurchargeReward: u64 = 150;
+}
+
+parameter_types! {
+    pub const MaxExecutionSteps: u32 = 250;
+    pub const InstanceDeposit: u64 = 100;
+    pub const InstanceRetention: u64 = 10;
+}
+
+impl contracts::Trait for Test {
+    type Time = Timestamp;
+    type Randomness = Randomness;
+    type Call = Call;
+    type Event = TestEvent;
+    type DetermineContractAddress = ContractAddressFor;
+    type TrieIdGenerator = TrieIdGenerator;
+    type RentPayment = ();
+    type SignedClaimHandicap = contracts::DefaultSignedClaimHandicap;
+    type TombstoneDeposit = TombstoneDeposit;
+    type StorageSizeOffset = contracts::DefaultStorageSizeOffset;
+    type RentByteFee = RentByteFee;
+    type RentDepositOffset = RentDepositOffset;
+    type SurchargeReward = SurchargeReward;
+    type MaxDepth = contracts::DefaultMaxDepth;
+    type MaxValueSize = contracts::DefaultMaxValueSize;
+}
+
+impl Trait for Test {
+    type Event = TestEvent;
+    type ContractAddressFor = ContractAddressFor;
+    type InstanceId = u64;
+    type AdminOrigin = system::EnsureRoot<u64>;
+    type ExecutionPermission = OwnerParticipantOrContract<Test>;
+    type ScriptExecutor = MockScripts;
+    type MaxExecutionSteps = MaxExecutionSteps;
+    type Currency = Balances;
+    type InstanceDeposit = InstanceDeposit;
+    type InstanceRetention = InstanceRetention;
+}
+
+pub type System = system::Module<Test>;
+pub type Balances = balances::Module<Test>;
+pub type Timestamp = timestamp::Module<Test>;
+pub type Randomness = randomness_collective_flip::Module<Test>;
+pub type Contracts = contracts::Module<Test>;
+pub type BpmnInterpreter = Module<Test>;
+
+thread_local! {
+    static SCRIPT_OUTPUTS: RefCell<BTreeMap<u128, VecDeque<BitSet>>> = RefCell::new(BTreeMap::new());
+    static SCRIPT_CALLS: RefCell<Vec<(u64, u128, BitSet)>> = RefCell::new(Vec::new());
+    static DATA_OUTPUTS: RefCell<BTreeMap<u128, VecDeque<Vec<u8>>>> = RefCell::new(BTreeMap::new());
+    static CAUGHT_PAYLOADS: RefCell<Vec<(u64, u128, Vec<u8>)>> = RefCell::new(Vec::new());
+}
+
+/// Scripts of the test runtime, answering with the tokens or the values given for the element
+/// instead of calling the data & scripts contract.
+pub struct MockScripts;
+
+impl MockScripts {
+    /// Queues the tokens the script of the element puts, each answering one execution in the
+    /// order they were queued.
+    pub fn answer(element_index: u128, tokens: BitSet) {
+        SCRIPT_OUTPUTS.with(|outputs| {
+            outputs
+                .borrow_mut()
+                .entry(element_index)
+                .or_default()
+                .push_back(tokens)
+        })
+    }
+
+    /// Queues the value the data & scripts contract answers for the element, to the
+    /// cardinality, completion condition, loop condition or thrown payload calls, in the order
+    /// they were queued.
+    pub fn answer_data<V: Encode>(element_index: u128, value: V) {
+        DATA_OUTPUTS.with(|outputs| {
+            outputs
+                .borrow_mut()
+                .entry(element_index)
+                .or_default()
+                .push_back(value.encode())
+        })
+    }
+
+    /// Scripts executed so far, with the instance, element and marking they were given.
+    pub fn calls() -> Vec<(u64, u128, BitSet)> {
+        SCRIPT_CALLS.with(|calls| calls.borrow().clone())
+    }
+
+    /// Payloads written so far, with the instance and the element that caught them.
+    pub fn caught_payloads() -> Vec<(u64, u128, Vec<u8>)> {
+        CAUGHT_PAYLOADS.with(|payloads| payloads.borrow().clone())
+    }
+
+    fn data<V: Decode>(element_index: u128) -> Result<V, DispatchError> {
+        DATA_OUTPUTS
+            .with(|outputs| {
+                outputs
+                    .borrow_mut()
+                    .get_mut(&element_index)
+                    .and_then(VecDeque::pop_front)
+            })
+            .ok_or_else(|| Error::<Test>::ScriptCallFailed.into())
+            .and_then(|output| {
+                V::decode(&mut &output[..]).map_err(|_| Error::<Test>::DecodingFailed.into())
+            })
+    }
+
+    fn reset() {
+        SCRIPT_OUTPUTS.with(|outputs| outputs.borrow_mut().clear());
+        SCRIPT_CALLS.with(|calls| calls.borrow_mut().clear());
+        DATA_OUTPUTS.with(|outputs| outputs.borrow_mut().clear());
+        CAUGHT_PAYLOADS.with(|payloads| payloads.borrow_mut().clear());
+    }
+}
+
+impl ScriptExecutor<u64> for MockScripts {
+    fn execute_script(
+        instance_id: u64,
+        element_index: u128,
+        marking: &BitSet,
+    ) -> Result<BitSet, DispatchError> {
+        SCRIPT_CALLS.with(|calls| {
+            calls
+                .borrow_mut()
+                .push((instance_id, element_index, marking.clone()))
+        });
+        // Failing as a trapped contract would once out of answers
+        SCRIPT_OUTPUTS
+            .with(|outputs| {
+                outputs
+                    .borrow_mut()
+                    .get_mut(&element_index)
+                    .and_then(VecDeque::pop_front)
+            })
+            .ok_or_else(|| Error::<Test>::ScriptCallFailed.into())
+    }
+
+    fn multi_instance_cardinality(_: u64, element_index: u128) -> Result<u128, DispatchError> {
+        Self::data(element_index)
+    }
+
+    fn is_multi_instance_complete(
+        _: u64,
+        element_index: u128,
+        _: u128,
+    ) -> Result<bool, DispatchError> {
+        Self::data(element_index)
+    }
+
+    fn is_loop_continued(_: u64, element_index: u128, _: u128) -> Result<bool, DispatchError> {
+        Self::data(element_index)
+    }
+
+    fn thrown_payload(_: u64, element_index: u128) -> Result<Vec<u8>, DispatchError> {
+        Self::data(element_index)
+    }
+
+    fn write_caught_payload(
+        instance_id: u64,
+        element_index: u128,
+        payload: &[u8],
+    ) -> DispatchResult {
+        CAUGHT_PAYLOADS.with(|payloads| {
+            payloads
+                .borrow_mut()
+                .push((instance_id, element_index, payload.to_vec()))
+        });
+        Ok(())
+    }
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    MockScripts::reset();
+    let mut storage = system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    balances::GenesisConfig::<Test> {
+        balances: vec![(ALICE, 1_000_000), (BOB, 1_000_000)],
+    }
+    .assimilate_storage(&mut storage)
+    .unwrap();
+    contracts::GenesisConfig {
+        current_schedule: Default::default(),
+    }
+    .assimilate_storage::<Test>(&mut storage)
+    .unwrap();
+    GenesisConfig::default()
+        .assimilate_storage::<Test>(&mut storage)
+        .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
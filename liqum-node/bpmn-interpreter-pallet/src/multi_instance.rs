@@ -0,0 +1,80 @@
+//! Multi-instance sub-processes whose number of instances and completion are decided at
+//! runtime by the data & scripts contract, see `set_multi_instance`.
+
+use crate::{
+    Error, ExecutionMeter, IdataById, Iflow, InstanceStatus, Module, ScriptExecutor, Trait,
+    MAX_SUB_PROCESSES,
+};
+use frame_support::{
+    dispatch::{DispatchError, DispatchResult},
+    ensure, StorageMap,
+};
+use sp_std::prelude::*;
+
+impl<T: Trait> Module<T> {
+    /// Number of instances of the sub-process to run, asked to the data & scripts contract
+    /// when its multi-instance characteristics tell so, as linked otherwise. Fails above
+    /// `MAX_SUB_PROCESSES`, more than a call of the interpreter can create.
+    pub(crate) fn multi_instance_cardinality(
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) -> Result<u128, DispatchError> {
+        let cardinality = match iflow.get_multi_instance(element_index) {
+            Some(multi_instance) if !multi_instance.cardinality_selector.is_empty() => {
+                T::ScriptExecutor::multi_instance_cardinality(instance_id, element_index)?
+            }
+            _ => iflow.get_instance_count(element_index)?,
+        };
+        ensure!(
+            cardinality <= MAX_SUB_PROCESSES as u128,
+            Error::<T>::TooManyInstances
+        );
+        Ok(cardinality)
+    }
+
+    /// Whether the completion condition of the multi-instance sub-process is met once its
+    /// instance numbered `loop_counter` completed, never without one.
+    pub(crate) fn is_multi_instance_complete(
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+        loop_counter: u128,
+    ) -> Result<bool, DispatchError> {
+        match iflow.get_multi_instance(element_index) {
+            Some(multi_instance) if !multi_instance.completion_selector.is_empty() => {
+                T::ScriptExecutor::is_multi_instance_complete(
+                    instance_id,
+                    element_index,
+                    loop_counter,
+                )
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Terminates the instances of the sub-process still running but the one completing it,
+    /// none are started after.
+    pub(crate) fn cancel_remaining_instances(
+        meter: &mut ExecutionMeter,
+        parent_case: T::InstanceId,
+        element_index: u128,
+        completing_case: T::InstanceId,
+    ) -> DispatchResult {
+        let idata = Self::ensure_idata_instance_exists(parent_case)?;
+        for &child_case in idata.get_child_process_instances(element_index)? {
+            if child_case == completing_case {
+                continue;
+            }
+            let status = Self::ensure_idata_instance_exists(child_case)?.get_status();
+            if status == InstanceStatus::Running || status == InstanceStatus::Suspended {
+                Self::kill_process(meter, child_case)?;
+                Self::end_instance(child_case, InstanceStatus::Terminated);
+            }
+        }
+        <IdataById<T>>::mutate(parent_case, |idata| {
+            idata.set_instance_count(element_index, 0)
+        });
+        Ok(())
+    }
+}
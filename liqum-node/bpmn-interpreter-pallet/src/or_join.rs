@@ -0,0 +1,327 @@
+//! Enablement analysis of Inclusive (OR) Join gateways.
+//!
+//! An OR-Join may fire once at least one of its incoming edges holds a token and no other
+//! token of the process instance can still reach one of its empty incoming edges without
+//! passing through the join itself. Reachability is computed over the `next_elem` graph,
+//! starting from every element holding a token outside the join and every started activity.
+
+use crate::BitSet;
+use crate::types::{has, OR_JOIN};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    prelude::*,
+};
+
+/// Returns whether the OR-Join at `join_index` is enabled under the given state.
+pub fn is_enabled(
+    cond_table: &BTreeMap<u128, (BitSet, BitSet, u128)>,
+    next_elem: &BTreeMap<u128, Vec<u128>>,
+    join_index: u128,
+    marking: &BitSet,
+    started_activities: &BitSet,
+) -> bool {
+    let join_pre_condition = match cond_table.get(&join_index) {
+        Some((pre_condition, _, _)) => pre_condition,
+        None => return false,
+    };
+    if !marking.intersects(join_pre_condition) {
+        return false;
+    }
+    let empty_edges = join_pre_condition - marking;
+    if empty_edges.is_empty() {
+        return true;
+    }
+    let outer_marking = marking - join_pre_condition;
+
+    // Elements able to move a token forward: enabled by a token not waiting at the join,
+    // or activities whose completion is still pending (i.e. running sub-processes).
+    let mut pending: Vec<u128> = cond_table
+        .iter()
+        .filter(|(&index, (pre_condition, _, _))| {
+            index != join_index
+                && (outer_marking.intersects(pre_condition) || started_activities.contains(index))
+        })
+        .map(|(&index, _)| index)
+        .collect();
+    let mut visited = BTreeSet::new();
+
+    while let Some(element_index) = pending.pop() {
+        if !visited.insert(element_index) {
+            continue;
+        }
+        let reaches_join = cond_table
+            .get(&element_index)
+            .map_or(false, |(_, post_condition, _)| {
+                post_condition.intersects(&empty_edges)
+            });
+        if reaches_join {
+            // A token upstream can still arrive at the join
+            return false;
+        }
+        if let Some(next) = next_elem.get(&element_index) {
+            pending.extend(
+                next.iter().filter(|&&next_index| {
+                    next_index != join_index && !visited.contains(&next_index)
+                }),
+            );
+        }
+    }
+    true
+}
+
+/// Returns the OR-Joins holding at least one token which are enabled under the given state.
+pub fn enabled_joins(
+    cond_table: &BTreeMap<u128, (BitSet, BitSet, u128)>,
+    next_elem: &BTreeMap<u128, Vec<u128>>,
+    marking: &BitSet,
+    started_activities: &BitSet,
+) -> Vec<u128> {
+    cond_table
+        .iter()
+        .filter(|(_, (pre_condition, _, type_info))| {
+            has(*type_info, OR_JOIN) && marking.intersects(pre_condition)
+        })
+        .map(|(&index, _)| index)
+        .filter(|&index| is_enabled(cond_table, next_elem, index, marking, started_activities))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASK: u128 = 1 << 0 | 1 << 3 | 1 << 11;
+    const OR_SPLIT: u128 = 1 << 1 | 1 << 6;
+    const XOR_SPLIT: u128 = 1 << 1 | 1 << 4;
+    const XOR_JOIN: u128 = 1 << 1 | 1 << 3 | 1 << 4;
+    const END: u128 = 1 << 2 | 1 << 3 | 1 << 9 | 1 << 10;
+
+    /// Set of edges given as a `u128` bitmask, wider models are covered by `wide_model`
+    fn edges(bits: u128) -> BitSet {
+        BitSet::from_bits(bits)
+    }
+
+    fn edge(index: u32) -> u128 {
+        1 << index
+    }
+
+    struct Model {
+        cond_table: BTreeMap<u128, (BitSet, BitSet, u128)>,
+        next_elem: BTreeMap<u128, Vec<u128>>,
+    }
+
+    impl Model {
+        fn new(elements: &[(u128, u128, u128, u128, &[u128])]) -> Self {
+            let mut cond_table = BTreeMap::new();
+            let mut next_elem = BTreeMap::new();
+            for &(index, pre_condition, post_condition, type_info, next) in elements {
+                cond_table.insert(
+                    index,
+                    (edges(pre_condition), edges(post_condition), type_info),
+                );
+                next_elem.insert(index, next.to_vec());
+            }
+            Self {
+                cond_table,
+                next_elem,
+            }
+        }
+
+        fn is_enabled(&self, join_index: u128, marking: u128, started_activities: u128) -> bool {
+            is_enabled(
+                &self.cond_table,
+                &self.next_elem,
+                join_index,
+                &edges(marking),
+                &edges(started_activities),
+            )
+        }
+    }
+
+    /// OR-Split (1) -> A (2), B (3) -> OR-Join (4) -> End (5)
+    ///
+    /// Edges: e0 split->A, e1 split->B, e2 A->join, e3 B->join, e4 join->end
+    fn simple_model() -> Model {
+        Model::new(&[
+            (1, 0, edge(0) | edge(1), OR_SPLIT, &[2, 3]),
+            (2, edge(0), edge(2), TASK, &[4]),
+            (3, edge(1), edge(3), TASK, &[4]),
+            (4, edge(2) | edge(3), edge(4), OR_JOIN, &[5]),
+            (5, edge(4), 0, END, &[]),
+        ])
+    }
+
+    #[test]
+    fn join_without_tokens_is_not_enabled() {
+        let model = simple_model();
+        assert!(!model.is_enabled(4, 0, 0));
+        assert!(!model.is_enabled(4, edge(0) | edge(1), 0));
+    }
+
+    #[test]
+    fn join_with_every_input_marked_is_enabled() {
+        let model = simple_model();
+        assert!(model.is_enabled(4, edge(2) | edge(3), 0));
+    }
+
+    #[test]
+    fn join_waits_for_token_upstream() {
+        let model = simple_model();
+        assert!(!model.is_enabled(4, edge(1) | edge(2), 0));
+        assert!(model.is_enabled(4, edge(2) | edge(3), 0));
+    }
+
+    #[test]
+    fn join_fires_when_only_one_branch_was_taken() {
+        let model = simple_model();
+        assert!(model.is_enabled(4, edge(2), 0));
+        assert!(model.is_enabled(4, edge(3), 0));
+    }
+
+    #[test]
+    fn join_ignores_tokens_that_cannot_reach_it() {
+        // An unrelated token on the end event edge cannot reach the join
+        let model = simple_model();
+        assert!(model.is_enabled(4, edge(2) | edge(4), 0));
+    }
+
+    #[test]
+    fn join_waits_for_started_sub_process() {
+        // B (3) is a running sub-process whose completion produces e3
+        let model = simple_model();
+        assert!(!model.is_enabled(4, edge(2), 1 << 3));
+        assert!(model.is_enabled(4, edge(2), 1 << 2));
+    }
+
+    /// OR-Split (1) -> A (2), OR-Split (3)
+    /// OR-Split (3) -> B (4), C (5) -> OR-Join (6)
+    /// OR-Join (6), A (2) -> OR-Join (7) -> End (8)
+    ///
+    /// Edges: e0 1->2, e1 1->3, e2 3->4, e3 3->5, e4 4->6, e5 5->6,
+    ///        e6 6->7, e7 2->7, e8 7->8
+    fn nested_model() -> Model {
+        Model::new(&[
+            (1, 0, edge(0) | edge(1), OR_SPLIT, &[2, 3]),
+            (2, edge(0), edge(7), TASK, &[7]),
+            (3, edge(1), edge(2) | edge(3), OR_SPLIT, &[4, 5]),
+            (4, edge(2), edge(4), TASK, &[6]),
+            (5, edge(3), edge(5), TASK, &[6]),
+            (6, edge(4) | edge(5), edge(6), OR_JOIN, &[7]),
+            (7, edge(6) | edge(7), edge(8), OR_JOIN, &[8]),
+            (8, edge(8), 0, END, &[]),
+        ])
+    }
+
+    #[test]
+    fn outer_join_waits_for_nested_branches() {
+        let model = nested_model();
+        // Inner branches still running, the outer join must wait
+        assert!(!model.is_enabled(7, edge(7) | edge(2), 0));
+        assert!(!model.is_enabled(7, edge(7) | edge(4) | edge(5), 0));
+        // Inner split not yet executed
+        assert!(!model.is_enabled(7, edge(7) | edge(1), 0));
+        // Inner join completed
+        assert!(model.is_enabled(7, edge(7) | edge(6), 0));
+    }
+
+    #[test]
+    fn inner_join_is_independent_from_outer_branch() {
+        let model = nested_model();
+        assert!(!model.is_enabled(6, edge(4) | edge(3) | edge(0), 0));
+        assert!(model.is_enabled(6, edge(4) | edge(0), 0));
+        assert!(model.is_enabled(6, edge(4) | edge(7), 0));
+    }
+
+    #[test]
+    fn enabled_joins_lists_only_marked_and_unblocked_joins() {
+        let model = nested_model();
+        let enabled = |marking| {
+            enabled_joins(
+                &model.cond_table,
+                &model.next_elem,
+                &edges(marking),
+                &BitSet::new(),
+            )
+        };
+        assert_eq!(enabled(edge(0) | edge(1)), Vec::<u128>::new());
+        assert_eq!(enabled(edge(4) | edge(0)), vec![6]);
+        assert_eq!(enabled(edge(4) | edge(7)), vec![6]);
+        assert_eq!(enabled(edge(6) | edge(7)), vec![7]);
+    }
+
+    /// Start edge e0 -> XOR-Join (1) -> A (2) -> OR-Split (3)
+    /// OR-Split (3) -> XOR-Split (4) (e3), B (5) (e4)
+    /// XOR-Split (4) -> back to XOR-Join (1) (e5), OR-Join (6) (e6)
+    /// B (5) -> OR-Join (6) (e7)
+    /// OR-Join (6) -> End (7) (e8)
+    ///
+    /// Edges: e1 1->2, e2 2->3
+    fn loop_model() -> Model {
+        Model::new(&[
+            (1, edge(0) | edge(5), edge(1), XOR_JOIN, &[2]),
+            (2, edge(1), edge(2), TASK, &[3]),
+            (3, edge(2), edge(3) | edge(4), OR_SPLIT, &[4, 5]),
+            (4, edge(3), edge(5) | edge(6), XOR_SPLIT, &[1, 6]),
+            (5, edge(4), edge(7), TASK, &[6]),
+            (6, edge(6) | edge(7), edge(8), OR_JOIN, &[7]),
+            (7, edge(8), 0, END, &[]),
+        ])
+    }
+
+    #[test]
+    fn join_waits_for_token_inside_loop() {
+        let model = loop_model();
+        // The token looping back can produce e6 again after another iteration
+        assert!(!model.is_enabled(6, edge(7) | edge(5), 0));
+        assert!(!model.is_enabled(6, edge(7) | edge(1), 0));
+        assert!(!model.is_enabled(6, edge(7) | edge(3), 0));
+    }
+
+    #[test]
+    fn join_fires_once_loop_is_left() {
+        let model = loop_model();
+        assert!(model.is_enabled(6, edge(7) | edge(6), 0));
+        assert!(model.is_enabled(6, edge(6), 0));
+    }
+
+    #[test]
+    fn join_waits_for_its_own_loop_only_through_other_elements() {
+        // A token on e2 reaches the OR-Split, which may produce both inputs again
+        let model = loop_model();
+        assert!(!model.is_enabled(6, edge(6) | edge(2), 0));
+    }
+
+    #[test]
+    fn join_on_edges_and_elements_beyond_128() {
+        // OR-Split (200) -> A (300), B (301) -> OR-Join (400), on edges 150..153
+        let mut cond_table = BTreeMap::new();
+        let mut next_elem = BTreeMap::new();
+        let wide = |indexes: &[u128]| {
+            let mut set = BitSet::new();
+            for index in indexes {
+                set.insert(*index);
+            }
+            set
+        };
+        let elements: [(u128, &[u128], &[u128], u128, &[u128]); 4] = [
+            (200, &[], &[150, 151], OR_SPLIT, &[300, 301]),
+            (300, &[150], &[152], TASK, &[400]),
+            (301, &[151], &[153], TASK, &[400]),
+            (400, &[152, 153], &[], OR_JOIN, &[]),
+        ];
+        for (index, pre_condition, post_condition, type_info, next) in elements.iter() {
+            cond_table.insert(
+                *index,
+                (wide(pre_condition), wide(post_condition), *type_info),
+            );
+            next_elem.insert(*index, next.to_vec());
+        }
+        let enabled = |marking: &[u128], started: &[u128]| {
+            is_enabled(&cond_table, &next_elem, 400, &wide(marking), &wide(started))
+        };
+        assert!(!enabled(&[152, 151], &[]));
+        assert!(enabled(&[152], &[]));
+        assert!(!enabled(&[152], &[301]));
+        assert!(enabled(&[152, 153], &[]));
+    }
+}
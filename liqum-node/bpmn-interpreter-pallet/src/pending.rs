@@ -0,0 +1,115 @@
+//! Elements left pending once a call of the interpreter is out of steps, resumed by
+//! `poke_instance` or by `on_initialize`.
+
+use crate::{
+    Error, ExecutionMeter, Module, PendingElements, PendingInstances, RawEvent, ResumeAttempts,
+    Trait, MAX_PENDING_INSTANCES, MAX_RESUMED_PER_BLOCK, MAX_RESUME_ATTEMPTS,
+};
+use frame_support::{dispatch::DispatchResult, transactional, StorageMap, StorageValue};
+use sp_std::prelude::*;
+
+impl<T: Trait> Module<T> {
+    /// Keeps the elements pending for the instance, after those already pending.
+    pub(crate) fn keep_pending(instance_id: T::InstanceId, elements: Vec<u128>) {
+        if elements.is_empty() {
+            return;
+        }
+        let pending = Self::add_pending(instance_id, elements);
+        Self::list_pending(instance_id);
+        Self::deposit_event(RawEvent::ExecutionPending(instance_id, pending));
+    }
+
+    /// Adds the elements to those pending for the instance, returning how many are pending.
+    /// Their enablement is checked once resumed, so an element is kept pending at most once
+    /// and the pending elements of an instance are bounded by those of its process model.
+    pub(crate) fn add_pending(instance_id: T::InstanceId, elements: Vec<u128>) -> u32 {
+        <PendingElements<T>>::mutate(instance_id, |pending| {
+            for element_index in elements {
+                if !pending.contains(&element_index) {
+                    pending.push(element_index);
+                }
+            }
+            pending.len() as u32
+        })
+    }
+
+    /// Lists the instance among those `on_initialize` resumes, unless there are already
+    /// `MAX_PENDING_INSTANCES` of them and it is left for `poke_instance`.
+    pub(crate) fn list_pending(instance_id: T::InstanceId) {
+        let listed = <PendingInstances<T>>::mutate(|instances| {
+            if instances.contains(&instance_id) {
+                true
+            } else if instances.len() < MAX_PENDING_INSTANCES as usize {
+                instances.push(instance_id);
+                true
+            } else {
+                false
+            }
+        });
+        if !listed {
+            Self::deposit_event(RawEvent::PendingInstancesFull(instance_id));
+        }
+    }
+
+    /// Executes the elements pending for the instance. Nothing a failed execution wrote is
+    /// kept, its elements stay pending.
+    #[transactional]
+    pub(crate) fn resume_execution(
+        meter: &mut ExecutionMeter,
+        instance_id: T::InstanceId,
+    ) -> DispatchResult {
+        let candidates = <PendingElements<T>>::take(instance_id);
+        <PendingInstances<T>>::mutate(|instances| instances.retain(|id| *id != instance_id));
+        <ResumeAttempts<T>>::remove(instance_id);
+        Self::execute_queue(meter, instance_id, candidates)
+    }
+
+    /// Resumes the instances pending the longest, each at most once and until the call of the
+    /// interpreter shared with the timers fired is exhausted, returning how many were resumed.
+    pub(crate) fn resume_pending_instances(meter: &mut ExecutionMeter) -> u32 {
+        let instances = Self::pending_instances();
+        let mut resumed = 0;
+        for &instance_id in instances.iter().take(MAX_RESUMED_PER_BLOCK as usize) {
+            if meter.is_exhausted::<T>() {
+                break;
+            }
+            resumed += 1;
+            // Killed since its elements were left pending
+            if !<PendingElements<T>>::contains_key(instance_id) {
+                <PendingInstances<T>>::mutate(|instances| {
+                    instances.retain(|id| *id != instance_id)
+                });
+                continue;
+            }
+            let shared = meter.instances > 0;
+            if let Err(error) = Self::resume_execution(meter, instance_id) {
+                Self::deposit_event(RawEvent::ExecutionResumeFailed(instance_id, error));
+                // Out of the instances the timers or other instances left, not its own failure
+                let attempt = !(shared && error == Error::<T>::WeightLimitExceeded.into());
+                Self::retry_later(instance_id, attempt);
+            }
+        }
+        resumed
+    }
+
+    /// Moves the instance that failed to resume after the other pending instances, or leaves it
+    /// for `poke_instance` once `MAX_RESUME_ATTEMPTS` failed in a row.
+    fn retry_later(instance_id: T::InstanceId, attempt: bool) {
+        let attempts = <ResumeAttempts<T>>::mutate(instance_id, |attempts| {
+            if attempt {
+                *attempts += 1;
+            }
+            *attempts
+        });
+        <PendingInstances<T>>::mutate(|instances| {
+            instances.retain(|id| *id != instance_id);
+            if attempts < MAX_RESUME_ATTEMPTS {
+                instances.push(instance_id);
+            }
+        });
+        if attempts >= MAX_RESUME_ATTEMPTS {
+            <ResumeAttempts<T>>::remove(instance_id);
+            Self::deposit_event(RawEvent::ExecutionResumeAbandoned(instance_id));
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Permission checks on the execution of process instances.
+
+use crate::{IdataById, IflowById, Module, Trait};
+use frame_support::StorageMap;
+use sp_std::marker::PhantomData;
+
+/// Decides which accounts may continue the execution of a process instance.
+pub trait ExecutionPermission<AccountId, InstanceId> {
+    fn can_continue(who: &AccountId, instance_id: InstanceId, element_index: u128) -> bool;
+}
+
+/// Anyone may continue the execution of any instance.
+impl<AccountId, InstanceId> ExecutionPermission<AccountId, InstanceId> for () {
+    fn can_continue(_: &AccountId, _: InstanceId, _: u128) -> bool {
+        true
+    }
+}
+
+/// Allows the owner of the process model, the participants assigned to the instance and the
+/// data & scripts contract of the instance.
+pub struct OwnerParticipantOrContract<T>(PhantomData<T>);
+
+impl<T: Trait> ExecutionPermission<T::AccountId, T::InstanceId> for OwnerParticipantOrContract<T> {
+    fn can_continue(who: &T::AccountId, instance_id: T::InstanceId, _: u128) -> bool {
+        if !<IdataById<T>>::contains_key(instance_id) {
+            return false;
+        }
+        let process_id = <IdataById<T>>::get(instance_id).get_flow_node();
+        Module::<T>::process_owner(process_id).as_ref() == Some(who)
+            || Module::<T>::participants(instance_id).contains(who)
+            || <IflowById<T>>::get(process_id)
+                .get_factory_instance()
+                .get_address()
+                .as_ref()
+                == Some(who)
+    }
+}
@@ -0,0 +1,114 @@
+//! Ended root instances removed from storage along with their sub-process instances, by
+//! `prune_instance` or by `on_initialize` once `Trait::InstanceRetention` blocks passed, and
+//! the deposits reserved for them.
+//!
+//! Only the storage of this pallet is pruned. The data & scripts contract of a process model
+//! is instantiated once and shared by all its instances, see `Ifactory::new_instance`, so it
+//! outlives them along with whatever it stored for them.
+
+use crate::{
+    weights, BalanceOf, CorrelatedInstances, CorrelationKey, EndedInstances, IdataById,
+    InstanceDeposits, InstanceStatus, MessageSubscriptions, Module, Participants, PendingElements,
+    PendingInstances, RawEvent, ResumeAttempts, Trait, MAX_ENDED_INSTANCES, MAX_PRUNED_PER_BLOCK,
+};
+use frame_support::{
+    dispatch::DispatchResult,
+    traits::{Get, ReservableCurrency},
+    weights::Weight,
+    StorageDoubleMap, StorageMap, StorageValue,
+};
+use sp_runtime::traits::Saturating;
+use sp_std::prelude::*;
+
+impl<T: Trait> Module<T> {
+    /// Reserves the deposit for the root instance from the account that created it.
+    pub(crate) fn reserve_deposit(
+        instance_id: T::InstanceId,
+        who: T::AccountId,
+        deposit: BalanceOf<T>,
+    ) -> DispatchResult {
+        <T as Trait>::Currency::reserve(&who, deposit)?;
+        <InstanceDeposits<T>>::insert(instance_id, (who, deposit));
+        Ok(())
+    }
+
+    /// Queues the ended root instance to be pruned by `on_initialize`, unless too many are.
+    pub(crate) fn prune_later(instance_id: T::InstanceId) {
+        let now = <frame_system::Module<T>>::block_number();
+        <EndedInstances<T>>::mutate(|instances| {
+            if instances.len() < MAX_ENDED_INSTANCES as usize {
+                instances.push((now, instance_id));
+            }
+        });
+    }
+
+    /// Removes the instances of the tree and everything stored for them by the pallet,
+    /// refunding the deposit reserved for the root instance, the first one.
+    pub(crate) fn prune_tree(tree: &[T::InstanceId]) {
+        for &instance_id in tree {
+            let idata = <IdataById<T>>::take(instance_id);
+            Self::disarm_timers(instance_id);
+            <MessageSubscriptions<T>>::remove_prefix(instance_id);
+            <PendingElements<T>>::remove(instance_id);
+            <ResumeAttempts<T>>::remove(instance_id);
+            <Participants<T>>::remove(instance_id);
+            if let Some(correlation_key) = <CorrelationKey<T>>::take(instance_id) {
+                let mut correlated = Self::correlated_instances(correlation_key);
+                correlated.retain(|correlated| *correlated != instance_id);
+                if correlated.is_empty() {
+                    <CorrelatedInstances<T>>::remove(correlation_key);
+                } else {
+                    <CorrelatedInstances<T>>::insert(correlation_key, correlated);
+                }
+            }
+            Self::deposit_event(RawEvent::InstanceArchived(
+                instance_id,
+                idata.get_status(),
+                idata.get_marking().clone(),
+            ));
+        }
+        <PendingInstances<T>>::mutate(|instances| instances.retain(|id| !tree.contains(id)));
+        <EndedInstances<T>>::mutate(|instances| instances.retain(|(_, id)| !tree.contains(id)));
+        if let Some(root) = tree.first() {
+            if let Some((depositor, deposit)) = <InstanceDeposits<T>>::take(root) {
+                <T as Trait>::Currency::unreserve(&depositor, deposit);
+            }
+        }
+    }
+
+    /// Prunes the root instances ended the longest ago, once kept for
+    /// `Trait::InstanceRetention` blocks, returning the weight consumed.
+    pub(crate) fn prune_ended_instances(now: T::BlockNumber) -> Weight {
+        let retention = T::InstanceRetention::get();
+        let mut pruned = 0;
+        let mut instances = 0;
+        for (ended_at, instance_id) in Self::ended_instances()
+            .into_iter()
+            .take(MAX_PRUNED_PER_BLOCK as usize)
+        {
+            if ended_at.saturating_add(retention) > now {
+                break;
+            }
+            pruned += 1;
+            let tree = Self::ensure_root_instance(instance_id)
+                .ok()
+                // Replaced by an instance still running since it ended
+                .filter(|idata| {
+                    idata.get_status() != InstanceStatus::Running
+                        && idata.get_status() != InstanceStatus::Suspended
+                })
+                .and_then(|_| Self::instance_tree(instance_id).ok());
+            match tree {
+                Some(tree) => {
+                    instances += tree.len() as u32;
+                    Self::prune_tree(&tree);
+                }
+                // Pruned already, or with too many sub-process instances to prune at once
+                None => <EndedInstances<T>>::mutate(|instances| {
+                    instances.retain(|(_, id)| *id != instance_id)
+                }),
+            }
+        }
+        weights::prune_ended_instances::<T>(pruned, instances)
+    }
+}
@@ -0,0 +1,531 @@
+//! Scenarios running whole process models from their root instance, the invoice example and
+//! the canonical workflow patterns, checking the marking of the instance after each step. Their
+//! scripts are answered by `MockScripts`.
+
+use crate::{
+    mock::*,
+    tests::{element, end_event, link, start_event},
+    types::*,
+    *,
+};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::H256;
+
+fn receive_task(
+    element_index: u128,
+    pre_condition: u128,
+    post_condition: u128,
+    message: u8,
+    next_elem: Vec<u128>,
+) -> ElementDefinition {
+    let mut task = element(
+        element_index,
+        pre_condition,
+        post_condition,
+        ElementType::Task(TaskKind::Receive),
+        next_elem,
+    );
+    task.event_code = [message; 32];
+    task
+}
+
+fn gateway(kind: GatewayKind, join: bool) -> ElementType {
+    ElementType::Gateway { kind, join }
+}
+
+/// Deploys the process model, its data & scripts contract already instantiated.
+fn deploy_model(elements: Vec<ElementDefinition>) -> u64 {
+    deploy_linked(elements, vec![])
+}
+
+/// Deploys the process model with its sub-processes linked to the models they run.
+fn deploy_linked(elements: Vec<ElementDefinition>, links: Vec<SubProcessLink<u64>>) -> u64 {
+    assert_ok!(BpmnInterpreter::deploy_process(
+        Origin::signed(ALICE),
+        elements,
+        links,
+        Default::default(),
+        vec![],
+        vec![],
+    ));
+    let process_id = BpmnInterpreter::process_id_count();
+    <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+    process_id
+}
+
+/// Creates a root instance of the process model, correlated by its own key.
+fn start(process_id: u64) -> u64 {
+    assert_ok!(BpmnInterpreter::create_root_instance(
+        Origin::signed(ALICE),
+        process_id
+    ));
+    let instance_id = BpmnInterpreter::instance_id_count();
+    correlate(instance_id);
+    instance_id
+}
+
+/// Correlates the instance by its own key.
+fn correlate(instance_id: u64) {
+    assert_ok!(BpmnInterpreter::set_correlation_key(
+        Origin::signed(ALICE),
+        instance_id,
+        correlation_key(instance_id)
+    ));
+}
+
+/// Creates a root instance of the process model, returning it with the last sub-process
+/// instance it started, correlated by its own key.
+fn start_with_child(process_id: u64) -> (u64, u64) {
+    let root = BpmnInterpreter::instance_id_count() + 1;
+    let child = start(process_id);
+    assert_ne!(root, child);
+    (root, child)
+}
+
+/// Last sub-process instance created, correlated by its own key.
+fn next_child() -> u64 {
+    let child = BpmnInterpreter::instance_id_count();
+    correlate(child);
+    child
+}
+
+fn correlation_key(instance_id: u64) -> H256 {
+    H256::from_low_u64_be(instance_id)
+}
+
+fn send(instance_id: u64, message: u8) -> DispatchResult {
+    BpmnInterpreter::send_message(
+        Origin::signed(ALICE),
+        correlation_key(instance_id),
+        [message; 32],
+        vec![],
+    )
+    .map(|_| ())
+    .map_err(|e| e.error)
+}
+
+/// Asserts the tokens on the sequence flows of the instance and its started activities.
+fn assert_marking(instance_id: u64, tokens: u128, started_activities: &[u128]) {
+    let idata = BpmnInterpreter::idata_by_id(instance_id);
+    let mut started = BitSet::new();
+    for &element_index in started_activities {
+        started.insert(element_index);
+    }
+    assert_eq!(idata.get_marking(), &BitSet::from(tokens));
+    assert_eq!(idata.get_started_activities(), &started);
+}
+
+fn status(instance_id: u64) -> InstanceStatus {
+    BpmnInterpreter::idata_by_id(instance_id).get_status()
+}
+
+#[test]
+fn invoice_is_paid_once_accepted() {
+    new_test_ext().execute_with(|| {
+        const CHECKED: u8 = 1;
+        const CORRECTED: u8 = 2;
+        let user_task = ElementType::Task(TaskKind::User);
+        // Entered (2) then checked (3), the script of the gateway (5) sending it to payment (6)
+        // if accepted or back to correction (7) otherwise, as the `invoice_managing_data`
+        // contract does
+        let process_id = deploy_model(vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            element(2, 0b1, 0b10, user_task, vec![4]),
+            element(
+                4,
+                0b1000_0010,
+                0b100,
+                gateway(GatewayKind::Exclusive, true),
+                vec![3],
+            ),
+            receive_task(3, 0b100, 0b1000, CHECKED, vec![5]),
+            element(
+                5,
+                0b1000,
+                0b110_0000,
+                gateway(GatewayKind::Exclusive, false),
+                vec![6, 7],
+            ),
+            element(6, 0b10_0000, 0b1_0000, user_task, vec![8]),
+            receive_task(7, 0b100_0000, 0b1000_0000, CORRECTED, vec![4]),
+            element(8, 0b1_0000, 0, end_event(), vec![]),
+        ]);
+        // Rejected first, then accepted
+        MockScripts::answer(5, BitSet::from(0b100_0000));
+        MockScripts::answer(5, BitSet::from(0b10_0000));
+
+        let instance_id = start(process_id);
+        assert_marking(instance_id, 0, &[3]);
+        assert_noop!(
+            send(instance_id, CORRECTED),
+            Error::<Test>::MessageNotCorrelated
+        );
+
+        assert_ok!(send(instance_id, CHECKED));
+        assert_marking(instance_id, 0, &[7]);
+
+        assert_ok!(send(instance_id, CORRECTED));
+        assert_marking(instance_id, 0, &[3]);
+
+        assert_ok!(send(instance_id, CHECKED));
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(status(instance_id), InstanceStatus::Completed);
+        assert_eq!(
+            MockScripts::calls(),
+            vec![
+                (instance_id, 5, BitSet::new()),
+                (instance_id, 5, BitSet::new())
+            ]
+        );
+    });
+}
+
+#[test]
+fn sequence_runs_its_activities_one_after_the_other() {
+    new_test_ext().execute_with(|| {
+        const FIRST: u8 = 1;
+        const SECOND: u8 = 2;
+        let process_id = deploy_model(vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            receive_task(2, 0b1, 0b10, FIRST, vec![3]),
+            receive_task(3, 0b10, 0b100, SECOND, vec![4]),
+            element(4, 0b100, 0, end_event(), vec![]),
+        ]);
+
+        let instance_id = start(process_id);
+        assert_marking(instance_id, 0, &[2]);
+        assert_noop!(
+            send(instance_id, SECOND),
+            Error::<Test>::MessageNotCorrelated
+        );
+
+        assert_ok!(send(instance_id, FIRST));
+        assert_marking(instance_id, 0, &[3]);
+
+        assert_ok!(send(instance_id, SECOND));
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(status(instance_id), InstanceStatus::Completed);
+    });
+}
+
+/// Parallel split (2) into two receive tasks, synchronized by a parallel join (5).
+fn parallel_model(first: u8, second: u8) -> Vec<ElementDefinition> {
+    vec![
+        element(1, 0, 0b1, start_event(), vec![2]),
+        element(
+            2,
+            0b1,
+            0b110,
+            gateway(GatewayKind::Parallel, false),
+            vec![3, 4],
+        ),
+        receive_task(3, 0b10, 0b1000, first, vec![5]),
+        receive_task(4, 0b100, 0b1_0000, second, vec![5]),
+        element(
+            5,
+            0b1_1000,
+            0b10_0000,
+            gateway(GatewayKind::Parallel, true),
+            vec![6],
+        ),
+        element(6, 0b10_0000, 0, end_event(), vec![]),
+    ]
+}
+
+#[test]
+fn parallel_split_branches_are_synchronized() {
+    new_test_ext().execute_with(|| {
+        const FIRST: u8 = 1;
+        const SECOND: u8 = 2;
+        let process_id = deploy_model(parallel_model(FIRST, SECOND));
+
+        let instance_id = start(process_id);
+        assert_marking(instance_id, 0, &[3, 4]);
+
+        // The join waits for the other branch
+        assert_ok!(send(instance_id, SECOND));
+        assert_marking(instance_id, 0b1_0000, &[3]);
+
+        assert_ok!(send(instance_id, FIRST));
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(status(instance_id), InstanceStatus::Completed);
+    });
+}
+
+#[test]
+fn exclusive_choice_takes_the_branch_its_script_chooses() {
+    new_test_ext().execute_with(|| {
+        const FIRST: u8 = 1;
+        const SECOND: u8 = 2;
+        let process_id = deploy_model(vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            element(
+                2,
+                0b1,
+                0b110,
+                gateway(GatewayKind::Exclusive, false),
+                vec![3, 4],
+            ),
+            receive_task(3, 0b10, 0b1000, FIRST, vec![5]),
+            receive_task(4, 0b100, 0b1_0000, SECOND, vec![6]),
+            element(5, 0b1000, 0, end_event(), vec![]),
+            element(6, 0b1_0000, 0, end_event(), vec![]),
+        ]);
+        MockScripts::answer(2, BitSet::from(0b100));
+        MockScripts::answer(2, BitSet::from(0b10));
+
+        let second = start(process_id);
+        assert_marking(second, 0, &[4]);
+        assert_noop!(send(second, FIRST), Error::<Test>::MessageNotCorrelated);
+        assert_ok!(send(second, SECOND));
+        assert_marking(second, 0, &[]);
+        assert_eq!(status(second), InstanceStatus::Completed);
+
+        let first = start(process_id);
+        assert_marking(first, 0, &[3]);
+        assert_ok!(send(first, FIRST));
+        assert_marking(first, 0, &[]);
+        assert_eq!(status(first), InstanceStatus::Completed);
+
+        // Without an output for its script, the gateway fails
+        assert_eq!(
+            BpmnInterpreter::create_root_instance(Origin::signed(ALICE), process_id)
+                .map_err(|e| e.error),
+            Err(Error::<Test>::ScriptCallFailed.into())
+        );
+    });
+}
+
+#[test]
+fn multi_merge_runs_the_merging_activity_for_each_branch() {
+    new_test_ext().execute_with(|| {
+        const FIRST: u8 = 1;
+        let task = ElementType::Task(TaskKind::Default);
+        let process_id = deploy_model(vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            element(
+                2,
+                0b1,
+                0b110,
+                gateway(GatewayKind::Parallel, false),
+                vec![3, 4],
+            ),
+            receive_task(3, 0b10, 0b1000, FIRST, vec![5]),
+            element(4, 0b100, 0b1_0000, task, vec![5]),
+            element(5, 0b1_1000, 0b10_0000, task, vec![6]),
+            element(6, 0b10_0000, 0, end_event(), vec![]),
+        ]);
+        let merged = |instance_id| {
+            System::events()
+                .iter()
+                .filter(|record| {
+                    record.event
+                        == TestEvent::bpmn_interpreter(RawEvent::ElementCompleted(instance_id, 5))
+                })
+                .count()
+        };
+
+        // The second branch runs through the merge and the end event, the first still waits
+        let instance_id = start(process_id);
+        assert_marking(instance_id, 0, &[3]);
+        assert_eq!(merged(instance_id), 1);
+        assert_eq!(status(instance_id), InstanceStatus::Running);
+
+        assert_ok!(send(instance_id, FIRST));
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(merged(instance_id), 2);
+        assert_eq!(status(instance_id), InstanceStatus::Completed);
+    });
+}
+
+#[test]
+fn cancelled_cases_no_longer_wait() {
+    new_test_ext().execute_with(|| {
+        const FIRST: u8 = 1;
+        const SECOND: u8 = 2;
+        let process_id = deploy_model(parallel_model(FIRST, SECOND));
+
+        let instance_id = start(process_id);
+        assert_ok!(send(instance_id, FIRST));
+        assert_marking(instance_id, 0b1000, &[4]);
+
+        assert_ok!(BpmnInterpreter::cancel_instance(
+            Origin::signed(ALICE),
+            instance_id
+        ));
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(status(instance_id), InstanceStatus::Terminated);
+        assert_noop!(
+            send(instance_id, SECOND),
+            Error::<Test>::MessageNotCorrelated
+        );
+    });
+}
+
+/// Process model running the sub-process 2, which waits for the message on the receive task 2
+/// of the model linked.
+fn sub_process_model(multi_instance: MultiInstance, message: u8) -> u64 {
+    let child_flow = deploy_model(vec![
+        element(1, 0, 0b1, start_event(), vec![2]),
+        receive_task(2, 0b1, 0b10, message, vec![3]),
+        element(3, 0b10, 0, end_event(), vec![]),
+    ]);
+    let sub_process = ElementType::SubProcess {
+        kind: SubProcessKind::Embedded,
+        multi_instance,
+    };
+    deploy_linked(
+        vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            element(2, 0b1, 0b10, sub_process, vec![3]),
+            element(3, 0b10, 0, end_event(), vec![]),
+        ],
+        vec![link(2, child_flow, vec![])],
+    )
+}
+
+fn loop_counter(instance_id: u64) -> u128 {
+    BpmnInterpreter::idata_by_id(instance_id).get_loop_counter()
+}
+
+#[test]
+fn invoice_lines_are_approved_until_the_contract_completes_the_invoice() {
+    new_test_ext().execute_with(|| {
+        const APPROVED: u8 = 1;
+        // One approval per invoice line, one after the other, the contract telling how many
+        // lines the invoice has and whether the approvals so far are enough
+        let process_id = sub_process_model(MultiInstance::Sequential, APPROVED);
+        assert_ok!(BpmnInterpreter::set_multi_instance(
+            Origin::signed(ALICE),
+            process_id,
+            2,
+            MultiInstanceDefinition {
+                cardinality_selector: vec![1, 2, 3, 4],
+                completion_selector: vec![5, 6, 7, 8],
+            }
+        ));
+        MockScripts::answer_data(2, 3u128);
+        MockScripts::answer_data(2, false);
+        MockScripts::answer_data(2, true);
+
+        let (instance_id, first_line) = start_with_child(process_id);
+        assert_marking(instance_id, 0, &[2]);
+        assert_marking(first_line, 0, &[2]);
+        assert_eq!(loop_counter(first_line), 0);
+
+        assert_ok!(send(first_line, APPROVED));
+        assert_eq!(status(first_line), InstanceStatus::Completed);
+        let second_line = next_child();
+        assert_eq!(loop_counter(second_line), 1);
+        assert_marking(instance_id, 0, &[2]);
+
+        // Complete before the third line
+        assert_ok!(send(second_line, APPROVED));
+        assert_eq!(status(second_line), InstanceStatus::Completed);
+        assert_eq!(BpmnInterpreter::instance_id_count(), second_line);
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(status(instance_id), InstanceStatus::Completed);
+    });
+}
+
+#[test]
+fn reminders_are_sent_while_the_contract_tells_so() {
+    new_test_ext().execute_with(|| {
+        const PAID: u8 = 1;
+        // Standard loop waiting for the payment, tested after each iteration
+        let process_id =
+            sub_process_model(MultiInstance::StandardLoop { test_before: false }, PAID);
+        assert_ok!(BpmnInterpreter::set_loop(
+            Origin::signed(ALICE),
+            process_id,
+            2,
+            LoopDefinition {
+                condition_selector: vec![1, 2, 3, 4],
+                maximum: None,
+            }
+        ));
+        MockScripts::answer_data(2, true);
+        MockScripts::answer_data(2, false);
+
+        let (instance_id, first_iteration) = start_with_child(process_id);
+        assert_marking(instance_id, 0, &[2]);
+        assert_eq!(loop_counter(first_iteration), 0);
+
+        assert_ok!(send(first_iteration, PAID));
+        let second_iteration = next_child();
+        assert_ne!(first_iteration, second_iteration);
+        assert_eq!(loop_counter(second_iteration), 1);
+        assert_marking(instance_id, 0, &[2]);
+
+        assert_ok!(send(second_iteration, PAID));
+        assert_eq!(BpmnInterpreter::instance_id_count(), second_iteration);
+        assert_marking(instance_id, 0, &[]);
+        assert_eq!(status(instance_id), InstanceStatus::Completed);
+    });
+}
+
+#[test]
+fn declined_payments_hand_their_reason_to_the_handler() {
+    new_test_ext().execute_with(|| {
+        const DECLINED: u8 = 1;
+        const PAYMENT_FAILED: [u8; 32] = [2; 32];
+        let error_event = |position| ElementType::Event {
+            position,
+            kind: EventKind::Error,
+            interrupting: position == EventPosition::Boundary,
+        };
+        // Payment (2) failing once declined, caught by the boundary event 4 of the sub-process
+        // leading to the handler 5
+        let mut payment_failed = element(3, 0b10, 0, error_event(EventPosition::End), vec![]);
+        payment_failed.event_code = PAYMENT_FAILED;
+        let child_flow = deploy_model(vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            receive_task(2, 0b1, 0b10, DECLINED, vec![3]),
+            payment_failed,
+        ]);
+        let mut caught = element(4, 0, 0b100, error_event(EventPosition::Boundary), vec![5]);
+        caught.event_code = PAYMENT_FAILED;
+        let process_id = deploy_linked(
+            vec![
+                element(1, 0, 0b1, start_event(), vec![2]),
+                element(
+                    2,
+                    0b1,
+                    0b10,
+                    ElementType::SubProcess {
+                        kind: SubProcessKind::Embedded,
+                        multi_instance: MultiInstance::None,
+                    },
+                    vec![3],
+                ),
+                element(3, 0b10, 0, end_event(), vec![]),
+                caught,
+                element(5, 0b100, 0b1000, ElementType::Task(TaskKind::User), vec![]),
+            ],
+            vec![link(2, child_flow, vec![4])],
+        );
+        for &(process_id, element_index) in &[(child_flow, 3), (process_id, 4)] {
+            assert_ok!(BpmnInterpreter::set_event_payload(
+                Origin::signed(ALICE),
+                process_id,
+                element_index,
+                vec![1, 2, 3, 4]
+            ));
+        }
+        MockScripts::answer_data(3, b"insufficient funds".to_vec());
+
+        let (instance_id, payment) = start_with_child(process_id);
+        assert_marking(payment, 0, &[2]);
+
+        assert_ok!(send(payment, DECLINED));
+        assert_eq!(status(payment), InstanceStatus::Terminated);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(instance_id).get_marking(),
+            &BitSet::from(0b1000)
+        );
+        assert_eq!(
+            MockScripts::caught_payloads(),
+            vec![(instance_id, 4, b"insufficient funds".to_vec())]
+        );
+    });
+}
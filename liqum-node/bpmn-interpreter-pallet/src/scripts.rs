@@ -0,0 +1,168 @@
+//! Execution of the scripts of script tasks and of exclusive and inclusive split gateways, and
+//! of the other calls of the data & scripts contract deciding the flow of an instance.
+
+use crate::{BitSet, Error, Iflow, Module, Trait};
+use codec::{Decode, Encode};
+use frame_support::dispatch::{DispatchError, DispatchResult};
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// Runs the script of an element of a process instance, returning the tokens the element puts
+/// on its outgoing sequence flows, and answers the other questions the interpreter asks about
+/// the data of the instance.
+pub trait ScriptExecutor<InstanceId> {
+    /// `marking` holds the tokens of the instance once the element took its own.
+    fn execute_script(
+        instance_id: InstanceId,
+        element_index: u128,
+        marking: &BitSet,
+    ) -> Result<BitSet, DispatchError>;
+
+    /// Number of instances the multi-instance sub-process runs.
+    fn multi_instance_cardinality(
+        instance_id: InstanceId,
+        element_index: u128,
+    ) -> Result<u128, DispatchError>;
+
+    /// Whether the multi-instance sub-process is complete once its instance numbered
+    /// `loop_counter` completed.
+    fn is_multi_instance_complete(
+        instance_id: InstanceId,
+        element_index: u128,
+        loop_counter: u128,
+    ) -> Result<bool, DispatchError>;
+
+    /// Whether the standard loop activity runs another iteration once `iterations` ran.
+    fn is_loop_continued(
+        instance_id: InstanceId,
+        element_index: u128,
+        iterations: u128,
+    ) -> Result<bool, DispatchError>;
+
+    /// Payload of the error or escalation the event throws.
+    fn thrown_payload(
+        instance_id: InstanceId,
+        element_index: u128,
+    ) -> Result<Vec<u8>, DispatchError>;
+
+    /// Keeps the payload the event caught for the handler it leads to.
+    fn write_caught_payload(
+        instance_id: InstanceId,
+        element_index: u128,
+        payload: &[u8],
+    ) -> DispatchResult;
+}
+
+/// Calls the data & scripts contract of the instance with the selector its process model has
+/// for the element, followed by the instance id, element index and the arguments of the call.
+/// Scripts are also given the marking and return the tokens as a `BitSet` or a legacy `u128`
+/// bitmask, the other calls return their SCALE encoded answer.
+pub struct ContractScripts<T>(PhantomData<T>);
+
+impl<T: Trait> ContractScripts<T> {
+    fn call<A: Encode>(
+        instance_id: T::InstanceId,
+        selector: impl FnOnce(&Iflow<T>) -> Option<&[u8]>,
+        arguments: A,
+    ) -> Result<Vec<u8>, DispatchError> {
+        let idata = Module::<T>::ensure_idata_instance_exists(instance_id)?;
+        let iflow = Module::<T>::ensure_iflow_instance_exists(idata.get_flow_node())?;
+        let selector = selector(&iflow).ok_or(Error::<T>::ScriptCallFailed)?;
+        let input_data = [selector, &(instance_id.into(), arguments).encode()[..]].concat();
+        Module::<T>::call_data_contract(&iflow, input_data)?
+            .ok_or_else(|| Error::<T>::ScriptCallFailed.into())
+    }
+
+    fn decode<V: Decode>(output: Vec<u8>) -> Result<V, DispatchError> {
+        V::decode(&mut &output[..]).map_err(|_| Error::<T>::DecodingFailed.into())
+    }
+}
+
+impl<T: Trait> ScriptExecutor<T::InstanceId> for ContractScripts<T> {
+    fn execute_script(
+        instance_id: T::InstanceId,
+        element_index: u128,
+        marking: &BitSet,
+    ) -> Result<BitSet, DispatchError> {
+        let output = Self::call(
+            instance_id,
+            |iflow| Some(iflow.get_factory_instance().get_execute_script_selector()),
+            (element_index, marking),
+        )?;
+        BitSet::decode_marking(&output).map_err(|_| Error::<T>::DecodingFailed.into())
+    }
+
+    fn multi_instance_cardinality(
+        instance_id: T::InstanceId,
+        element_index: u128,
+    ) -> Result<u128, DispatchError> {
+        let output = Self::call(
+            instance_id,
+            |iflow| {
+                iflow
+                    .get_multi_instance(element_index)
+                    .map(|multi_instance| &multi_instance.cardinality_selector[..])
+            },
+            element_index,
+        )?;
+        Self::decode(output)
+    }
+
+    fn is_multi_instance_complete(
+        instance_id: T::InstanceId,
+        element_index: u128,
+        loop_counter: u128,
+    ) -> Result<bool, DispatchError> {
+        let output = Self::call(
+            instance_id,
+            |iflow| {
+                iflow
+                    .get_multi_instance(element_index)
+                    .map(|multi_instance| &multi_instance.completion_selector[..])
+            },
+            (element_index, loop_counter),
+        )?;
+        Self::decode(output)
+    }
+
+    fn is_loop_continued(
+        instance_id: T::InstanceId,
+        element_index: u128,
+        iterations: u128,
+    ) -> Result<bool, DispatchError> {
+        let output = Self::call(
+            instance_id,
+            |iflow| {
+                iflow
+                    .get_loop(element_index)
+                    .map(|standard_loop| &standard_loop.condition_selector[..])
+            },
+            (element_index, iterations),
+        )?;
+        Self::decode(output)
+    }
+
+    fn thrown_payload(
+        instance_id: T::InstanceId,
+        element_index: u128,
+    ) -> Result<Vec<u8>, DispatchError> {
+        let output = Self::call(
+            instance_id,
+            |iflow| iflow.get_event_payload(element_index),
+            element_index,
+        )?;
+        Self::decode(output)
+    }
+
+    fn write_caught_payload(
+        instance_id: T::InstanceId,
+        element_index: u128,
+        payload: &[u8],
+    ) -> DispatchResult {
+        Self::call(
+            instance_id,
+            |iflow| iflow.get_event_payload(element_index),
+            (element_index, payload),
+        )?;
+        Ok(())
+    }
+}
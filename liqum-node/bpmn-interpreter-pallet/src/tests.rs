@@ -0,0 +1,2453 @@
+use crate::{mock::*, types::*, *};
+use codec::Decode;
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::IterableStorageMap,
+    traits::{Get, OnInitialize},
+};
+use sp_core::H256;
+
+pub(crate) fn start_event() -> ElementType {
+    ElementType::Event {
+        position: EventPosition::Start,
+        kind: EventKind::Default,
+        interrupting: false,
+    }
+}
+
+pub(crate) fn end_event() -> ElementType {
+    ElementType::Event {
+        position: EventPosition::End,
+        kind: EventKind::Default,
+        interrupting: false,
+    }
+}
+
+fn timer_event(position: EventPosition, interrupting: bool) -> ElementType {
+    ElementType::Event {
+        position,
+        kind: EventKind::Timer,
+        interrupting,
+    }
+}
+
+fn sub_process() -> ElementType {
+    ElementType::SubProcess {
+        kind: SubProcessKind::Embedded,
+        multi_instance: MultiInstance::None,
+    }
+}
+
+pub(crate) fn element(
+    element_index: u128,
+    pre_condition: u128,
+    post_condition: u128,
+    element_type: ElementType,
+    next_elem: Vec<u128>,
+) -> ElementDefinition {
+    ElementDefinition {
+        element_index,
+        pre_condition: BitSet::from(pre_condition),
+        post_condition: BitSet::from(post_condition),
+        element_type,
+        event_code: [0; 32],
+        next_elem,
+    }
+}
+
+/// Start event, `activity` and end event, linked by the sequence flows 0 and 1.
+fn sequence(activity: ElementType) -> Vec<ElementDefinition> {
+    vec![
+        element(1, 0, 0b01, start_event(), vec![2]),
+        element(2, 0b01, 0b10, activity, vec![3]),
+        element(3, 0b10, 0, end_event(), vec![]),
+    ]
+}
+
+fn deploy(elements: Vec<ElementDefinition>, links: Vec<SubProcessLink<u64>>) -> DispatchResult {
+    BpmnInterpreter::deploy_process(
+        Origin::signed(ALICE),
+        elements,
+        links,
+        Default::default(),
+        vec![],
+        vec![],
+    )
+}
+
+pub(crate) fn link(
+    parent_index: u128,
+    child_flow: u64,
+    attached_events: Vec<u128>,
+) -> SubProcessLink<u64> {
+    SubProcessLink {
+        parent_index,
+        child_flow,
+        attached_events,
+        count_instances: 1,
+    }
+}
+
+#[test]
+fn deploys_process_under_fresh_process_ids() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(sequence(ElementType::Task(TaskKind::User)), vec![]));
+        assert_ok!(deploy(sequence(sub_process()), vec![link(2, 1, vec![])]));
+
+        assert_eq!(BpmnInterpreter::process_id_count(), 2);
+        assert_eq!(BpmnInterpreter::instance_id_count(), 0);
+        assert_eq!(BpmnInterpreter::iflow_by_id(1).get_first_elem(), 1);
+        let iflow = BpmnInterpreter::iflow_by_id(2);
+        assert_eq!(iflow.get_sub_process_instance(2).ok(), Some(1));
+        assert_eq!(iflow.get_sub_process_list(), &[2][..]);
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::ProcessDeployed(2, ALICE))));
+    });
+}
+
+#[test]
+fn deploy_process_validates_the_graph() {
+    new_test_ext().execute_with(|| {
+        let task = ElementType::Task(TaskKind::User);
+
+        let mut no_start = sequence(task);
+        no_start.remove(0);
+        assert_noop!(deploy(no_start, vec![]), Error::<Test>::StartEventNotFound);
+
+        let mut dangling = sequence(task);
+        dangling[2].next_elem = vec![4];
+        assert_noop!(deploy(dangling, vec![]), Error::<Test>::ElementNotFound);
+
+        let mut duplicate = sequence(task);
+        duplicate[2].element_index = 2;
+        assert_noop!(deploy(duplicate, vec![]), Error::<Test>::DuplicateElement);
+
+        let mut interrupting_end = sequence(task);
+        interrupting_end[2].element_type = ElementType::Event {
+            position: EventPosition::End,
+            kind: EventKind::Error,
+            interrupting: true,
+        };
+        assert_noop!(
+            deploy(interrupting_end, vec![]),
+            Error::<Test>::InvalidElementType
+        );
+
+        let mut out_of_bounds = sequence(task);
+        out_of_bounds[2].element_index = MAX_BIT_INDEX;
+        out_of_bounds[1].next_elem = vec![MAX_BIT_INDEX];
+        assert_noop!(
+            deploy(out_of_bounds, vec![]),
+            Error::<Test>::IndexOutOfBounds
+        );
+
+        assert_ok!(deploy(sequence(task), vec![]));
+        assert_noop!(
+            deploy(sequence(task), vec![link(2, 1, vec![])]),
+            Error::<Test>::SubProcessToLinkNotFound
+        );
+        assert_noop!(
+            deploy(sequence(sub_process()), vec![link(2, 7, vec![])]),
+            Error::<Test>::IflowNotFound
+        );
+        assert_noop!(
+            deploy(sequence(sub_process()), vec![link(2, 1, vec![3, 2])]),
+            Error::<Test>::InvalidAttachedEvent
+        );
+    });
+}
+
+#[test]
+fn link_sub_process_rejects_cycles() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(sequence(sub_process()), vec![]));
+        assert_ok!(deploy(sequence(sub_process()), vec![link(2, 1, vec![])]));
+
+        for child_flow in [1, 2].iter() {
+            assert_noop!(
+                BpmnInterpreter::link_sub_process(
+                    Origin::signed(ALICE),
+                    1,
+                    2,
+                    *child_flow,
+                    vec![],
+                    1
+                ),
+                Error::<Test>::CyclicSubProcess
+            );
+        }
+        assert_noop!(
+            BpmnInterpreter::link_sub_process(Origin::signed(ALICE), 1, 1, 2, vec![], 1),
+            Error::<Test>::SubProcessToLinkNotFound
+        );
+    });
+}
+
+#[test]
+fn continue_execution_rejects_unknown_elements() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            BpmnInterpreter::continue_execution(Origin::signed(ALICE), 1, 2),
+            Error::<Test>::IdataNotFound
+        );
+
+        assert_ok!(deploy(sequence(ElementType::Task(TaskKind::User)), vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        <IdataById<Test>>::insert(1, idata);
+
+        assert_noop!(
+            BpmnInterpreter::continue_execution(Origin::signed(ALICE), 1, 42),
+            Error::<Test>::ElementNotFound
+        );
+    });
+}
+
+#[test]
+fn completing_more_instances_than_started_is_an_error() {
+    new_test_ext().execute_with(|| {
+        let mut parent = Idata::default();
+        parent.set_parent(None, 1, 0);
+        parent.set_instance_count(2, 0);
+        <IdataById<Test>>::insert(1, parent);
+
+        let mut child = Idata::default();
+        child.set_parent(Some(1), 2, 2);
+        <IdataById<Test>>::insert(2, child.clone());
+        <IflowById<Test>>::insert(1, Iflow::default());
+
+        assert_eq!(
+            BpmnInterpreter::try_catch_event(
+                &mut ExecutionMeter::default(),
+                2,
+                &child,
+                [0; 32],
+                EVENT | THROW | END | DEFAULT_EVENT,
+                true,
+                &[]
+            ),
+            Err(Error::<Test>::InstanceCountUnderflow.into())
+        );
+    });
+}
+
+#[test]
+fn process_models_are_mutated_by_their_owner_or_admin() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(sequence(sub_process()), vec![]));
+        assert_ok!(deploy(sequence(sub_process()), vec![]));
+        assert_eq!(BpmnInterpreter::process_owner(1), Some(ALICE));
+
+        let set_factory = |origin| {
+            BpmnInterpreter::set_factory_instance(origin, 1, Default::default(), vec![], vec![])
+        };
+        assert_noop!(
+            set_factory(Origin::signed(BOB)),
+            Error::<Test>::Unauthorized
+        );
+        assert_noop!(
+            BpmnInterpreter::link_sub_process(Origin::signed(BOB), 1, 2, 2, vec![], 1),
+            Error::<Test>::Unauthorized
+        );
+        assert_ok!(set_factory(Origin::root()));
+
+        assert_noop!(
+            BpmnInterpreter::transfer_ownership(Origin::signed(BOB), 1, BOB),
+            Error::<Test>::Unauthorized
+        );
+        assert_ok!(BpmnInterpreter::transfer_ownership(
+            Origin::signed(ALICE),
+            1,
+            BOB
+        ));
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::OwnershipTransferred(1, Some(ALICE), BOB))));
+        assert_noop!(
+            set_factory(Origin::signed(ALICE)),
+            Error::<Test>::Unauthorized
+        );
+        assert_ok!(BpmnInterpreter::link_sub_process(
+            Origin::signed(BOB),
+            1,
+            2,
+            2,
+            vec![],
+            1
+        ));
+    });
+}
+
+#[test]
+fn execution_is_continued_by_owner_and_participants() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(sequence(ElementType::Task(TaskKind::User)), vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        <IdataById<Test>>::insert(1, idata);
+
+        assert_noop!(
+            BpmnInterpreter::continue_execution(Origin::signed(BOB), 1, 2),
+            Error::<Test>::Unauthorized
+        );
+        assert_noop!(
+            BpmnInterpreter::set_participants(Origin::signed(BOB), 1, vec![BOB]),
+            Error::<Test>::Unauthorized
+        );
+        let too_many = vec![BOB; MAX_PARTICIPANTS as usize + 1];
+        assert_noop!(
+            BpmnInterpreter::set_participants(Origin::signed(ALICE), 1, too_many),
+            Error::<Test>::TooManyParticipants
+        );
+
+        assert_ok!(BpmnInterpreter::set_participants(
+            Origin::signed(ALICE),
+            1,
+            vec![BOB]
+        ));
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(BOB),
+            1,
+            2
+        ));
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+    });
+}
+
+#[test]
+fn execution_is_charged_for_the_work_done() {
+    new_test_ext().execute_with(|| {
+        let task = ElementType::Task(TaskKind::Default);
+        assert_ok!(deploy(sequence(task), vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        idata.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(1, idata);
+
+        let max_weight = weights::continue_execution::<Test>(
+            MaxExecutionSteps::get(),
+            MaxExecutionSteps::get(),
+            MAX_SUB_PROCESSES,
+        );
+        let post_info = BpmnInterpreter::continue_execution(Origin::signed(ALICE), 1, 2).unwrap();
+        assert!(post_info.actual_weight.unwrap() < max_weight);
+    });
+}
+
+#[test]
+fn execution_out_of_steps_is_resumed_later() {
+    new_test_ext().execute_with(|| {
+        // Chain of 300 tasks, more than a call can queue
+        let tasks = 300;
+        let task = ElementType::Task(TaskKind::Default);
+        let mut elements = vec![element(1, 0, 0b01, start_event(), vec![2])];
+        for flow in 0..=tasks {
+            let element_type = if flow < tasks { task } else { end_event() };
+            let mut chained = element(2 + flow, 0, 0, element_type, vec![]);
+            chained.pre_condition = BitSet::with_bit(flow);
+            if flow < tasks {
+                chained.post_condition = BitSet::with_bit(flow + 1);
+                chained.next_elem = vec![3 + flow];
+            }
+            elements.push(chained);
+        }
+        assert_ok!(deploy(elements, vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        idata.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(1, idata);
+
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        assert!(!BpmnInterpreter::idata_by_id(1).get_marking().is_empty());
+        assert_eq!(BpmnInterpreter::pending_elements(1).len(), 1);
+        assert_eq!(BpmnInterpreter::pending_instances(), vec![1]);
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event
+                == TestEvent::bpmn_interpreter(RawEvent::ExecutionPending(1, 1))));
+
+        // Anyone may pay for the execution to go on
+        assert_ok!(BpmnInterpreter::poke_instance(Origin::signed(BOB), 1));
+        assert!(BpmnInterpreter::idata_by_id(1).get_marking().is_empty());
+        assert!(BpmnInterpreter::pending_elements(1).is_empty());
+        assert!(BpmnInterpreter::pending_instances().is_empty());
+        assert_noop!(
+            BpmnInterpreter::poke_instance(Origin::signed(BOB), 1),
+            Error::<Test>::NoPendingElements
+        );
+
+        // Two tasks passing the token to each other forever, resumed block after block
+        let mut cycle = sequence(task);
+        cycle[1].next_elem = vec![3];
+        cycle[2] = element(3, 0b10, 0b01, task, vec![2]);
+        assert_ok!(deploy(cycle, vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 2, 0);
+        idata.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(2, idata);
+
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            2,
+            2
+        ));
+        System::reset_events();
+        BpmnInterpreter::on_initialize(System::block_number());
+        assert_eq!(BpmnInterpreter::pending_instances(), vec![2]);
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event
+                == TestEvent::bpmn_interpreter(RawEvent::ExecutionPending(2, 1))));
+    });
+}
+
+#[test]
+fn execution_queue_holds_every_enabled_branch() {
+    new_test_ext().execute_with(|| {
+        // Parallel split into 120 tasks, each queuing the join once completed
+        let branches = 120;
+        let task = ElementType::Task(TaskKind::Default);
+        let parallel = |join| ElementType::Gateway {
+            kind: GatewayKind::Parallel,
+            join,
+        };
+        let mut split = element(2, 0b01, 0, parallel(false), vec![]);
+        let mut join = element(3 + branches, 0, 0, parallel(true), vec![4 + branches]);
+        let mut elements = vec![element(1, 0, 0b01, start_event(), vec![2])];
+        for branch in 1..=branches {
+            split.post_condition.insert(branch);
+            split.next_elem.push(2 + branch);
+            join.pre_condition.insert(branches + branch);
+            let mut branch_task = element(2 + branch, 0, 0, task, vec![3 + branches]);
+            branch_task.pre_condition = BitSet::with_bit(branch);
+            branch_task.post_condition = BitSet::with_bit(branches + branch);
+            elements.push(branch_task);
+        }
+        join.post_condition = BitSet::with_bit(2 * branches + 1);
+        let mut end = element(4 + branches, 0, 0, end_event(), vec![]);
+        end.pre_condition = BitSet::with_bit(2 * branches + 1);
+        elements.extend(vec![split, join, end]);
+        assert_ok!(deploy(elements, vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        idata.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(1, idata);
+
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        assert!(BpmnInterpreter::idata_by_id(1).get_marking().is_empty());
+    });
+}
+
+#[test]
+fn pending_elements_are_kept_until_resumed() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Script)),
+            vec![]
+        ));
+        <IflowById<Test>>::mutate(1, |iflow| iflow.factory.address = Some(BOB));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        idata.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(1, idata);
+
+        // An element is pending at most once
+        BpmnInterpreter::keep_pending(1, vec![2, 3]);
+        BpmnInterpreter::keep_pending(1, vec![3, 2]);
+        assert_eq!(BpmnInterpreter::pending_elements(1), vec![2, 3]);
+        assert_eq!(BpmnInterpreter::pending_instances(), vec![1]);
+
+        // The script fails, its task is still pending
+        assert_eq!(
+            BpmnInterpreter::poke_instance(Origin::signed(BOB), 1).map_err(|e| e.error),
+            Err(Error::<Test>::ScriptCallFailed.into())
+        );
+        assert_eq!(BpmnInterpreter::pending_elements(1), vec![2, 3]);
+        assert_eq!(BpmnInterpreter::pending_instances(), vec![1]);
+        BpmnInterpreter::on_initialize(System::block_number());
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::ExecutionResumeFailed(
+                1,
+                Error::<Test>::ScriptCallFailed.into()
+            ))));
+        assert_eq!(BpmnInterpreter::pending_elements(1), vec![2, 3]);
+        assert_eq!(BpmnInterpreter::resume_attempts(1), 1);
+
+        // Failing to resume it again, `on_initialize` leaves it to be poked
+        for _ in 1..MAX_RESUME_ATTEMPTS {
+            BpmnInterpreter::on_initialize(System::block_number());
+        }
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::ExecutionResumeAbandoned(1))));
+        assert!(BpmnInterpreter::pending_instances().is_empty());
+        assert_eq!(BpmnInterpreter::resume_attempts(1), 0);
+        assert_eq!(BpmnInterpreter::pending_elements(1), vec![2, 3]);
+
+        MockScripts::answer(2, BitSet::from(0b10));
+        assert_ok!(BpmnInterpreter::poke_instance(Origin::signed(BOB), 1));
+        assert!(BpmnInterpreter::pending_elements(1).is_empty());
+        assert!(BpmnInterpreter::pending_instances().is_empty());
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_status(),
+            InstanceStatus::Completed
+        );
+
+        // Past the instances `on_initialize` resumes, the instance is left to be poked
+        <PendingInstances<Test>>::put(
+            (100..100 + MAX_PENDING_INSTANCES as u64).collect::<Vec<_>>(),
+        );
+        BpmnInterpreter::keep_pending(1, vec![2]);
+        assert_eq!(BpmnInterpreter::pending_elements(1), vec![2]);
+        assert!(!BpmnInterpreter::pending_instances().contains(&1));
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event
+                == TestEvent::bpmn_interpreter(RawEvent::PendingInstancesFull(1))));
+    });
+}
+
+#[test]
+fn sub_processes_past_the_instance_limit_are_left_pending() {
+    new_test_ext().execute_with(|| {
+        // Parallel split into more sub-processes than a call can create, each waiting for a
+        // message
+        let sub_processes = MAX_SUB_PROCESSES as u128 + 5;
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Receive)),
+            vec![]
+        ));
+        let mut split = element(
+            2,
+            0b01,
+            0,
+            ElementType::Gateway {
+                kind: GatewayKind::Parallel,
+                join: false,
+            },
+            vec![],
+        );
+        let mut elements = vec![element(1, 0, 0b01, start_event(), vec![2])];
+        let mut links = vec![];
+        for branch in 1..=sub_processes {
+            split.post_condition.insert(branch);
+            split.next_elem.push(2 + branch);
+            let mut activity = element(2 + branch, 0, 0, sub_process(), vec![]);
+            activity.pre_condition = BitSet::with_bit(branch);
+            elements.push(activity);
+            links.push(link(2 + branch, 1, vec![]));
+        }
+        elements.push(split);
+        assert_ok!(deploy(elements, links));
+        for process_id in 1..=2 {
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+        }
+        let started = || {
+            let idata = BpmnInterpreter::idata_by_id(1);
+            (3..3 + sub_processes)
+                .filter(|activity| idata.get_started_activities().contains(*activity))
+                .count() as u128
+        };
+
+        assert_ok!(BpmnInterpreter::create_root_instance(
+            Origin::signed(ALICE),
+            2
+        ));
+        let first_call = started();
+        assert!(first_call > 0 && first_call < sub_processes);
+        assert_eq!(
+            BpmnInterpreter::pending_elements(1).len() as u128,
+            sub_processes - first_call
+        );
+        assert_eq!(BpmnInterpreter::pending_instances(), vec![1]);
+
+        assert_ok!(BpmnInterpreter::poke_instance(Origin::signed(BOB), 1));
+        assert_eq!(started(), sub_processes);
+        assert!(BpmnInterpreter::pending_elements(1).is_empty());
+    });
+}
+
+#[test]
+fn message_end_events_end_their_instance() {
+    new_test_ext().execute_with(|| {
+        const SHIPPED: [u8; 32] = [3; 32];
+        // Sub-process 2 of process 2 ending with a message end event
+        let mut child_model = sequence(ElementType::Task(TaskKind::Default));
+        child_model[2].element_type = ElementType::Event {
+            position: EventPosition::End,
+            kind: EventKind::Message,
+            interrupting: false,
+        };
+        child_model[2].event_code = SHIPPED;
+        assert_ok!(deploy(child_model, vec![]));
+        assert_ok!(deploy(sequence(sub_process()), vec![link(2, 1, vec![])]));
+        for process_id in 1..=2 {
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+        }
+        assert_ok!(BpmnInterpreter::create_root_instance(
+            Origin::signed(ALICE),
+            2
+        ));
+        let child = BpmnInterpreter::instance_id_count();
+        let root = BpmnInterpreter::idata_by_id(child)
+            .get_idata_parent()
+            .unwrap();
+
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::MessageSent(SHIPPED.to_vec()))));
+        for &instance_id in &[child, root] {
+            let idata = BpmnInterpreter::idata_by_id(instance_id);
+            assert_eq!(idata.get_status(), InstanceStatus::Completed);
+            assert!(idata.get_marking().is_empty());
+            assert!(idata.get_started_activities().is_empty());
+        }
+    });
+}
+
+#[test]
+fn instances_record_how_they_ended() {
+    new_test_ext().execute_with(|| {
+        let instance = |process_id| {
+            let mut idata = Idata::default();
+            idata.set_parent(None, process_id, 0);
+            idata.set_marking(BitSet::from(0b001));
+            <IdataById<Test>>::insert(process_id, idata);
+        };
+        let events = || -> Vec<_> {
+            System::events()
+                .into_iter()
+                .map(|record| record.event)
+                .collect()
+        };
+
+        // Every token reaching the end event
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Default)),
+            vec![]
+        ));
+        instance(1);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_status(),
+            InstanceStatus::Completed
+        );
+        let trail: Vec<_> = vec![
+            RawEvent::ElementStarted(1, 2),
+            RawEvent::ElementCompleted(1, 2),
+            RawEvent::TokenMoved(1, 2, BitSet::from(0b10)),
+            RawEvent::ElementStarted(1, 3),
+            RawEvent::ElementCompleted(1, 3),
+            RawEvent::InstanceCompleted(1),
+        ]
+        .into_iter()
+        .map(TestEvent::bpmn_interpreter)
+        .collect();
+        assert!(events().ends_with(&trail));
+
+        // Terminate end event reached while a receive task waits
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b001, start_event(), vec![2]),
+                element(
+                    2,
+                    0b001,
+                    0b110,
+                    ElementType::Gateway {
+                        kind: GatewayKind::Parallel,
+                        join: false,
+                    },
+                    vec![3, 4]
+                ),
+                element(3, 0b010, 0, ElementType::Task(TaskKind::Receive), vec![]),
+                element(
+                    4,
+                    0b100,
+                    0,
+                    ElementType::Event {
+                        position: EventPosition::End,
+                        kind: EventKind::Terminate,
+                        interrupting: false,
+                    },
+                    vec![]
+                ),
+            ],
+            vec![]
+        ));
+        instance(2);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            2,
+            2
+        ));
+        let idata = BpmnInterpreter::idata_by_id(2);
+        assert_eq!(idata.get_status(), InstanceStatus::Terminated);
+        assert!(idata.get_started_activities().is_empty());
+        assert!(BpmnInterpreter::message_subscriptions(2, [0; 32]).is_empty());
+        assert!(
+            events().contains(&TestEvent::bpmn_interpreter(RawEvent::InstanceTerminated(
+                2
+            )))
+        );
+
+        // Error end event no event catches
+        let mut failing = sequence(ElementType::Task(TaskKind::Default));
+        failing[2].element_type = ElementType::Event {
+            position: EventPosition::End,
+            kind: EventKind::Error,
+            interrupting: false,
+        };
+        assert_ok!(deploy(failing, vec![]));
+        instance(3);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            3,
+            2
+        ));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(3).get_status(),
+            InstanceStatus::Failed
+        );
+        assert!(
+            events().contains(&TestEvent::bpmn_interpreter(RawEvent::InstanceFailed(
+                3, [0; 32]
+            )))
+        );
+    });
+}
+
+#[test]
+fn root_instances_are_suspended_resumed_and_cancelled() {
+    new_test_ext().execute_with(|| {
+        const ORDER: [u8; 32] = [1; 32];
+        let correlation_key = H256::repeat_byte(7);
+        let mut receive_task =
+            element(2, 0b01, 0b10, ElementType::Task(TaskKind::Receive), vec![3]);
+        receive_task.event_code = ORDER;
+        let instance = |process_id| {
+            let mut idata = Idata::default();
+            idata.set_parent(None, process_id, 0);
+            idata.set_marking(BitSet::from(0b01));
+            <IdataById<Test>>::insert(process_id, idata);
+        };
+        let has_event = |event| {
+            System::events()
+                .iter()
+                .any(|record| record.event == TestEvent::bpmn_interpreter(event))
+        };
+        let suspend = |origin| BpmnInterpreter::suspend_instance(origin, 1).map_err(|e| e.error);
+        let resume = |origin| BpmnInterpreter::resume_instance(origin, 1).map_err(|e| e.error);
+
+        // Receive task waiting for the order before the end event
+        let mut model = sequence(ElementType::Task(TaskKind::Receive));
+        model[1] = receive_task;
+        assert_ok!(deploy(model, vec![]));
+        instance(1);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            1,
+            correlation_key
+        ));
+
+        assert_noop!(suspend(Origin::signed(BOB)), Error::<Test>::Unauthorized);
+        assert_noop!(
+            resume(Origin::signed(ALICE)),
+            Error::<Test>::InstanceNotSuspended
+        );
+        assert_ok!(suspend(Origin::signed(ALICE)));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_status(),
+            InstanceStatus::Suspended
+        );
+        assert!(has_event(RawEvent::InstanceSuspended(1)));
+        assert_noop!(
+            suspend(Origin::signed(ALICE)),
+            Error::<Test>::InstanceNotRunning
+        );
+        assert_noop!(
+            BpmnInterpreter::continue_execution(Origin::signed(ALICE), 1, 3).map_err(|e| e.error),
+            Error::<Test>::InstanceNotRunning
+        );
+
+        // The message is received, the end event is executed once resumed
+        assert_ok!(BpmnInterpreter::send_message(
+            Origin::signed(ALICE),
+            correlation_key,
+            ORDER,
+            vec![]
+        ));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_marking(),
+            &BitSet::from(0b10)
+        );
+        assert_eq!(BpmnInterpreter::pending_elements(1), vec![3]);
+        assert!(BpmnInterpreter::pending_instances().is_empty());
+        assert_noop!(
+            BpmnInterpreter::poke_instance(Origin::signed(ALICE), 1).map_err(|e| e.error),
+            Error::<Test>::InstanceNotRunning
+        );
+
+        assert_ok!(resume(Origin::root()));
+        assert!(has_event(RawEvent::InstanceResumed(1)));
+        assert_eq!(BpmnInterpreter::pending_instances(), vec![1]);
+        BpmnInterpreter::on_initialize(1);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_status(),
+            InstanceStatus::Completed
+        );
+
+        // Cancelled while waiting, suspended or not
+        instance(1);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        assert_ok!(suspend(Origin::signed(ALICE)));
+        assert_noop!(
+            BpmnInterpreter::cancel_instance(Origin::signed(BOB), 1).map_err(|e| e.error),
+            Error::<Test>::Unauthorized
+        );
+        assert_ok!(BpmnInterpreter::cancel_instance(Origin::signed(ALICE), 1));
+        let idata = BpmnInterpreter::idata_by_id(1);
+        assert_eq!(idata.get_status(), InstanceStatus::Terminated);
+        assert!(idata.get_started_activities().is_empty());
+        assert!(BpmnInterpreter::message_subscriptions(1, ORDER).is_empty());
+        assert!(has_event(RawEvent::InstanceCancelled(1)));
+        assert_noop!(
+            BpmnInterpreter::cancel_instance(Origin::root(), 1).map_err(|e| e.error),
+            Error::<Test>::InstanceNotRunning
+        );
+
+        // Sub-process instances follow their root instance
+        let mut child = Idata::default();
+        child.set_parent(Some(1), 1, 2);
+        <IdataById<Test>>::insert(2, child);
+        assert_noop!(
+            BpmnInterpreter::suspend_instance(Origin::root(), 2).map_err(|e| e.error),
+            Error::<Test>::NotRootInstance
+        );
+    });
+}
+
+#[test]
+fn ended_instances_are_pruned_and_their_deposit_refunded() {
+    new_test_ext().execute_with(|| {
+        let deposit = InstanceDeposit::get();
+        let deploy_with_contract = |activity| {
+            assert_ok!(deploy(sequence(activity), vec![]));
+            let process_id = BpmnInterpreter::process_id_count();
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+            process_id
+        };
+        let create = |origin, process_id| -> Result<u64, DispatchError> {
+            BpmnInterpreter::create_root_instance(origin, process_id).map_err(|e| e.error)?;
+            Ok(BpmnInterpreter::instance_id_count())
+        };
+
+        // Completed right away, pruned once kept long enough
+        let process_id = deploy_with_contract(ElementType::Task(TaskKind::Default));
+        assert_noop!(
+            create(Origin::signed(3), process_id),
+            Error::<Test>::InsufficientDeposit
+        );
+        let completed = create(Origin::signed(ALICE), process_id).unwrap();
+        assert_eq!(Balances::reserved_balance(ALICE), deposit);
+        assert_eq!(
+            BpmnInterpreter::instance_deposit(completed),
+            Some((ALICE, deposit))
+        );
+        assert_eq!(BpmnInterpreter::ended_instances(), vec![(1, completed)]);
+
+        BpmnInterpreter::on_initialize(InstanceRetention::get());
+        assert!(<IdataById<Test>>::contains_key(completed));
+        BpmnInterpreter::on_initialize(1 + InstanceRetention::get());
+        assert!(!<IdataById<Test>>::contains_key(completed));
+        assert!(BpmnInterpreter::ended_instances().is_empty());
+        assert_eq!(BpmnInterpreter::instance_deposit(completed), None);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::InstanceArchived(
+                completed,
+                InstanceStatus::Completed,
+                BitSet::new()
+            ))));
+
+        // Waiting for a message until cancelled, then pruned on demand
+        let process_id = deploy_with_contract(ElementType::Task(TaskKind::Receive));
+        let waiting = create(Origin::signed(BOB), process_id).unwrap();
+        let correlation_key = H256::repeat_byte(7);
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            waiting,
+            correlation_key
+        ));
+        assert_noop!(
+            BpmnInterpreter::prune_instance(Origin::signed(BOB), waiting).map_err(|e| e.error),
+            Error::<Test>::InstanceNotEnded
+        );
+        assert_ok!(BpmnInterpreter::cancel_instance(
+            Origin::signed(ALICE),
+            waiting
+        ));
+        assert_ok!(BpmnInterpreter::prune_instance(
+            Origin::signed(ALICE),
+            waiting
+        ));
+        assert!(!<IdataById<Test>>::contains_key(waiting));
+        assert_eq!(BpmnInterpreter::correlation_key(waiting), None);
+        assert!(BpmnInterpreter::correlated_instances(correlation_key).is_empty());
+        assert!(BpmnInterpreter::ended_instances().is_empty());
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+    });
+}
+
+#[test]
+fn cases_of_the_same_model_run_apart() {
+    new_test_ext().execute_with(|| {
+        const ORDER: [u8; 32] = [1; 32];
+        let correlation_key = H256::repeat_byte(7);
+        // Sub-process 2 of process 2 waiting for the order on a receive task of process 1
+        let mut child_model = sequence(ElementType::Task(TaskKind::Receive));
+        child_model[1].event_code = ORDER;
+        assert_ok!(deploy(child_model, vec![]));
+        assert_ok!(deploy(sequence(sub_process()), vec![link(2, 1, vec![])]));
+        for process_id in 1..=2 {
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+        }
+        let create = || {
+            assert_ok!(BpmnInterpreter::create_root_instance(
+                Origin::signed(ALICE),
+                2
+            ));
+            let child = BpmnInterpreter::instance_id_count();
+            let root = BpmnInterpreter::idata_by_id(child)
+                .get_idata_parent()
+                .unwrap();
+            (root, child)
+        };
+
+        let (first, first_child) = create();
+        let (second, second_child) = create();
+        assert_ne!(first, second);
+        assert_ne!(first_child, second_child);
+        for &(root, child) in &[(first, first_child), (second, second_child)] {
+            let idata = BpmnInterpreter::idata_by_id(root);
+            assert_eq!(idata.get_flow_node(), 2);
+            assert_eq!(
+                idata.get_child_process_instances(2).ok(),
+                Some(&[child][..])
+            );
+            assert_eq!(BpmnInterpreter::idata_by_id(child).get_flow_node(), 1);
+            assert_eq!(
+                BpmnInterpreter::message_subscriptions(child, ORDER),
+                vec![2]
+            );
+            assert!(System::events().iter().any(|record| record.event
+                == TestEvent::bpmn_interpreter(RawEvent::InstanceCreated(root, 2))));
+        }
+
+        // The order completes the first case only
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            first_child,
+            correlation_key
+        ));
+        assert_ok!(BpmnInterpreter::send_message(
+            Origin::signed(ALICE),
+            correlation_key,
+            ORDER,
+            vec![]
+        ));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(first).get_status(),
+            InstanceStatus::Completed
+        );
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(second).get_status(),
+            InstanceStatus::Running
+        );
+        assert!(BpmnInterpreter::idata_by_id(second)
+            .get_started_activities()
+            .contains(2));
+        assert_eq!(
+            BpmnInterpreter::message_subscriptions(second_child, ORDER),
+            vec![2]
+        );
+    });
+}
+
+#[test]
+fn multi_instance_activities_number_their_instances() {
+    new_test_ext().execute_with(|| {
+        let multi_instance = |multi_instance| ElementType::SubProcess {
+            kind: SubProcessKind::Embedded,
+            multi_instance,
+        };
+        let linked = |child_flow, count_instances| SubProcessLink {
+            count_instances,
+            ..link(2, child_flow, vec![])
+        };
+        // Children of the multi-instance activity of a fresh root instance of the model
+        let create = |process_id| {
+            let root = BpmnInterpreter::instance_id_count() + 1;
+            assert_ok!(BpmnInterpreter::create_root_instance(
+                Origin::signed(ALICE),
+                process_id
+            ));
+            let children: Vec<_> = (root + 1..=BpmnInterpreter::instance_id_count()).collect();
+            (root, children)
+        };
+        let loop_counters = |children: &[u64]| -> Vec<_> {
+            children
+                .iter()
+                .map(|&child| BpmnInterpreter::idata_by_id(child).get_loop_counter())
+                .collect()
+        };
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Receive)),
+            vec![]
+        ));
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Default)),
+            vec![]
+        ));
+        assert_ok!(deploy(
+            sequence(multi_instance(MultiInstance::Parallel)),
+            vec![linked(1, 3)]
+        ));
+        assert_ok!(deploy(
+            sequence(multi_instance(MultiInstance::Sequential)),
+            vec![linked(2, 2)]
+        ));
+        assert_ok!(deploy(
+            sequence(multi_instance(MultiInstance::Parallel)),
+            vec![linked(1, 0)]
+        ));
+        for process_id in 1..=5 {
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+        }
+
+        // Parallel instances all started at once, waiting for their message
+        let (root, children) = create(3);
+        assert_eq!(loop_counters(&children), vec![0, 1, 2]);
+        let idata = BpmnInterpreter::idata_by_id(root);
+        assert_eq!(idata.get_instance_count(2).ok(), Some(3));
+        assert_eq!(idata.get_status(), InstanceStatus::Running);
+
+        // Sequential instances started once the one before completed
+        let (root, children) = create(4);
+        assert_eq!(loop_counters(&children), vec![0, 1]);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(root).get_status(),
+            InstanceStatus::Completed
+        );
+
+        // No instance to run
+        let (root, children) = create(5);
+        assert!(children.is_empty());
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(root).get_status(),
+            InstanceStatus::Completed
+        );
+    });
+}
+
+#[test]
+fn multi_instance_characteristics_are_set_on_multi_instance_activities() {
+    new_test_ext().execute_with(|| {
+        let definition = MultiInstanceDefinition {
+            cardinality_selector: vec![1, 2, 3, 4],
+            completion_selector: vec![],
+        };
+        assert_ok!(deploy(
+            sequence(ElementType::SubProcess {
+                kind: SubProcessKind::Embedded,
+                multi_instance: MultiInstance::Sequential,
+            }),
+            vec![]
+        ));
+        assert_ok!(deploy(sequence(sub_process()), vec![]));
+
+        assert_noop!(
+            BpmnInterpreter::set_multi_instance(Origin::signed(BOB), 1, 2, definition.clone()),
+            Error::<Test>::Unauthorized
+        );
+        assert_noop!(
+            BpmnInterpreter::set_multi_instance(Origin::signed(ALICE), 2, 2, definition.clone()),
+            Error::<Test>::InvalidMultiInstance
+        );
+        assert_noop!(
+            BpmnInterpreter::set_multi_instance(Origin::signed(ALICE), 1, 3, definition.clone()),
+            Error::<Test>::InvalidMultiInstance
+        );
+        assert_ok!(BpmnInterpreter::set_multi_instance(
+            Origin::signed(ALICE),
+            1,
+            2,
+            definition.clone()
+        ));
+        assert_eq!(
+            BpmnInterpreter::iflow_by_id(1).get_multi_instance(2),
+            Some(&definition)
+        );
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event
+                == TestEvent::bpmn_interpreter(RawEvent::MultiInstanceSet(1, 2))));
+    });
+}
+
+#[test]
+fn multi_instance_activities_ask_the_contract_for_their_instances_and_completion() {
+    new_test_ext().execute_with(|| {
+        const ORDER: [u8; 32] = [1; 32];
+        let correlation_key = H256::repeat_byte(7);
+        // Parallel multi-instance activity 2 of process 2 over receive tasks of process 1
+        let mut child_model = sequence(ElementType::Task(TaskKind::Receive));
+        child_model[1].event_code = ORDER;
+        assert_ok!(deploy(child_model, vec![]));
+        assert_ok!(deploy(
+            sequence(ElementType::SubProcess {
+                kind: SubProcessKind::Embedded,
+                multi_instance: MultiInstance::Parallel,
+            }),
+            vec![link(2, 1, vec![])]
+        ));
+        for process_id in 1..=2 {
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+        }
+        assert_ok!(BpmnInterpreter::set_multi_instance(
+            Origin::signed(ALICE),
+            2,
+            2,
+            MultiInstanceDefinition {
+                cardinality_selector: vec![1, 2, 3, 4],
+                completion_selector: vec![5, 6, 7, 8],
+            }
+        ));
+
+        // Three instances instead of the one linked
+        MockScripts::answer_data(2, 3u128);
+        let root = BpmnInterpreter::instance_id_count() + 1;
+        assert_ok!(BpmnInterpreter::create_root_instance(
+            Origin::signed(ALICE),
+            2
+        ));
+        let children: Vec<_> = (root + 1..=BpmnInterpreter::instance_id_count()).collect();
+        assert_eq!(children.len(), 3);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(root)
+                .get_instance_count(2)
+                .ok(),
+            Some(3)
+        );
+
+        // The first instance completed meets the completion condition, the others are
+        // cancelled
+        MockScripts::answer_data(2, true);
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            children[0],
+            correlation_key
+        ));
+        assert_ok!(BpmnInterpreter::send_message(
+            Origin::signed(ALICE),
+            correlation_key,
+            ORDER,
+            vec![]
+        ));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(children[0]).get_status(),
+            InstanceStatus::Completed
+        );
+        for &child in &children[1..] {
+            assert_eq!(
+                BpmnInterpreter::idata_by_id(child).get_status(),
+                InstanceStatus::Terminated
+            );
+        }
+        let idata = BpmnInterpreter::idata_by_id(root);
+        assert_eq!(idata.get_instance_count(2).ok(), Some(0));
+        assert_eq!(idata.get_status(), InstanceStatus::Completed);
+    });
+}
+
+#[test]
+fn multi_instance_activities_run_at_most_max_sub_processes_instances() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Receive)),
+            vec![]
+        ));
+        let parent_model = sequence(ElementType::SubProcess {
+            kind: SubProcessKind::Embedded,
+            multi_instance: MultiInstance::Parallel,
+        });
+        let mut too_many = link(2, 1, vec![]);
+        too_many.count_instances = MAX_SUB_PROCESSES as u128 + 1;
+        assert_noop!(
+            deploy(parent_model.clone(), vec![too_many]),
+            Error::<Test>::TooManyInstances
+        );
+        assert_ok!(deploy(parent_model, vec![link(2, 1, vec![])]));
+        assert_noop!(
+            BpmnInterpreter::link_sub_process(
+                Origin::signed(ALICE),
+                2,
+                2,
+                1,
+                vec![],
+                MAX_SUB_PROCESSES as u128 + 1
+            ),
+            Error::<Test>::TooManyInstances
+        );
+
+        // Nor as many as the contract answers
+        <IflowById<Test>>::mutate(2, |iflow| iflow.factory.address = Some(BOB));
+        assert_ok!(BpmnInterpreter::set_multi_instance(
+            Origin::signed(ALICE),
+            2,
+            2,
+            MultiInstanceDefinition {
+                cardinality_selector: vec![1, 2, 3, 4],
+                completion_selector: vec![],
+            }
+        ));
+        MockScripts::answer_data(2, MAX_SUB_PROCESSES as u128 + 1);
+        assert_noop!(
+            BpmnInterpreter::create_root_instance(Origin::signed(ALICE), 2),
+            Error::<Test>::TooManyInstances
+        );
+    });
+}
+
+#[test]
+fn standard_loops_iterate_up_to_their_maximum() {
+    new_test_ext().execute_with(|| {
+        let standard_loop = |test_before| ElementType::SubProcess {
+            kind: SubProcessKind::Embedded,
+            multi_instance: MultiInstance::StandardLoop { test_before },
+        };
+        // Iterations of the loop activity of a fresh root instance of the model
+        let create = |process_id| {
+            let root = BpmnInterpreter::instance_id_count() + 1;
+            assert_ok!(BpmnInterpreter::create_root_instance(
+                Origin::signed(ALICE),
+                process_id
+            ));
+            assert_eq!(
+                BpmnInterpreter::idata_by_id(root).get_status(),
+                InstanceStatus::Completed
+            );
+            (root + 1..=BpmnInterpreter::instance_id_count())
+                .map(|child| BpmnInterpreter::idata_by_id(child).get_loop_counter())
+                .collect::<Vec<_>>()
+        };
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Default)),
+            vec![]
+        ));
+        assert_ok!(deploy(
+            sequence(standard_loop(false)),
+            vec![link(2, 1, vec![])]
+        ));
+        assert_ok!(deploy(
+            sequence(standard_loop(true)),
+            vec![link(2, 1, vec![])]
+        ));
+        for process_id in 1..=3 {
+            <IflowById<Test>>::mutate(process_id, |iflow| iflow.factory.address = Some(BOB));
+        }
+
+        // Without a loop condition, tested after the only iteration or before none
+        assert_eq!(create(2), vec![0]);
+        assert!(create(3).is_empty());
+
+        // Without a condition selector, up to the maximum
+        let up_to = |maximum| LoopDefinition {
+            condition_selector: vec![],
+            maximum,
+        };
+        for process_id in 2..=3 {
+            assert_ok!(BpmnInterpreter::set_loop(
+                Origin::signed(ALICE),
+                process_id,
+                2,
+                up_to(Some(3))
+            ));
+            assert_eq!(create(process_id), vec![0, 1, 2]);
+        }
+
+        assert_noop!(
+            BpmnInterpreter::set_loop(Origin::signed(BOB), 2, 2, up_to(None)),
+            Error::<Test>::Unauthorized
+        );
+        assert_noop!(
+            BpmnInterpreter::set_loop(Origin::signed(ALICE), 1, 2, up_to(None)),
+            Error::<Test>::InvalidLoop
+        );
+        assert_noop!(
+            BpmnInterpreter::set_loop(Origin::signed(ALICE), 2, 2, up_to(Some(0))),
+            Error::<Test>::InvalidLoop
+        );
+        assert_noop!(
+            BpmnInterpreter::set_loop(
+                Origin::signed(ALICE),
+                2,
+                2,
+                up_to(Some(MAX_LOOP_ITERATIONS + 1))
+            ),
+            Error::<Test>::InvalidLoop
+        );
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event == TestEvent::bpmn_interpreter(RawEvent::LoopSet(3, 2))));
+    });
+}
+
+#[test]
+fn runtime_api_reports_instances_and_models() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(deploy(sequence(ElementType::Task(TaskKind::User)), vec![]));
+        assert_ok!(deploy(sequence(sub_process()), vec![link(2, 1, vec![])]));
+
+        let mut parent = Idata::default();
+        parent.set_parent(None, 2, 0);
+        parent.set_activity_marking(BitSet::from(0b100));
+        parent.add_child(2, 3);
+        <IdataById<Test>>::insert(2, parent);
+        let mut child = Idata::default();
+        child.set_parent(Some(2), 1, 2);
+        child.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(3, child);
+
+        let state = BpmnInterpreter::instance_state(3).unwrap();
+        assert_eq!(state.parent, Some(2));
+        assert_eq!(state.marking, vec![0]);
+        assert_eq!(state.enabled_elements, vec![2]);
+        assert_eq!(BpmnInterpreter::instance_state(4), None);
+
+        let tree = BpmnInterpreter::sub_process_tree(2);
+        let instances: Vec<_> = tree.iter().map(|node| node.instance).collect();
+        assert_eq!(instances, vec![2, 3]);
+
+        let model = BpmnInterpreter::process_model(2).unwrap();
+        assert_eq!(model.start_event, 1);
+        assert_eq!(model.elements.len(), 3);
+        assert_eq!(model.sub_processes[0].child_flow, 1);
+        assert_eq!(BpmnInterpreter::process_model(3), None);
+    });
+}
+
+fn fire_timers_at(moment: u64) {
+    Timestamp::set_timestamp(moment);
+    BpmnInterpreter::on_initialize(System::block_number());
+}
+
+#[test]
+fn timer_start_and_intermediate_events_fire_once_due() {
+    new_test_ext().execute_with(|| {
+        let user_task = ElementType::Task(TaskKind::User);
+        // Timer start event followed by a user task
+        assert_ok!(deploy(
+            vec![
+                element(
+                    1,
+                    0,
+                    0b01,
+                    timer_event(EventPosition::Start, false),
+                    vec![2]
+                ),
+                element(2, 0b01, 0b10, user_task, vec![]),
+            ],
+            vec![]
+        ));
+        <IflowById<Test>>::mutate(1, |iflow| iflow.factory.address = Some(BOB));
+        assert_noop!(
+            BpmnInterpreter::set_timer(Origin::signed(BOB), 1, 1, TimerDefinition::Date(5)),
+            Error::<Test>::Unauthorized
+        );
+        assert_noop!(
+            BpmnInterpreter::set_timer(Origin::signed(ALICE), 1, 2, TimerDefinition::Date(5)),
+            Error::<Test>::InvalidTimer
+        );
+        assert_ok!(BpmnInterpreter::set_timer(
+            Origin::signed(ALICE),
+            1,
+            1,
+            TimerDefinition::Date(5)
+        ));
+
+        fire_timers_at(4);
+        assert_eq!(BpmnInterpreter::instance_id_count(), 0);
+        fire_timers_at(5);
+        let instance_id = BpmnInterpreter::instance_id_count();
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(instance_id).get_marking(),
+            &BitSet::from(0b10)
+        );
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event == TestEvent::bpmn_interpreter(RawEvent::TimerFired(1, 1))));
+
+        // Intermediate timer between the start event and a user task
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b001, start_event(), vec![2]),
+                element(
+                    2,
+                    0b001,
+                    0b010,
+                    timer_event(EventPosition::IntermediateCatch, false),
+                    vec![3]
+                ),
+                element(3, 0b010, 0b100, user_task, vec![]),
+            ],
+            vec![]
+        ));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 2, 0);
+        idata.set_marking(BitSet::from(0b001));
+        <IdataById<Test>>::insert(2, idata);
+        assert_noop!(
+            BpmnInterpreter::continue_execution(Origin::signed(ALICE), 2, 2).map_err(|e| e.error),
+            Error::<Test>::TimerNotSet
+        );
+        assert_ok!(BpmnInterpreter::set_timer(
+            Origin::signed(ALICE),
+            2,
+            2,
+            TimerDefinition::Duration(10)
+        ));
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            2,
+            2
+        ));
+        assert!(BpmnInterpreter::idata_by_id(2).get_marking().is_empty());
+        assert_eq!(BpmnInterpreter::armed_timer(2, 2), Some(15));
+
+        fire_timers_at(14);
+        assert!(BpmnInterpreter::idata_by_id(2).get_marking().is_empty());
+        fire_timers_at(15);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(2).get_marking(),
+            &BitSet::from(0b100)
+        );
+        assert_eq!(BpmnInterpreter::armed_timer(2, 2), None);
+        assert!(<TimerQueue<Test>>::iter().next().is_none());
+    });
+}
+
+/// Parent process 2 waiting on its sub-process 2, instance 3 of process 1, which has the
+/// boundary timer 4 leading to a user task.
+fn boundary_timer_process(interrupting: bool, timer: TimerDefinition<u64>) {
+    assert_ok!(deploy(sequence(ElementType::Task(TaskKind::User)), vec![]));
+    assert_ok!(deploy(
+        vec![
+            element(1, 0, 0b01, start_event(), vec![2]),
+            element(2, 0b01, 0b10, sub_process(), vec![3]),
+            element(3, 0b10, 0, end_event(), vec![]),
+            element(
+                4,
+                0,
+                0b100,
+                timer_event(EventPosition::Boundary, interrupting),
+                vec![5]
+            ),
+            element(5, 0b100, 0b1000, ElementType::Task(TaskKind::User), vec![]),
+        ],
+        vec![link(2, 1, vec![4])]
+    ));
+    assert_ok!(BpmnInterpreter::set_timer(
+        Origin::signed(ALICE),
+        2,
+        4,
+        timer
+    ));
+
+    let mut parent = Idata::default();
+    parent.set_parent(None, 2, 0);
+    parent.set_activity_marking(BitSet::from(0b100));
+    parent.add_child(2, 3);
+    <IdataById<Test>>::insert(2, parent);
+    let mut child = Idata::default();
+    child.set_parent(Some(2), 1, 2);
+    child.set_marking(BitSet::from(0b01));
+    <IdataById<Test>>::insert(3, child);
+
+    let iflow = BpmnInterpreter::iflow_by_id(2);
+    assert_ok!(BpmnInterpreter::arm_boundary_events(
+        &mut ExecutionMeter::default(),
+        2,
+        &iflow,
+        2
+    ));
+}
+
+#[test]
+fn interrupting_boundary_timers_cancel_the_activity() {
+    new_test_ext().execute_with(|| {
+        boundary_timer_process(true, TimerDefinition::Duration(10));
+
+        fire_timers_at(10);
+        let parent = BpmnInterpreter::idata_by_id(2);
+        assert_eq!(parent.get_marking(), &BitSet::from(0b1000));
+        assert!(parent.get_started_activities().is_empty());
+        assert!(BpmnInterpreter::idata_by_id(3).get_marking().is_empty());
+        assert!(<TimerQueue<Test>>::iter().next().is_none());
+    });
+}
+
+#[test]
+fn non_interrupting_boundary_timers_repeat_while_the_activity_runs() {
+    new_test_ext().execute_with(|| {
+        let cycle = TimerDefinition::Cycle {
+            interval: 10,
+            repetitions: Some(3),
+        };
+        boundary_timer_process(false, cycle);
+
+        fire_timers_at(10);
+        let parent = BpmnInterpreter::idata_by_id(2);
+        assert_eq!(parent.get_marking(), &BitSet::from(0b1000));
+        assert!(parent.get_started_activities().contains(2));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(3).get_marking(),
+            &BitSet::from(0b01)
+        );
+        assert_eq!(BpmnInterpreter::armed_timer(2, 4), Some(20));
+
+        // Once the activity completed, the cycle stops
+        <IdataById<Test>>::mutate(2, |parent| parent.set_activity_marking(BitSet::new()));
+        fire_timers_at(20);
+        assert_eq!(BpmnInterpreter::armed_timer(2, 4), None);
+        assert!(<TimerQueue<Test>>::iter().next().is_none());
+    });
+}
+
+#[test]
+fn messages_are_delivered_to_correlated_instances() {
+    new_test_ext().execute_with(|| {
+        const ORDER: [u8; 32] = [1; 32];
+        let correlation_key = H256::repeat_byte(7);
+        let message_event = |position| ElementType::Event {
+            position,
+            kind: EventKind::Message,
+            interrupting: false,
+        };
+        let with_event_code = |mut element: ElementDefinition| {
+            element.event_code = ORDER;
+            element
+        };
+        let user_task = ElementType::Task(TaskKind::User);
+        let instance = |process_id| {
+            let mut idata = Idata::default();
+            idata.set_parent(None, process_id, 0);
+            idata.set_marking(BitSet::from(0b001));
+            <IdataById<Test>>::insert(process_id, idata);
+        };
+
+        // Supplier waiting for the order on a receive task
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b001, start_event(), vec![2]),
+                with_event_code(element(
+                    2,
+                    0b001,
+                    0b010,
+                    ElementType::Task(TaskKind::Receive),
+                    vec![3]
+                )),
+                element(3, 0b010, 0b100, user_task, vec![]),
+            ],
+            vec![]
+        ));
+        instance(1);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        assert!(BpmnInterpreter::idata_by_id(1).get_marking().is_empty());
+        assert_eq!(BpmnInterpreter::message_subscriptions(1, ORDER), vec![2]);
+
+        let send = |origin| {
+            BpmnInterpreter::send_message(origin, correlation_key, ORDER, b"order".to_vec())
+                .map_err(|e| e.error)
+        };
+        assert_noop!(
+            send(Origin::signed(ALICE)),
+            Error::<Test>::MessageNotCorrelated
+        );
+        assert_noop!(
+            BpmnInterpreter::set_correlation_key(Origin::signed(BOB), 1, correlation_key),
+            Error::<Test>::Unauthorized
+        );
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            1,
+            correlation_key
+        ));
+        // Only the accounts allowed to continue the instance may send it messages
+        assert_noop!(
+            send(Origin::signed(BOB)),
+            Error::<Test>::MessageNotCorrelated
+        );
+
+        assert_ok!(send(Origin::signed(ALICE)));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_marking(),
+            &BitSet::from(0b100)
+        );
+        assert!(BpmnInterpreter::message_subscriptions(1, ORDER).is_empty());
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::MessageReceived(1, 2, b"order".to_vec()))));
+
+        // Supplier waiting for the order on an intermediate event, thrown by the buyer
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b001, start_event(), vec![2]),
+                with_event_code(element(
+                    2,
+                    0b001,
+                    0b010,
+                    message_event(EventPosition::IntermediateCatch),
+                    vec![3]
+                )),
+                element(3, 0b010, 0b100, user_task, vec![]),
+            ],
+            vec![]
+        ));
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b01, start_event(), vec![2]),
+                with_event_code(element(
+                    2,
+                    0b01,
+                    0b10,
+                    message_event(EventPosition::IntermediateThrow),
+                    vec![]
+                )),
+            ],
+            vec![]
+        ));
+        instance(2);
+        instance(3);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            2,
+            2
+        ));
+        assert_eq!(BpmnInterpreter::message_subscriptions(2, ORDER), vec![2]);
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            2,
+            correlation_key
+        ));
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            3,
+            correlation_key
+        ));
+        assert_eq!(
+            BpmnInterpreter::correlated_instances(correlation_key),
+            vec![1, 2, 3]
+        );
+
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            3,
+            2
+        ));
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(2).get_marking(),
+            &BitSet::from(0b100)
+        );
+        assert!(BpmnInterpreter::message_subscriptions(2, ORDER).is_empty());
+    });
+}
+
+#[test]
+fn event_based_gateways_take_the_first_event_caught() {
+    new_test_ext().execute_with(|| {
+        const ORDER: [u8; 32] = [1; 32];
+        let correlation_key = H256::repeat_byte(7);
+        let event_based = ElementType::Gateway {
+            kind: GatewayKind::EventBased,
+            join: false,
+        };
+        let user_task = ElementType::Task(TaskKind::User);
+        let mut message_catch = element(
+            3,
+            0b0010,
+            0b01000,
+            ElementType::Event {
+                position: EventPosition::IntermediateCatch,
+                kind: EventKind::Message,
+                interrupting: false,
+            },
+            vec![5],
+        );
+        message_catch.event_code = ORDER;
+        let model = |gateway_next| {
+            vec![
+                element(1, 0, 0b0001, start_event(), vec![2]),
+                element(2, 0b0001, 0b0110, event_based, gateway_next),
+                message_catch.clone(),
+                element(
+                    4,
+                    0b0100,
+                    0b10000,
+                    timer_event(EventPosition::IntermediateCatch, false),
+                    vec![6],
+                ),
+                element(5, 0b01000, 1 << 5, user_task, vec![]),
+                element(6, 0b10000, 1 << 6, user_task, vec![]),
+            ]
+        };
+        assert_noop!(
+            deploy(model(vec![3, 5]), vec![]),
+            Error::<Test>::InvalidEventBasedGateway
+        );
+        assert_ok!(deploy(model(vec![3, 4]), vec![]));
+        assert_ok!(BpmnInterpreter::set_timer(
+            Origin::signed(ALICE),
+            1,
+            4,
+            TimerDefinition::Duration(10)
+        ));
+        for instance_id in 1..=2 {
+            let mut idata = Idata::default();
+            idata.set_parent(None, 1, 0);
+            idata.set_marking(BitSet::from(0b0001));
+            <IdataById<Test>>::insert(instance_id, idata);
+            assert_ok!(BpmnInterpreter::continue_execution(
+                Origin::signed(ALICE),
+                instance_id,
+                2
+            ));
+            let idata = BpmnInterpreter::idata_by_id(instance_id);
+            assert!(idata.get_marking().is_empty());
+            assert_eq!(idata.get_started_activities(), &BitSet::from(0b11100));
+            assert_eq!(BpmnInterpreter::armed_timer(instance_id, 4), Some(10));
+            assert_eq!(
+                BpmnInterpreter::message_subscriptions(instance_id, ORDER),
+                vec![3]
+            );
+        }
+
+        // The message reaches the first instance before its timer is due
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            1,
+            correlation_key
+        ));
+        assert_ok!(BpmnInterpreter::send_message(
+            Origin::signed(ALICE),
+            correlation_key,
+            ORDER,
+            vec![]
+        ));
+        let idata = BpmnInterpreter::idata_by_id(1);
+        assert_eq!(idata.get_marking(), &BitSet::from(1 << 5));
+        assert!(idata.get_started_activities().is_empty());
+        assert_eq!(BpmnInterpreter::armed_timer(1, 4), None);
+        assert_eq!(
+            BpmnInterpreter::timer_queue(0)
+                .iter()
+                .map(|timer| timer.instance_id)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::TokenMoved(1, 2, BitSet::from(0b0010)))));
+
+        // The timer of the second instance is due before any message
+        fire_timers_at(10);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(1).get_marking(),
+            &BitSet::from(1 << 5)
+        );
+        let idata = BpmnInterpreter::idata_by_id(2);
+        assert_eq!(idata.get_marking(), &BitSet::from(1 << 6));
+        assert!(idata.get_started_activities().is_empty());
+        assert!(BpmnInterpreter::message_subscriptions(2, ORDER).is_empty());
+        assert!(<TimerQueue<Test>>::iter().next().is_none());
+    });
+}
+
+#[test]
+fn signals_reach_only_the_events_named_by_their_code() {
+    new_test_ext().execute_with(|| {
+        const RED: [u8; 32] = [1; 32];
+        const BLUE: [u8; 32] = [2; 32];
+        const ORDER: [u8; 32] = [3; 32];
+        let event_based = ElementType::Gateway {
+            kind: GatewayKind::EventBased,
+            join: false,
+        };
+        let event = |position, kind| ElementType::Event {
+            position,
+            kind,
+            interrupting: false,
+        };
+        let catch = |element_index, edge, kind, event_code, next| ElementDefinition {
+            event_code,
+            ..element(
+                element_index,
+                1 << edge,
+                1 << (edge + 4),
+                event(EventPosition::IntermediateCatch, kind),
+                vec![next],
+            )
+        };
+        let split = ElementType::Gateway {
+            kind: GatewayKind::Parallel,
+            join: false,
+        };
+        let user_task = ElementType::Task(TaskKind::User);
+        // A parallel split to two event-based gateways, each waiting on a signal of its own or
+        // on a message, and to a throw event of the first signal
+        let mut elements = vec![
+            element(1, 0, 0b1, start_event(), vec![2]),
+            element(2, 0b1, 0b1110, split, vec![3, 4, 9]),
+            element(3, 0b0010, 0b0011_0000, event_based, vec![5, 6]),
+            element(4, 0b0100, 0b1100_0000, event_based, vec![7, 8]),
+            catch(5, 4, EventKind::Signal, RED, 10),
+            catch(6, 5, EventKind::Message, ORDER, 11),
+            catch(7, 6, EventKind::Signal, BLUE, 12),
+            catch(8, 7, EventKind::Message, ORDER, 13),
+            ElementDefinition {
+                event_code: RED,
+                ..element(
+                    9,
+                    0b1000,
+                    1 << 12,
+                    event(EventPosition::IntermediateThrow, EventKind::Signal),
+                    vec![],
+                )
+            },
+        ];
+        for task in 10..14 {
+            elements.push(element(
+                task,
+                1 << (task - 2),
+                1 << (task + 3),
+                user_task,
+                vec![],
+            ));
+        }
+        assert_ok!(deploy(elements, vec![]));
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        idata.set_marking(BitSet::from(0b1));
+        <IdataById<Test>>::insert(1, idata);
+
+        // The signal thrown is caught by the first gateway only
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        let idata = BpmnInterpreter::idata_by_id(1);
+        assert_eq!(idata.get_marking(), &BitSet::from((1 << 12) | (1 << 13)));
+        assert_eq!(idata.get_started_activities(), &BitSet::from(0b1_1001_0000));
+        assert!(System::events().iter().any(|record| record.event
+            == TestEvent::bpmn_interpreter(RawEvent::TokenMoved(1, 3, BitSet::with_bit(4)))));
+        assert_eq!(BpmnInterpreter::message_subscriptions(1, ORDER), vec![8]);
+
+        // The other gateway still takes the message
+        let correlation_key = H256::repeat_byte(7);
+        assert_ok!(BpmnInterpreter::set_correlation_key(
+            Origin::signed(ALICE),
+            1,
+            correlation_key
+        ));
+        assert_ok!(BpmnInterpreter::send_message(
+            Origin::signed(ALICE),
+            correlation_key,
+            ORDER,
+            vec![]
+        ));
+        let idata = BpmnInterpreter::idata_by_id(1);
+        assert!(idata.get_started_activities().is_empty());
+        assert_eq!(
+            idata.get_marking(),
+            &BitSet::from((1 << 12) | (1 << 13) | (1 << 16))
+        );
+    });
+}
+
+#[test]
+fn completed_activities_are_compensated_in_reverse_order() {
+    new_test_ext().execute_with(|| {
+        let user_task = ElementType::Task(TaskKind::User);
+        let compensation = |position| ElementType::Event {
+            position,
+            kind: EventKind::Compensation,
+            interrupting: false,
+        };
+        let boundary = compensation(EventPosition::Boundary);
+        // Two user tasks followed by a compensation end event, each task with a compensation
+        // boundary event leading to its handler
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b00001, start_event(), vec![2]),
+                element(2, 0b00001, 0b00010, user_task, vec![3]),
+                element(3, 0b00010, 0b00100, user_task, vec![4]),
+                element(4, 0b00100, 0, compensation(EventPosition::End), vec![]),
+                element(5, 0, 0b01000, boundary, vec![6]),
+                element(6, 0b01000, 0, user_task, vec![]),
+                element(7, 0, 0b10000, boundary, vec![8]),
+                element(8, 0b10000, 0, user_task, vec![]),
+            ],
+            vec![]
+        ));
+        assert_noop!(
+            BpmnInterpreter::set_compensation(Origin::signed(BOB), 1, 2, 5),
+            Error::<Test>::Unauthorized
+        );
+        assert_noop!(
+            BpmnInterpreter::set_compensation(Origin::signed(ALICE), 1, 2, 6),
+            Error::<Test>::InvalidCompensation
+        );
+        assert_noop!(
+            BpmnInterpreter::set_compensation(Origin::signed(ALICE), 1, 5, 7),
+            Error::<Test>::InvalidCompensation
+        );
+        assert_ok!(BpmnInterpreter::set_compensation(
+            Origin::signed(ALICE),
+            1,
+            2,
+            5
+        ));
+        assert_ok!(BpmnInterpreter::set_compensation(
+            Origin::signed(ALICE),
+            1,
+            3,
+            7
+        ));
+
+        let mut idata = Idata::default();
+        idata.set_parent(None, 1, 0);
+        idata.set_marking(BitSet::from(0b00001));
+        <IdataById<Test>>::insert(1, idata);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            1,
+            2
+        ));
+        let idata = BpmnInterpreter::idata_by_id(1);
+        assert_eq!(idata.get_status(), InstanceStatus::Completed);
+        assert!(idata.get_marking().is_empty());
+
+        let position = |event| {
+            System::events()
+                .iter()
+                .position(|record| record.event == TestEvent::bpmn_interpreter(event))
+        };
+        let compensated_last = position(RawEvent::ActivityCompensated(1, 3)).unwrap();
+        let compensated_first = position(RawEvent::ActivityCompensated(1, 2)).unwrap();
+        assert!(compensated_last < compensated_first);
+        assert!(position(RawEvent::ElementCompleted(1, 8)).unwrap() < compensated_last);
+        assert!(position(RawEvent::ElementCompleted(1, 6)).unwrap() < compensated_first);
+    });
+}
+
+#[test]
+fn errors_are_caught_by_their_code_before_catch_all_events() {
+    new_test_ext().execute_with(|| {
+        const PAYMENT_FAILED: [u8; 32] = [1; 32];
+        const TIMEOUT: [u8; 32] = [2; 32];
+        let user_task = ElementType::Task(TaskKind::User);
+        let error_event = |position| ElementType::Event {
+            position,
+            kind: EventKind::Error,
+            interrupting: position == EventPosition::Boundary,
+        };
+        let with_event_code = |mut element: ElementDefinition, event_code| {
+            element.event_code = event_code;
+            element
+        };
+        // Sub-process throwing the error, and its parent catching errors on the boundary events
+        // 4, any error, and 6, payment failures
+        let deploy_throwing = |event_code| {
+            let mut throwing = sequence(user_task);
+            throwing[2] = with_event_code(
+                element(3, 0b10, 0, error_event(EventPosition::End), vec![]),
+                event_code,
+            );
+            assert_ok!(deploy(throwing, vec![]));
+            BpmnInterpreter::process_id_count()
+        };
+        let deploy_catching = |child_flow| {
+            let boundary = error_event(EventPosition::Boundary);
+            assert_ok!(deploy(
+                vec![
+                    element(1, 0, 0b0001, start_event(), vec![2]),
+                    element(2, 0b0001, 0b0010, sub_process(), vec![3]),
+                    element(3, 0b0010, 0, end_event(), vec![]),
+                    element(4, 0, 0b0100, boundary, vec![5]),
+                    element(5, 0b0100, 1 << 4, user_task, vec![]),
+                    with_event_code(element(6, 0, 1 << 5, boundary, vec![7]), PAYMENT_FAILED),
+                    element(7, 1 << 5, 1 << 6, user_task, vec![]),
+                ],
+                vec![link(2, child_flow, vec![4, 6])]
+            ));
+            BpmnInterpreter::process_id_count()
+        };
+        let run = |parent_flow, child_flow, parent_case, child_case| {
+            let mut parent = Idata::default();
+            parent.set_parent(None, parent_flow, 0);
+            parent.set_activity_marking(BitSet::from(0b100));
+            parent.add_child(2, child_case);
+            parent.set_instance_count(2, 1);
+            <IdataById<Test>>::insert(parent_case, parent);
+            let mut child = Idata::default();
+            child.set_parent(Some(parent_case), child_flow, 2);
+            child.set_marking(BitSet::from(0b01));
+            <IdataById<Test>>::insert(child_case, child);
+            assert_ok!(BpmnInterpreter::continue_execution(
+                Origin::signed(ALICE),
+                child_case,
+                2
+            ));
+            assert_eq!(
+                BpmnInterpreter::idata_by_id(child_case).get_status(),
+                InstanceStatus::Terminated
+            );
+            BpmnInterpreter::idata_by_id(parent_case)
+                .get_marking()
+                .clone()
+        };
+
+        // No boundary event has the code of the timeout, the catch-all one catches it
+        let child_flow = deploy_throwing(TIMEOUT);
+        let parent_flow = deploy_catching(child_flow);
+        assert_eq!(run(parent_flow, child_flow, 10, 11), BitSet::from(1 << 4));
+
+        // The payment failure is caught by the boundary event with its code
+        let child_flow = deploy_throwing(PAYMENT_FAILED);
+        let parent_flow = deploy_catching(child_flow);
+        assert_eq!(run(parent_flow, child_flow, 20, 21), BitSet::from(1 << 6));
+    });
+}
+
+#[test]
+fn errors_start_the_event_sub_processes_catching_them() {
+    new_test_ext().execute_with(|| {
+        let user_task = ElementType::Task(TaskKind::User);
+        let mut throwing = sequence(user_task);
+        throwing[2].element_type = ElementType::Event {
+            position: EventPosition::End,
+            kind: EventKind::Error,
+            interrupting: false,
+        };
+        assert_ok!(deploy(throwing, vec![]));
+        let child_flow = BpmnInterpreter::process_id_count();
+        assert_ok!(deploy(
+            sequence(ElementType::Task(TaskKind::Receive)),
+            vec![]
+        ));
+        let handler_flow = BpmnInterpreter::process_id_count();
+        // The error event sub-process 5 is started by its catch event 4 of the parent
+        assert_ok!(deploy(
+            vec![
+                element(1, 0, 0b01, start_event(), vec![2]),
+                element(2, 0b01, 0b10, sub_process(), vec![3]),
+                element(3, 0b10, 0, end_event(), vec![]),
+                element(
+                    4,
+                    0,
+                    0,
+                    ElementType::Event {
+                        position: EventPosition::EventSubProcessStart,
+                        kind: EventKind::Error,
+                        interrupting: false,
+                    },
+                    vec![]
+                ),
+                element(
+                    5,
+                    0,
+                    0,
+                    ElementType::SubProcess {
+                        kind: SubProcessKind::EventSubProcess,
+                        multi_instance: MultiInstance::None,
+                    },
+                    vec![]
+                ),
+            ],
+            vec![link(2, child_flow, vec![]), link(5, handler_flow, vec![4])]
+        ));
+        let parent_flow = BpmnInterpreter::process_id_count();
+
+        let mut parent = Idata::default();
+        parent.set_parent(None, parent_flow, 0);
+        parent.set_activity_marking(BitSet::from(0b100));
+        parent.add_child(2, 11);
+        parent.set_instance_count(2, 1);
+        <IdataById<Test>>::insert(10, parent);
+        let mut child = Idata::default();
+        child.set_parent(Some(10), child_flow, 2);
+        child.set_marking(BitSet::from(0b01));
+        <IdataById<Test>>::insert(11, child);
+        assert_ok!(BpmnInterpreter::continue_execution(
+            Origin::signed(ALICE),
+            11,
+            2
+        ));
+
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(11).get_status(),
+            InstanceStatus::Terminated
+        );
+        let parent = BpmnInterpreter::idata_by_id(10);
+        assert_eq!(parent.get_status(), InstanceStatus::Running);
+        assert!(parent.get_started_activities().contains(5));
+        let handlers = parent.get_child_process_instances(5).unwrap();
+        assert_eq!(handlers.len(), 1);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(handlers[0]).get_started_activities(),
+            &BitSet::with_bit(2)
+        );
+        assert!(System::events()
+            .iter()
+            .any(|record| record.event
+                == TestEvent::bpmn_interpreter(RawEvent::ElementStarted(10, 5))));
+    });
+}
+
+#[test]
+fn migrates_the_storage_of_the_original_interpreter() {
+    new_test_ext().execute_with(|| {
+        use frame_support::storage::unhashed;
+
+        // Start event, user task and end event, encoded as the original interpreter stored
+        // them under identifiers chosen by the caller, the shared counter left at 0
+        let user_task = ElementType::Task(TaskKind::User).type_info();
+        let legacy_iflow = (
+            1u128,
+            vec![
+                (1u128, [0u128, 0b01, start_event().type_info()]),
+                (2, [0b01, 0b10, user_task]),
+                (3, [0b10, 0, end_event().type_info()]),
+            ]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+            vec![(1u128, vec![2u128]), (2, vec![3]), (3, vec![])]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>(),
+            Vec::<u128>::new(),
+            vec![1u128, 3],
+            BTreeMap::<u128, u128>::new(),
+            vec![(1u128, [0u8; 32]), (3, [0; 32])]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>(),
+            BTreeMap::<u128, u64>::new(),
+            BTreeMap::<u128, u128>::new(),
+            Ifactory::<Test>::default(),
+        );
+        let legacy_idata = |tokens_on_edges: u128, started_activities: u128| {
+            (
+                tokens_on_edges,
+                started_activities,
+                None::<u64>,
+                7u64,
+                0u128,
+                BTreeMap::<u128, Vec<u64>>::new(),
+                BTreeMap::<u128, u128>::new(),
+            )
+        };
+        unhashed::put(&<IflowById<Test>>::hashed_key_for(7), &legacy_iflow);
+        unhashed::put(
+            &<IdataById<Test>>::hashed_key_for(9),
+            &legacy_idata(0, 0b100),
+        );
+        unhashed::put(&<IdataById<Test>>::hashed_key_for(4), &legacy_idata(0, 0));
+        StorageVersion::put(Releases::V1BitMask);
+
+        migration::migrate::<Test>();
+
+        assert_eq!(StorageVersion::get(), Releases::V2BitSet);
+        let iflow = BpmnInterpreter::iflow_by_id(7);
+        assert_eq!(iflow.get_first_elem(), 1);
+        assert_eq!(iflow.get_pre_condition(2), BitSet::from(0b01));
+        assert_eq!(iflow.get_post_condition(2), BitSet::from(0b10));
+        assert_eq!(iflow.get_type_info(2), user_task);
+        assert_eq!(BpmnInterpreter::process_owner(7), None);
+        let running = BpmnInterpreter::idata_by_id(9);
+        assert_eq!(running.get_started_activities(), &BitSet::with_bit(2));
+        assert_eq!(running.get_status(), InstanceStatus::Running);
+        assert_eq!(running.get_flow_node(), 7);
+        assert_eq!(
+            BpmnInterpreter::idata_by_id(4).get_status(),
+            InstanceStatus::Completed
+        );
+        assert_eq!(BpmnInterpreter::process_id_count(), 7);
+        assert_eq!(BpmnInterpreter::instance_id_count(), 9);
+
+        // Models and instances are numbered after those migrated
+        assert_ok!(deploy(sequence(ElementType::Task(TaskKind::User)), vec![]));
+        assert_eq!(BpmnInterpreter::process_id_count(), 8);
+        <IflowById<Test>>::mutate(8, |iflow| iflow.factory.address = Some(BOB));
+        assert_ok!(BpmnInterpreter::create_root_instance(
+            Origin::signed(ALICE),
+            8
+        ));
+        assert_eq!(BpmnInterpreter::instance_id_count(), 10);
+        assert_eq!(BpmnInterpreter::idata_by_id(10).get_flow_node(), 8);
+        assert_eq!(BpmnInterpreter::idata_by_id(9).get_flow_node(), 7);
+
+        // Migrated once
+        migration::migrate::<Test>();
+        assert_eq!(BpmnInterpreter::process_id_count(), 8);
+    });
+}
+
+/// Xorshift generator, so that a failing case can be replayed from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// Mostly small indexes, so that lookups hit, with the occasional extreme value.
+    fn index(&mut self) -> u128 {
+        match self.below(10) {
+            0 => u128::max_value(),
+            1 => MAX_BIT_INDEX + self.below(2) as u128 - 1,
+            _ => self.below(8) as u128,
+        }
+    }
+
+    fn instance_id(&mut self) -> u64 {
+        match self.below(10) {
+            0 => u64::max_value(),
+            _ => self.below(4),
+        }
+    }
+
+    fn indexes(&mut self) -> Vec<u128> {
+        (0..self.below(4)).map(|_| self.index()).collect()
+    }
+
+    fn bit_set(&mut self) -> BitSet {
+        let mut set = BitSet::from(self.below(256) as u128);
+        if self.below(10) == 0 {
+            set.insert(self.index());
+        }
+        set
+    }
+
+    fn element_type(&mut self) -> ElementType {
+        loop {
+            let bytes = self.next().to_le_bytes();
+            if let Ok(element_type) = ElementType::decode(&mut &bytes[..]) {
+                return element_type;
+            }
+        }
+    }
+
+    fn event_code(&mut self) -> [u8; 32] {
+        let mut event_code = [0; 32];
+        event_code[0] = self.below(2) as u8;
+        event_code
+    }
+
+    fn element(&mut self) -> ElementDefinition {
+        ElementDefinition {
+            element_index: self.index(),
+            pre_condition: self.bit_set(),
+            post_condition: self.bit_set(),
+            element_type: self.element_type(),
+            event_code: self.event_code(),
+            next_elem: self.indexes(),
+        }
+    }
+
+    fn link(&mut self) -> SubProcessLink<u64> {
+        SubProcessLink {
+            parent_index: self.index(),
+            child_flow: self.instance_id(),
+            attached_events: self.indexes(),
+            count_instances: self.index(),
+        }
+    }
+
+    /// Instance stored under `instance_id`, parents have lower ids and children higher ones
+    /// so that the instances form a tree, as they do when created by the pallet.
+    fn idata(&mut self, instance_id: u64) -> Idata<Test> {
+        let mut idata = Idata::default();
+        idata.set_marking(self.bit_set());
+        idata.set_activity_marking(self.bit_set());
+        let parent = match instance_id {
+            0 => None,
+            _ => Some(self.below(instance_id)).filter(|_| self.below(2) == 0),
+        };
+        idata.set_parent(parent, self.instance_id(), self.index());
+        for _ in 0..self.below(3) {
+            if let Some(child) = instance_id.checked_add(1 + self.below(3)) {
+                idata.add_child(self.index(), child);
+            }
+        }
+        idata
+    }
+}
+
+#[test]
+fn dispatchables_do_not_panic_on_arbitrary_input() {
+    for seed in 1..=300 {
+        let mut rng = Rng(seed);
+        new_test_ext().execute_with(|| {
+            for _ in 0..40 {
+                let origin = Origin::signed(ALICE);
+                // Only the absence of panics matters, most of the calls are expected to fail
+                let _ = match rng.below(16) {
+                    0 => {
+                        let element = rng.element();
+                        BpmnInterpreter::set_element(
+                            origin,
+                            rng.instance_id(),
+                            element.element_index,
+                            element.pre_condition,
+                            element.post_condition,
+                            element.element_type,
+                            element.event_code,
+                            element.next_elem,
+                        )
+                    }
+                    1 => {
+                        let link = rng.link();
+                        BpmnInterpreter::link_sub_process(
+                            origin,
+                            rng.instance_id(),
+                            link.parent_index,
+                            link.child_flow,
+                            link.attached_events,
+                            link.count_instances,
+                        )
+                    }
+                    2 => BpmnInterpreter::set_factory_instance(
+                        origin,
+                        rng.instance_id(),
+                        Default::default(),
+                        vec![],
+                        vec![],
+                    ),
+                    3 => {
+                        let elements = (0..rng.below(6)).map(|_| rng.element()).collect();
+                        let links = (0..rng.below(2)).map(|_| rng.link()).collect();
+                        deploy(elements, links)
+                    }
+                    4 => {
+                        BpmnInterpreter::continue_execution(origin, rng.instance_id(), rng.index())
+                            .map(drop)
+                            .map_err(|e| e.error)
+                    }
+                    5 => BpmnInterpreter::create_root_instance(origin, rng.instance_id())
+                        .map(drop)
+                        .map_err(|e| e.error),
+                    6 => BpmnInterpreter::set_timer(
+                        origin,
+                        rng.instance_id(),
+                        rng.index(),
+                        TimerDefinition::Cycle {
+                            interval: rng.below(3),
+                            repetitions: Some(rng.below(3) as u32),
+                        },
+                    ),
+                    7 => {
+                        fire_timers_at(rng.below(20));
+                        Ok(())
+                    }
+                    8 => BpmnInterpreter::set_correlation_key(
+                        origin,
+                        rng.instance_id(),
+                        H256::repeat_byte(rng.below(2) as u8),
+                    ),
+                    9 => BpmnInterpreter::send_message(
+                        origin,
+                        H256::repeat_byte(rng.below(2) as u8),
+                        rng.event_code(),
+                        vec![],
+                    )
+                    .map(drop)
+                    .map_err(|e| e.error),
+                    10 => BpmnInterpreter::poke_instance(origin, rng.instance_id())
+                        .map(drop)
+                        .map_err(|e| e.error),
+                    11 => BpmnInterpreter::suspend_instance(origin, rng.instance_id())
+                        .map(drop)
+                        .map_err(|e| e.error),
+                    12 => BpmnInterpreter::resume_instance(origin, rng.instance_id())
+                        .map(drop)
+                        .map_err(|e| e.error),
+                    13 => BpmnInterpreter::cancel_instance(origin, rng.instance_id())
+                        .map(drop)
+                        .map_err(|e| e.error),
+                    14 => BpmnInterpreter::prune_instance(origin, rng.instance_id())
+                        .map(drop)
+                        .map_err(|e| e.error),
+                    _ => {
+                        let instance_id = rng.instance_id();
+                        <IdataById<Test>>::insert(instance_id, rng.idata(instance_id));
+                        Ok(())
+                    }
+                };
+            }
+        });
+    }
+}
+
+#[test]
+fn deployed_models_are_consistent() {
+    for seed in 1..=300 {
+        let mut rng = Rng(seed);
+        new_test_ext().execute_with(|| {
+            let elements: Vec<_> = (0..rng.below(8)).map(|_| rng.element()).collect();
+            if deploy(elements.clone(), vec![]).is_err() {
+                return;
+            }
+            let iflow = BpmnInterpreter::iflow_by_id(BpmnInterpreter::process_id_count());
+            assert!(has(
+                iflow.get_type_info(iflow.get_first_elem()),
+                EVENT | START
+            ));
+            for element in elements {
+                let (_, next_elem) = iflow.get_element_info(element.element_index).unwrap();
+                for next in next_elem {
+                    assert!(iflow.get_element_info(*next).is_ok());
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,238 @@
+//! Timer events, armed by the interpreter and fired by `on_initialize` once due.
+
+use crate::{
+    types::*, ArmedStartTimers, ArmedTimers, Error, ExecutionMeter, Iflow, Module, MomentOf,
+    NextTimerBucket, RawEvent, TimerQueue, Trait, MAX_TIMERS_PER_BLOCK, MAX_TIMERS_PER_BUCKET,
+    MAX_TIMER_BUCKETS_PER_BLOCK, TIMER_BUCKET_SPAN,
+};
+use codec::{Decode, Encode};
+use frame_support::{
+    dispatch::{DispatchError, DispatchResult},
+    ensure,
+    storage::IterableStorageDoubleMap,
+    traits::Time,
+    transactional, StorageDoubleMap, StorageMap, StorageValue,
+};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// Timer of the timer queue, due for the element of an instance, or of a process model for
+/// timer start events.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ScheduledTimer<InstanceId, Moment> {
+    pub due: Moment,
+    pub instance_id: InstanceId,
+    pub element_index: u128,
+    /// Times the timer was due before, for cycles
+    pub fired: u32,
+    /// Whether the timer is due for a timer start event, `instance_id` being the process model
+    pub start_event: bool,
+}
+
+impl<T: Trait> Module<T> {
+    /// Arms the timer event of the instance, charged as an executed element.
+    pub(crate) fn arm_timer(
+        meter: &mut ExecutionMeter,
+        instance_id: T::InstanceId,
+        iflow: &Iflow<T>,
+        element_index: u128,
+    ) -> DispatchResult {
+        let timer = iflow.get_timer(element_index)?;
+        meter.execute();
+        Self::schedule_timer(ScheduledTimer {
+            due: timer.first_due(T::Time::now()),
+            instance_id,
+            element_index,
+            fired: 0,
+            start_event: false,
+        })
+    }
+
+    /// Puts the timer in the timer queue, replacing the one armed for the same element. Timers
+    /// already due are due now.
+    pub(crate) fn schedule_timer(
+        mut timer: ScheduledTimer<T::InstanceId, MomentOf<T>>,
+    ) -> DispatchResult {
+        timer.due = timer.due.max(T::Time::now());
+        Self::ensure_timer_bucket_has_room(timer.due)?;
+        let previous = if timer.start_event {
+            <ArmedStartTimers<T>>::take(timer.instance_id, timer.element_index)
+        } else {
+            <ArmedTimers<T>>::take(timer.instance_id, timer.element_index)
+        };
+        if let Some(previous) = previous {
+            Self::unschedule_timer(
+                previous,
+                timer.instance_id,
+                timer.element_index,
+                timer.start_event,
+            );
+        }
+
+        if timer.start_event {
+            <ArmedStartTimers<T>>::insert(timer.instance_id, timer.element_index, timer.due);
+        } else {
+            <ArmedTimers<T>>::insert(timer.instance_id, timer.element_index, timer.due);
+        }
+        let bucket = Self::timer_bucket(timer.due);
+        if Self::next_timer_bucket().map_or(true, |next| bucket < next) {
+            <NextTimerBucket<T>>::put(bucket);
+        }
+        <TimerQueue<T>>::mutate(bucket, |timers| timers.push(timer));
+        Ok(())
+    }
+
+    /// Ensures a timer due at the moment, not before now, can be put in the timer queue.
+    pub(crate) fn ensure_timer_bucket_has_room(due: MomentOf<T>) -> DispatchResult {
+        ensure!(
+            Self::timer_queue(Self::timer_bucket(due)).len() < MAX_TIMERS_PER_BUCKET as usize,
+            Error::<T>::TooManyTimers
+        );
+        Ok(())
+    }
+
+    /// Disarms the timer event of the instance, if armed.
+    pub(crate) fn disarm_timer(instance_id: T::InstanceId, element_index: u128) {
+        if let Some(due) = <ArmedTimers<T>>::take(instance_id, element_index) {
+            Self::unschedule_timer(due, instance_id, element_index, false);
+        }
+    }
+
+    /// Disarms every timer event of the instance.
+    pub(crate) fn disarm_timers(instance_id: T::InstanceId) {
+        for (element_index, due) in <ArmedTimers<T>>::drain_prefix(instance_id) {
+            Self::unschedule_timer(due, instance_id, element_index, false);
+        }
+    }
+
+    /// Takes the timer out of the bucket of the timer queue it is due in.
+    fn unschedule_timer(
+        due: MomentOf<T>,
+        instance_id: T::InstanceId,
+        element_index: u128,
+        start_event: bool,
+    ) {
+        let bucket = Self::timer_bucket(due);
+        let mut timers = Self::timer_queue(bucket);
+        timers.retain(|timer| {
+            timer.instance_id != instance_id
+                || timer.element_index != element_index
+                || timer.start_event != start_event
+        });
+        Self::put_timer_bucket(bucket, timers);
+    }
+
+    /// Bucket of the timer queue a timer due at the moment is put in.
+    fn timer_bucket(due: MomentOf<T>) -> MomentOf<T> {
+        due - due % TIMER_BUCKET_SPAN.into()
+    }
+
+    /// Stores the timers of the bucket, removing it once empty.
+    fn put_timer_bucket(
+        bucket: MomentOf<T>,
+        timers: Vec<ScheduledTimer<T::InstanceId, MomentOf<T>>>,
+    ) {
+        if timers.is_empty() {
+            <TimerQueue<T>>::remove(bucket);
+        } else {
+            <TimerQueue<T>>::insert(bucket, timers);
+        }
+    }
+
+    /// Fires the timers due until the call of the interpreter they share is exhausted,
+    /// returning how many were fired and how many buckets of the timer queue it moved past.
+    pub(crate) fn fire_due_timers(meter: &mut ExecutionMeter) -> (u32, u32) {
+        let now = T::Time::now();
+        let last_bucket = Self::timer_bucket(now);
+        let mut fired = 0;
+        let mut passed = 0;
+        while fired < MAX_TIMERS_PER_BLOCK
+            && passed < MAX_TIMER_BUCKETS_PER_BLOCK
+            && !meter.is_exhausted::<T>()
+        {
+            let bucket = match Self::next_timer_bucket() {
+                Some(bucket) if bucket <= last_bucket => bucket,
+                _ => break,
+            };
+            let mut timers = Self::timer_queue(bucket);
+            let timer = match timers.iter().position(|timer| timer.due <= now) {
+                Some(position) => timers.remove(position),
+                None if bucket < last_bucket => {
+                    // Every timer of the bucket fired, the next ones may hold timers
+                    passed += 1;
+                    <NextTimerBucket<T>>::put(bucket + TIMER_BUCKET_SPAN.into());
+                    continue;
+                }
+                None => break,
+            };
+            Self::put_timer_bucket(bucket, timers);
+            fired += 1;
+
+            match Self::fire_timer(meter, &timer) {
+                Ok(false) => (),
+                Ok(true) => Self::deposit_event(RawEvent::TimerFired(
+                    timer.instance_id,
+                    timer.element_index,
+                )),
+                Err(error) => Self::deposit_event(RawEvent::TimerFailed(
+                    timer.instance_id,
+                    timer.element_index,
+                    error,
+                )),
+            }
+        }
+        (fired, passed)
+    }
+
+    /// Catches the timer event, starting an instance of the process model for start events.
+    /// Returns whether the event was caught, see `catch_waiting_event`. Nothing a failed
+    /// execution wrote is kept.
+    #[transactional]
+    fn fire_timer(
+        meter: &mut ExecutionMeter,
+        timer: &ScheduledTimer<T::InstanceId, MomentOf<T>>,
+    ) -> Result<bool, DispatchError> {
+        let instance_id = timer.instance_id;
+        let event = timer.element_index;
+        if timer.start_event {
+            // Timer start event of the process model
+            <ArmedStartTimers<T>>::remove(instance_id, event);
+            let iflow = Self::ensure_iflow_instance_exists(instance_id)?;
+            Self::start_root_instance(meter, instance_id)?;
+            Self::rearm_timer(&iflow, timer)?;
+            return Ok(true);
+        }
+        <ArmedTimers<T>>::remove(instance_id, event);
+
+        let idata = Self::ensure_idata_instance_exists(instance_id)?;
+        let iflow = Self::ensure_iflow_instance_exists(idata.get_flow_node())?;
+        if !Self::catch_waiting_event(meter, instance_id, &idata, &iflow, event)? {
+            return Ok(false);
+        }
+        let event_info = iflow.get_type_info(event);
+        if has(event_info, BOUNDARY) && !has(event_info, INTERRUPTING) {
+            // Non-interrupting boundary timers repeat while the activity runs
+            Self::rearm_timer(&iflow, timer)?;
+        }
+        Ok(true)
+    }
+
+    /// Schedules the next time the cycle of the timer is due, if any.
+    fn rearm_timer(
+        iflow: &Iflow<T>,
+        timer: &ScheduledTimer<T::InstanceId, MomentOf<T>>,
+    ) -> DispatchResult {
+        let fired = timer.fired.saturating_add(1);
+        match iflow
+            .get_timer(timer.element_index)?
+            .next_due(timer.due, fired)
+        {
+            Some(due) => Self::schedule_timer(ScheduledTimer {
+                due,
+                fired,
+                ..timer.clone()
+            }),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,728 @@
+//! Typed model of the element `type_info` flags.
+//!
+//! Elements are stored with the legacy `u128` bit layout, `ElementType` converts losslessly
+//! from and to it. Bits 3 to 9 are shared between activities, gateways and events, their
+//! meaning depends on which of the first three bits is set.
+
+use codec::{Decode, Encode};
+use core::convert::TryFrom;
+use sp_runtime::{
+    traits::{Saturating, Zero},
+    RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+pub const ACTIVITY: u128 = 1 << 0;
+pub const GATEWAY: u128 = 1 << 1;
+pub const EVENT: u128 = 1 << 2;
+
+// Activities
+pub const TASK: u128 = 1 << 3;
+pub const CALL_ACTIVITY: u128 = 1 << 4;
+pub const SUB_PROCESS: u128 = 1 << 5;
+pub const PARALLEL_MULTI_INSTANCE: u128 = 1 << 6;
+pub const SEQUENTIAL_MULTI_INSTANCE: u128 = 1 << 7;
+pub const STANDARD_LOOP: u128 = 1 << 8;
+pub const LOOP_TEST_BEFORE: u128 = 1 << 9;
+pub const DEFAULT_TASK: u128 = 1 << 10;
+pub const USER_TASK: u128 = 1 << 11;
+pub const SCRIPT_TASK: u128 = 1 << 12;
+pub const SERVICE_TASK: u128 = 1 << 13;
+pub const RECEIVE_TASK: u128 = 1 << 14;
+pub const EVENT_SUB_PROCESS: u128 = 1 << 12;
+
+// Gateways
+pub const JOIN: u128 = 1 << 3;
+pub const EXCLUSIVE: u128 = 1 << 4;
+pub const PARALLEL: u128 = 1 << 5;
+pub const INCLUSIVE: u128 = 1 << 6;
+pub const EVENT_BASED: u128 = 1 << 7;
+
+// Events
+pub const THROW: u128 = 1 << 3;
+pub const INTERRUPTING: u128 = 1 << 4;
+pub const START: u128 = 1 << 5;
+pub const INTERMEDIATE: u128 = 1 << 7;
+pub const BOUNDARY: u128 = 1 << 8;
+pub const END: u128 = 1 << 9;
+pub const DEFAULT_EVENT: u128 = 1 << 10;
+pub const TERMINATE: u128 = 1 << 11;
+pub const MESSAGE: u128 = 1 << 12;
+pub const ERROR: u128 = 1 << 13;
+pub const ESCALATION: u128 = 1 << 14;
+pub const SIGNAL: u128 = 1 << 15;
+pub const TIMER: u128 = 1 << 16;
+pub const COMPENSATION: u128 = 1 << 17;
+
+// Masks checked by the interpreter
+pub const AND_JOIN: u128 = GATEWAY | JOIN | PARALLEL;
+pub const OR_JOIN: u128 = GATEWAY | JOIN | INCLUSIVE;
+pub const SCRIPT: u128 = ACTIVITY | TASK | SCRIPT_TASK;
+pub const THROW_EVENT: u128 = EVENT | THROW;
+/// Start events of event sub-processes, as encoded by the original interpreter, see
+/// `EventPosition::EventSubProcessStart`
+pub const EVENT_SUB_PROCESS_START: u128 = GATEWAY | EVENT;
+
+/// Whether every bit of `mask` is set in `type_info`.
+pub fn has(type_info: u128, mask: u128) -> bool {
+    type_info & mask == mask
+}
+
+/// Whether at least one bit of `mask` is set in `type_info`.
+pub fn has_any(type_info: u128, mask: u128) -> bool {
+    type_info & mask != 0
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum TaskKind {
+    /// Task without a specific behaviour, completed off-chain
+    Default,
+    User,
+    /// Executed on-chain by the data & scripts contract
+    Script,
+    Service,
+    Receive,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum SubProcessKind {
+    Embedded,
+    CallActivity,
+    /// Sub-process started by one of its events rather than by a token
+    EventSubProcess,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum MultiInstance {
+    None,
+    Parallel,
+    Sequential,
+    /// Instances run one after the other while the loop condition holds, tested before each
+    /// of them or only after, see `LoopDefinition`
+    StandardLoop {
+        test_before: bool,
+    },
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum GatewayKind {
+    Exclusive,
+    Parallel,
+    Inclusive,
+    /// Waits for the first of the intermediate catch events it leads to
+    EventBased,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum EventPosition {
+    Start,
+    IntermediateCatch,
+    IntermediateThrow,
+    Boundary,
+    End,
+    /// Catch event of the enclosing process starting the event sub-process it is attached to
+    EventSubProcessStart,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum EventKind {
+    /// Event without trigger or result
+    Default,
+    Terminate,
+    Message,
+    Error,
+    Escalation,
+    Signal,
+    /// Triggered once its `TimerDefinition` is due
+    Timer,
+    /// Thrown to undo the activities completed, caught by the boundary events leading to
+    /// their compensation handlers
+    Compensation,
+}
+
+/// When a timer event is due, relative to the moment it is armed: when set for start events,
+/// when reached by a token for intermediate events and when the activity starts for boundary
+/// events.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum TimerDefinition<Moment> {
+    Duration(Moment),
+    /// Due at the given moment, or right away once past
+    Date(Moment),
+    /// Due every `interval`, `repetitions` times or forever. Intermediate events only wait for
+    /// the first one.
+    Cycle {
+        interval: Moment,
+        repetitions: Option<u32>,
+    },
+}
+
+impl<Moment: Copy + Saturating + Zero> TimerDefinition<Moment> {
+    /// Whether the timer is ever due.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            TimerDefinition::Cycle {
+                interval,
+                repetitions,
+            } => !interval.is_zero() && repetitions != Some(0),
+            _ => true,
+        }
+    }
+
+    /// First moment the timer is due, once armed at `now`.
+    pub fn first_due(&self, now: Moment) -> Moment {
+        match *self {
+            TimerDefinition::Duration(duration) => now.saturating_add(duration),
+            TimerDefinition::Date(date) => date,
+            TimerDefinition::Cycle { interval, .. } => now.saturating_add(interval),
+        }
+    }
+
+    /// Next moment the timer is due, after being due at `due` for the `fired`-th time.
+    pub fn next_due(&self, due: Moment, fired: u32) -> Option<Moment> {
+        match *self {
+            TimerDefinition::Cycle {
+                interval,
+                repetitions,
+            } if repetitions.map_or(true, |repetitions| fired < repetitions) => {
+                Some(due.saturating_add(interval))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Multi-instance characteristics of a sub-process decided at runtime by the data & scripts
+/// contract, whose messages are called with the id of the parent instance and the index of the
+/// sub-process.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct MultiInstanceDefinition {
+    /// Selector of the message returning how many instances to run, as a `u128`. The instances
+    /// are counted as linked with the sub-process when empty.
+    pub cardinality_selector: Vec<u8>,
+    /// Selector of the message returning whether the activity is complete once one of its
+    /// instances completed, as a `bool`, also called with the loop counter of that instance.
+    /// The activity completes with its last instance when empty.
+    pub completion_selector: Vec<u8>,
+}
+
+/// Loop condition of a standard loop activity decided at runtime by the data & scripts
+/// contract, whose message is called with the id of the parent instance, the index of the
+/// activity and the number of iterations run so far.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct LoopDefinition {
+    /// Selector of the message returning whether to run another iteration, as a `bool`. The
+    /// loop runs up to its maximum when empty.
+    pub condition_selector: Vec<u8>,
+    /// Most iterations run, `MAX_LOOP_ITERATIONS` when not set
+    pub maximum: Option<u32>,
+}
+
+/// Category and flags of a flow element.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum ElementType {
+    Task(TaskKind),
+    SubProcess {
+        kind: SubProcessKind,
+        multi_instance: MultiInstance,
+    },
+    Gateway {
+        kind: GatewayKind,
+        /// Merges several incoming sequence flows
+        join: bool,
+    },
+    Event {
+        position: EventPosition,
+        kind: EventKind,
+        /// Cancels the activity (boundary events) or the enclosing process (start events of
+        /// event sub-processes)
+        interrupting: bool,
+    },
+}
+
+impl ElementType {
+    /// Whether the combination of flags is meaningful for the interpreter.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            ElementType::Event {
+                position,
+                kind,
+                interrupting,
+            } => {
+                (!interrupting
+                    || position == EventPosition::Start
+                    || position == EventPosition::Boundary
+                    || position == EventPosition::EventSubProcessStart)
+                    && (kind != EventKind::Terminate || position == EventPosition::End)
+                    && (kind != EventKind::Timer
+                        || position == EventPosition::Start
+                        || position == EventPosition::IntermediateCatch
+                        || position == EventPosition::Boundary
+                        || position == EventPosition::EventSubProcessStart)
+                    && (kind != EventKind::Compensation
+                        || (!interrupting
+                            && (position == EventPosition::IntermediateThrow
+                                || position == EventPosition::Boundary
+                                || position == EventPosition::End)))
+            }
+            ElementType::SubProcess {
+                kind: SubProcessKind::EventSubProcess,
+                multi_instance,
+            } => multi_instance == MultiInstance::None,
+            ElementType::Gateway {
+                kind: GatewayKind::EventBased,
+                join,
+            } => !join,
+            _ => true,
+        }
+    }
+
+    /// Encoding of the element type in the legacy `type_info` bit layout.
+    pub fn type_info(&self) -> u128 {
+        match *self {
+            ElementType::Task(kind) => {
+                ACTIVITY
+                    | TASK
+                    | match kind {
+                        TaskKind::Default => DEFAULT_TASK,
+                        TaskKind::User => USER_TASK,
+                        TaskKind::Script => SCRIPT_TASK,
+                        TaskKind::Service => SERVICE_TASK,
+                        TaskKind::Receive => RECEIVE_TASK,
+                    }
+            }
+            ElementType::SubProcess {
+                kind,
+                multi_instance,
+            } => {
+                ACTIVITY
+                    | match kind {
+                        SubProcessKind::Embedded => SUB_PROCESS,
+                        SubProcessKind::CallActivity => CALL_ACTIVITY,
+                        SubProcessKind::EventSubProcess => SUB_PROCESS | EVENT_SUB_PROCESS,
+                    }
+                    | match multi_instance {
+                        MultiInstance::None => 0,
+                        MultiInstance::Parallel => PARALLEL_MULTI_INSTANCE,
+                        MultiInstance::Sequential => SEQUENTIAL_MULTI_INSTANCE,
+                        MultiInstance::StandardLoop { test_before } => {
+                            STANDARD_LOOP | if test_before { LOOP_TEST_BEFORE } else { 0 }
+                        }
+                    }
+            }
+            ElementType::Gateway { kind, join } => {
+                GATEWAY
+                    | if join { JOIN } else { 0 }
+                    | match kind {
+                        GatewayKind::Exclusive => EXCLUSIVE,
+                        GatewayKind::Parallel => PARALLEL,
+                        GatewayKind::Inclusive => INCLUSIVE,
+                        GatewayKind::EventBased => EVENT_BASED,
+                    }
+            }
+            ElementType::Event {
+                position,
+                kind,
+                interrupting,
+            } => {
+                EVENT
+                    | if interrupting { INTERRUPTING } else { 0 }
+                    | match position {
+                        EventPosition::Start => START,
+                        EventPosition::IntermediateCatch => INTERMEDIATE,
+                        EventPosition::IntermediateThrow => INTERMEDIATE | THROW,
+                        EventPosition::Boundary => BOUNDARY,
+                        EventPosition::End => END | THROW,
+                        EventPosition::EventSubProcessStart => EVENT_SUB_PROCESS_START,
+                    }
+                    | match kind {
+                        EventKind::Default => DEFAULT_EVENT,
+                        EventKind::Terminate => TERMINATE,
+                        EventKind::Message => MESSAGE,
+                        EventKind::Error => ERROR,
+                        EventKind::Escalation => ESCALATION,
+                        EventKind::Signal => SIGNAL,
+                        EventKind::Timer => TIMER,
+                        EventKind::Compensation => COMPENSATION,
+                    }
+            }
+        }
+    }
+
+    fn decode_type_info(type_info: u128) -> Option<Self> {
+        let one_of = |options: &[(u128, u128)]| {
+            options
+                .iter()
+                .find(|(mask, _)| has(type_info, *mask))
+                .map(|(_, index)| *index)
+        };
+        let element_type = if has(type_info, ACTIVITY) {
+            if has(type_info, TASK) {
+                ElementType::Task(
+                    match one_of(&[
+                        (DEFAULT_TASK, 0),
+                        (USER_TASK, 1),
+                        (SCRIPT_TASK, 2),
+                        (SERVICE_TASK, 3),
+                        (RECEIVE_TASK, 4),
+                    ])? {
+                        0 => TaskKind::Default,
+                        1 => TaskKind::User,
+                        2 => TaskKind::Script,
+                        3 => TaskKind::Service,
+                        _ => TaskKind::Receive,
+                    },
+                )
+            } else {
+                ElementType::SubProcess {
+                    kind: if has(type_info, CALL_ACTIVITY) {
+                        SubProcessKind::CallActivity
+                    } else if has(type_info, EVENT_SUB_PROCESS) {
+                        SubProcessKind::EventSubProcess
+                    } else {
+                        SubProcessKind::Embedded
+                    },
+                    multi_instance: if has(type_info, PARALLEL_MULTI_INSTANCE) {
+                        MultiInstance::Parallel
+                    } else if has(type_info, SEQUENTIAL_MULTI_INSTANCE) {
+                        MultiInstance::Sequential
+                    } else if has(type_info, STANDARD_LOOP) {
+                        MultiInstance::StandardLoop {
+                            test_before: has(type_info, LOOP_TEST_BEFORE),
+                        }
+                    } else {
+                        MultiInstance::None
+                    },
+                }
+            }
+        } else if has(type_info, GATEWAY) && !has(type_info, EVENT) {
+            ElementType::Gateway {
+                kind: match one_of(&[
+                    (EXCLUSIVE, 0),
+                    (PARALLEL, 1),
+                    (INCLUSIVE, 2),
+                    (EVENT_BASED, 3),
+                ])? {
+                    0 => GatewayKind::Exclusive,
+                    1 => GatewayKind::Parallel,
+                    2 => GatewayKind::Inclusive,
+                    _ => GatewayKind::EventBased,
+                },
+                join: has(type_info, JOIN),
+            }
+        } else if has(type_info, EVENT) {
+            ElementType::Event {
+                position: match one_of(&[
+                    (EVENT_SUB_PROCESS_START, 0),
+                    (START, 1),
+                    (INTERMEDIATE | THROW, 2),
+                    (INTERMEDIATE, 3),
+                    (BOUNDARY, 4),
+                    (END, 5),
+                ])? {
+                    0 => EventPosition::EventSubProcessStart,
+                    1 => EventPosition::Start,
+                    2 => EventPosition::IntermediateThrow,
+                    3 => EventPosition::IntermediateCatch,
+                    4 => EventPosition::Boundary,
+                    _ => EventPosition::End,
+                },
+                kind: match one_of(&[
+                    (DEFAULT_EVENT, 0),
+                    (TERMINATE, 1),
+                    (MESSAGE, 2),
+                    (ERROR, 3),
+                    (ESCALATION, 4),
+                    (SIGNAL, 5),
+                    (TIMER, 6),
+                    (COMPENSATION, 7),
+                ])? {
+                    0 => EventKind::Default,
+                    1 => EventKind::Terminate,
+                    2 => EventKind::Message,
+                    3 => EventKind::Error,
+                    4 => EventKind::Escalation,
+                    5 => EventKind::Signal,
+                    6 => EventKind::Timer,
+                    _ => EventKind::Compensation,
+                },
+                interrupting: has(type_info, INTERRUPTING),
+            }
+        } else {
+            return None;
+        };
+        Some(element_type)
+    }
+}
+
+impl From<ElementType> for u128 {
+    fn from(element_type: ElementType) -> u128 {
+        element_type.type_info()
+    }
+}
+
+impl TryFrom<u128> for ElementType {
+    type Error = ();
+
+    /// Only accepts the exact encoding of an element type, any extra or conflicting bit is
+    /// rejected so that the conversion is lossless in both directions.
+    fn try_from(type_info: u128) -> Result<Self, ()> {
+        Self::decode_type_info(type_info)
+            .filter(|element_type| element_type.type_info() == type_info)
+            .ok_or(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_types() -> Vec<ElementType> {
+        let mut types = Vec::new();
+        for kind in [
+            TaskKind::Default,
+            TaskKind::User,
+            TaskKind::Script,
+            TaskKind::Service,
+            TaskKind::Receive,
+        ]
+        .iter()
+        {
+            types.push(ElementType::Task(*kind));
+        }
+        for kind in [
+            SubProcessKind::Embedded,
+            SubProcessKind::CallActivity,
+            SubProcessKind::EventSubProcess,
+        ]
+        .iter()
+        {
+            for multi_instance in [
+                MultiInstance::None,
+                MultiInstance::Parallel,
+                MultiInstance::Sequential,
+                MultiInstance::StandardLoop { test_before: false },
+                MultiInstance::StandardLoop { test_before: true },
+            ]
+            .iter()
+            {
+                types.push(ElementType::SubProcess {
+                    kind: *kind,
+                    multi_instance: *multi_instance,
+                });
+            }
+        }
+        for kind in [
+            GatewayKind::Exclusive,
+            GatewayKind::Parallel,
+            GatewayKind::Inclusive,
+            GatewayKind::EventBased,
+        ]
+        .iter()
+        {
+            for join in [false, true].iter() {
+                types.push(ElementType::Gateway {
+                    kind: *kind,
+                    join: *join,
+                });
+            }
+        }
+        for position in [
+            EventPosition::Start,
+            EventPosition::IntermediateCatch,
+            EventPosition::IntermediateThrow,
+            EventPosition::Boundary,
+            EventPosition::End,
+            EventPosition::EventSubProcessStart,
+        ]
+        .iter()
+        {
+            for kind in [
+                EventKind::Default,
+                EventKind::Terminate,
+                EventKind::Message,
+                EventKind::Error,
+                EventKind::Escalation,
+                EventKind::Signal,
+                EventKind::Timer,
+                EventKind::Compensation,
+            ]
+            .iter()
+            {
+                for interrupting in [false, true].iter() {
+                    types.push(ElementType::Event {
+                        position: *position,
+                        kind: *kind,
+                        interrupting: *interrupting,
+                    });
+                }
+            }
+        }
+        types
+    }
+
+    #[test]
+    fn converts_losslessly() {
+        let types = all_types();
+        for element_type in types.iter() {
+            let type_info = element_type.type_info();
+            assert_eq!(ElementType::try_from(type_info), Ok(*element_type));
+            assert_eq!(
+                types.iter().filter(|t| t.type_info() == type_info).count(),
+                1,
+                "{:?} shares its encoding",
+                element_type
+            );
+        }
+    }
+
+    #[test]
+    fn matches_legacy_masks() {
+        let script = ElementType::Task(TaskKind::Script).type_info();
+        assert_eq!(script, 4105);
+        let and_join = ElementType::Gateway {
+            kind: GatewayKind::Parallel,
+            join: true,
+        };
+        assert_eq!(and_join.type_info(), 42);
+        let or_join = ElementType::Gateway {
+            kind: GatewayKind::Inclusive,
+            join: true,
+        };
+        assert_eq!(or_join.type_info(), 74);
+        let signal_catch = ElementType::Event {
+            position: EventPosition::IntermediateCatch,
+            kind: EventKind::Signal,
+            interrupting: false,
+        };
+        assert_eq!(signal_catch.type_info() & 32780, 32772);
+        let start = ElementType::Event {
+            position: EventPosition::Start,
+            kind: EventKind::Default,
+            interrupting: false,
+        };
+        assert!(has(start.type_info(), EVENT | START));
+    }
+
+    #[test]
+    fn encodes_event_sub_process_starts() {
+        let message_start = ElementType::Event {
+            position: EventPosition::EventSubProcessStart,
+            kind: EventKind::Message,
+            interrupting: true,
+        };
+        let type_info = message_start.type_info();
+        assert_eq!(type_info, EVENT_SUB_PROCESS_START | INTERRUPTING | MESSAGE);
+        assert!(has(type_info, EVENT_SUB_PROCESS_START));
+        assert!(!has(type_info, EVENT | START));
+        assert_eq!(ElementType::try_from(type_info), Ok(message_start));
+        assert!(message_start.is_valid());
+        // The interpreter's bits for an error event sub-process, as in the original contracts
+        assert_eq!(
+            ElementType::try_from(GATEWAY | EVENT | ERROR),
+            Ok(ElementType::Event {
+                position: EventPosition::EventSubProcessStart,
+                kind: EventKind::Error,
+                interrupting: false,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_non_canonical_type_info() {
+        assert_eq!(ElementType::try_from(0), Err(()));
+        // Task without kind
+        assert_eq!(ElementType::try_from(ACTIVITY | TASK), Err(()));
+        // Two task kinds
+        assert_eq!(
+            ElementType::try_from(ACTIVITY | TASK | USER_TASK | SCRIPT_TASK),
+            Err(())
+        );
+        // Start of an event sub-process without trigger
+        assert_eq!(ElementType::try_from(GATEWAY | EVENT | EXCLUSIVE), Err(()));
+        // Start of an event sub-process also flagged as start of its process
+        assert_eq!(
+            ElementType::try_from(EVENT_SUB_PROCESS_START | START | MESSAGE),
+            Err(())
+        );
+        // Loop tested before without the loop marker
+        assert_eq!(
+            ElementType::try_from(ACTIVITY | SUB_PROCESS | LOOP_TEST_BEFORE),
+            Err(())
+        );
+        // Bits beyond the layout
+        assert_eq!(
+            ElementType::try_from(ElementType::Task(TaskKind::User).type_info() | 1 << 20),
+            Err(())
+        );
+    }
+
+    #[test]
+    fn validates_flag_combinations() {
+        let event = |position, kind, interrupting| ElementType::Event {
+            position,
+            kind,
+            interrupting,
+        };
+        assert!(event(EventPosition::Boundary, EventKind::Error, true).is_valid());
+        assert!(event(EventPosition::Start, EventKind::Message, true).is_valid());
+        assert!(event(EventPosition::End, EventKind::Terminate, false).is_valid());
+        assert!(!event(EventPosition::End, EventKind::Error, true).is_valid());
+        assert!(!event(EventPosition::Start, EventKind::Terminate, false).is_valid());
+        assert!(event(EventPosition::Boundary, EventKind::Timer, false).is_valid());
+        assert!(!event(EventPosition::IntermediateThrow, EventKind::Timer, false).is_valid());
+        assert!(!event(EventPosition::End, EventKind::Timer, false).is_valid());
+        assert!(event(EventPosition::EventSubProcessStart, EventKind::Timer, true).is_valid());
+        assert!(!event(
+            EventPosition::EventSubProcessStart,
+            EventKind::Terminate,
+            false
+        )
+        .is_valid());
+        assert!(event(EventPosition::Boundary, EventKind::Compensation, false).is_valid());
+        assert!(event(EventPosition::End, EventKind::Compensation, false).is_valid());
+        assert!(!event(EventPosition::Boundary, EventKind::Compensation, true).is_valid());
+        assert!(!event(
+            EventPosition::IntermediateCatch,
+            EventKind::Compensation,
+            false
+        )
+        .is_valid());
+        let event_based = |join| ElementType::Gateway {
+            kind: GatewayKind::EventBased,
+            join,
+        };
+        assert!(event_based(false).is_valid());
+        assert!(!event_based(true).is_valid());
+    }
+
+    #[test]
+    fn schedules_timers() {
+        let cycle = |repetitions| TimerDefinition::Cycle {
+            interval: 10u64,
+            repetitions,
+        };
+        assert_eq!(TimerDefinition::Duration(10u64).first_due(5), 15);
+        assert_eq!(TimerDefinition::Date(3u64).first_due(5), 3);
+        assert_eq!(TimerDefinition::Duration(10u64).next_due(15, 1), None);
+        assert_eq!(cycle(Some(2)).first_due(5), 15);
+        assert_eq!(cycle(Some(2)).next_due(15, 1), Some(25));
+        assert_eq!(cycle(Some(2)).next_due(25, 2), None);
+        assert_eq!(cycle(None).next_due(25, 2), Some(35));
+        assert!(!cycle(Some(0)).is_valid());
+        assert!(!TimerDefinition::Cycle {
+            interval: 0u64,
+            repetitions: None,
+        }
+        .is_valid());
+        assert!(!ElementType::SubProcess {
+            kind: SubProcessKind::EventSubProcess,
+            multi_instance: MultiInstance::Parallel,
+        }
+        .is_valid());
+        assert!(!ElementType::SubProcess {
+            kind: SubProcessKind::EventSubProcess,
+            multi_instance: MultiInstance::StandardLoop { test_before: true },
+        }
+        .is_valid());
+    }
+}
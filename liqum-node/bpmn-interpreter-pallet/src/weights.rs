@@ -0,0 +1,257 @@
+//! Weights of the dispatchables, linear in the components of the benchmarks of
+//! `benchmarking.rs`:
+//! - `e` elements executed, `q` elements queued and `d` sub-process instances created or
+//!   traversed by the interpreter, see `ExecutionMeter`
+//! - `n` successors of an element, `a` events attached to a sub-process, `s` bytes of
+//!   selectors of a factory, of multi-instance characteristics, of a loop condition or of an event payload, `l`
+//!   elements and `k` sub-process links of a deployed model, `b` bytes of the encoded pre and
+//!   post conditions of the elements set or deployed, `p` participants of an instance,
+//!   `t` timers fired and `m` buckets of the timer queue moved past by `on_initialize`, `p`
+//!   bytes of message payload, `r` instances with pending elements resumed or ended root
+//!   instances pruned by `on_initialize`
+//! - `d` also counts the instances of the tree of a root instance suspended, resumed,
+//!   cancelled or pruned
+//!
+//! Scripts and data & scripts contracts are charged the gas they are called with.
+
+use crate::{
+    BitSet, Trait, GAS, MAX_CORRELATED_INSTANCES, MAX_ENDED_INSTANCES, MAX_PARTICIPANTS,
+    MAX_PENDING_INSTANCES, MAX_SUB_PROCESSES, MAX_TIMERS_PER_BUCKET,
+};
+use codec::Encode;
+use frame_support::{traits::Get, weights::Weight};
+
+/// Bytes of the encoded pre and post conditions of an element.
+pub fn condition_bytes(pre_condition: &BitSet, post_condition: &BitSet) -> u32 {
+    (pre_condition.encoded_size() + post_condition.encoded_size()) as u32
+}
+
+pub fn set_element<T: Trait>(n: u32, b: u32) -> Weight {
+    (42_000_000 as Weight)
+        .saturating_add((310_000 as Weight).saturating_mul(n as Weight))
+        .saturating_add((2_000 as Weight).saturating_mul(b as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+/// Includes the cycle check, which reads up to `MAX_SUB_PROCESSES` process models.
+pub fn link_sub_process<T: Trait>(a: u32) -> Weight {
+    (38_000_000 as Weight)
+        .saturating_add((290_000 as Weight).saturating_mul(a as Weight))
+        .saturating_add((21_000_000 as Weight).saturating_mul(MAX_SUB_PROCESSES as Weight))
+        .saturating_add(T::DbWeight::get().reads(3 + MAX_SUB_PROCESSES as Weight))
+        .saturating_add(T::DbWeight::get().writes(1))
+}
+
+pub fn set_factory_instance<T: Trait>(s: u32) -> Weight {
+    (27_000_000 as Weight)
+        .saturating_add((2_000 as Weight).saturating_mul(s as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+pub fn set_multi_instance<T: Trait>(s: u32) -> Weight {
+    (27_000_000 as Weight)
+        .saturating_add((2_000 as Weight).saturating_mul(s as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+pub fn set_loop<T: Trait>(s: u32) -> Weight {
+    (27_000_000 as Weight)
+        .saturating_add((2_000 as Weight).saturating_mul(s as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+pub fn set_event_payload<T: Trait>(s: u32) -> Weight {
+    (27_000_000 as Weight)
+        .saturating_add((2_000 as Weight).saturating_mul(s as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+pub fn set_compensation<T: Trait>() -> Weight {
+    (29_000_000 as Weight).saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+/// Includes the permission check, assuming it reads as much as `OwnerParticipantOrContract`.
+pub fn continue_execution<T: Trait>(e: u32, q: u32, d: u32) -> Weight {
+    (24_000_000 as Weight)
+        .saturating_add((190_000 as Weight).saturating_mul(MAX_PARTICIPANTS as Weight))
+        .saturating_add(T::DbWeight::get().reads(4))
+        .saturating_add(execution::<T>(e, q, d))
+}
+
+/// Includes reserving the deposit for the instance.
+pub fn create_root_instance<T: Trait>(e: u32, q: u32, d: u32) -> Weight {
+    (131_000_000 as Weight)
+        .saturating_add(GAS as Weight)
+        .saturating_add(T::DbWeight::get().reads_writes(5, 6))
+        .saturating_add(execution::<T>(e, q, d))
+}
+
+pub fn deploy_process<T: Trait>(l: u32, k: u32, b: u32) -> Weight {
+    (51_000_000 as Weight)
+        .saturating_add((4_700_000 as Weight).saturating_mul(l as Weight))
+        .saturating_add((2_000 as Weight).saturating_mul(b as Weight))
+        .saturating_add((23_000_000 as Weight).saturating_mul(k as Weight))
+        .saturating_add(T::DbWeight::get().reads(2))
+        .saturating_add(T::DbWeight::get().reads(k as Weight))
+        .saturating_add(T::DbWeight::get().writes(3))
+}
+
+pub fn transfer_ownership<T: Trait>() -> Weight {
+    (33_000_000 as Weight).saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+pub fn set_participants<T: Trait>(p: u32) -> Weight {
+    (36_000_000 as Weight)
+        .saturating_add((240_000 as Weight).saturating_mul(p as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(2, 1))
+}
+
+/// Includes arming the timer of a start event, which moves it from the bucket of the timer
+/// queue it was due in to the one it is due in.
+pub fn set_timer<T: Trait>() -> Weight {
+    (35_000_000 as Weight)
+        .saturating_add((120_000 as Weight).saturating_mul(MAX_TIMERS_PER_BUCKET as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(5, 5))
+}
+
+/// Each timer fired may start an instance of its process model, the execution the timers and
+/// the instances resumed lead to is measured over all of them.
+pub fn on_initialize<T: Trait>(t: u32, m: u32, r: u32, e: u32, q: u32, d: u32) -> Weight {
+    let timer = (96_000_000 as Weight)
+        .saturating_add((60_000 as Weight).saturating_mul(MAX_TIMERS_PER_BUCKET as Weight))
+        .saturating_add(GAS as Weight)
+        .saturating_add(T::DbWeight::get().reads_writes(5, 5));
+    let instance = (21_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(4, 3));
+    (5_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads(3))
+        .saturating_add(timer.saturating_mul(t as Weight))
+        .saturating_add(
+            T::DbWeight::get()
+                .reads_writes(1, 1)
+                .saturating_mul(m as Weight),
+        )
+        .saturating_add(instance.saturating_mul(r as Weight))
+        .saturating_add(execution::<T>(e, q, d))
+}
+
+pub fn set_correlation_key<T: Trait>() -> Weight {
+    (39_000_000 as Weight)
+        .saturating_add((170_000 as Weight).saturating_mul(MAX_CORRELATED_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(5, 3))
+}
+
+/// Includes the permission check of the instances visited, assuming it reads as much as
+/// `OwnerParticipantOrContract`.
+pub fn send_message<T: Trait>(p: u32, e: u32, q: u32, d: u32) -> Weight {
+    let permission = (190_000 as Weight)
+        .saturating_mul(MAX_PARTICIPANTS as Weight)
+        .saturating_add(T::DbWeight::get().reads(5));
+    (26_000_000 as Weight)
+        .saturating_add((2_000 as Weight).saturating_mul(p as Weight))
+        .saturating_add(T::DbWeight::get().reads(1))
+        .saturating_add(permission.saturating_mul(d as Weight))
+        .saturating_add(execution::<T>(e, q, d))
+}
+
+/// Includes reading and clearing the pending elements of the instance.
+pub fn poke_instance<T: Trait>(e: u32, q: u32, d: u32) -> Weight {
+    (21_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(3, 3))
+        .saturating_add(execution::<T>(e, q, d))
+}
+
+/// Includes visiting the instance tree, `d` instances, and leaving them out of the pending
+/// instances.
+pub fn suspend_instance<T: Trait>(d: u32) -> Weight {
+    (30_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(3, 1))
+        .saturating_add((14_000_000 as Weight).saturating_mul(d as Weight))
+        .saturating_add(
+            T::DbWeight::get()
+                .reads_writes(1, 1)
+                .saturating_mul(d as Weight),
+        )
+}
+
+/// Includes visiting the instance tree, `d` instances, and adding those with pending elements
+/// to the pending instances.
+pub fn resume_instance<T: Trait>(d: u32) -> Weight {
+    let instance = (16_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(3, 2));
+    (30_000_000 as Weight)
+        .saturating_add(T::DbWeight::get().reads(2))
+        .saturating_add(instance.saturating_mul(d as Weight))
+}
+
+/// Includes visiting the instance tree, `d` instances, and killing each of them, disarming a
+/// timer.
+pub fn cancel_instance<T: Trait>(d: u32) -> Weight {
+    let instance = (35_000_000 as Weight)
+        .saturating_add((60_000 as Weight).saturating_mul(MAX_TIMERS_PER_BUCKET as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(4, 6));
+    (32_000_000 as Weight)
+        .saturating_add((20_000 as Weight).saturating_mul(MAX_ENDED_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(3, 2))
+        .saturating_add(instance.saturating_mul(d as Weight))
+}
+
+/// Includes visiting the instance tree, `d` instances, removing everything stored for each of
+/// them and refunding the deposit.
+pub fn prune_instance<T: Trait>(d: u32) -> Weight {
+    (64_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_ENDED_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(4, 4))
+        .saturating_add(pruned_instance::<T>().saturating_mul(d as Weight))
+}
+
+/// The instances pruned are counted over all the trees.
+pub fn prune_ended_instances<T: Trait>(r: u32, d: u32) -> Weight {
+    let tree = (64_000_000 as Weight)
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_PENDING_INSTANCES as Weight))
+        .saturating_add((40_000 as Weight).saturating_mul(MAX_ENDED_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(4, 4));
+    (2_000_000 as Weight)
+        .saturating_add(T::DbWeight::get().reads(1))
+        .saturating_add(tree.saturating_mul(r as Weight))
+        .saturating_add(pruned_instance::<T>().saturating_mul(d as Weight))
+}
+
+/// Instance of a tree visited and removed, along with its correlation key and a timer it
+/// armed.
+fn pruned_instance<T: Trait>() -> Weight {
+    (31_000_000 as Weight)
+        .saturating_add((170_000 as Weight).saturating_mul(MAX_CORRELATED_INSTANCES as Weight))
+        .saturating_add((60_000 as Weight).saturating_mul(MAX_TIMERS_PER_BUCKET as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(4, 8))
+}
+
+/// Work of the interpreter, shared by the calls that run it.
+fn execution<T: Trait>(e: u32, q: u32, d: u32) -> Weight {
+    // Executed elements may be scripts, splits, multi-instance or loop activities or events
+    // with a payload calling the data & scripts contract, timers armed are charged as executed elements, each depositing up
+    // to three events
+    let element = (21_000_000 as Weight)
+        .saturating_add(GAS as Weight)
+        .saturating_add((120_000 as Weight).saturating_mul(MAX_TIMERS_PER_BUCKET as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(5, 8));
+    // Instances may be created along with their data & scripts contract, or ended and queued
+    // to be pruned, completing a multi-instance activity or an iteration of a loop whose
+    // condition the contract decides
+    let instance = (89_000_000 as Weight)
+        .saturating_add((2 * GAS) as Weight)
+        .saturating_add((20_000 as Weight).saturating_mul(MAX_ENDED_INSTANCES as Weight))
+        .saturating_add(T::DbWeight::get().reads_writes(5, 6));
+    (1_900_000 as Weight)
+        .saturating_mul(q as Weight)
+        .saturating_add(element.saturating_mul(e as Weight))
+        .saturating_add(instance.saturating_mul(d as Weight))
+        // State of the instance and the elements it is left pending with
+        .saturating_add(T::DbWeight::get().reads_writes(2, 3))
+}
@@ -4,6 +4,7 @@ use ink_lang as ink;
 
 #[ink::contract(version = "0.1.0", env = NodeRuntimeTypes)]
 mod invoice_managing_data {
+    use bitset::BitSet;
     use ink_core::env;
     use ink_prelude::*;
     use ink_types_node_runtime::{calls as runtime_calls, NodeRuntimeTypes};
@@ -31,19 +32,45 @@ mod invoice_managing_data {
             self.idata_instance_id.set(idata_instance_id);
         }
 
+        /// Tokens the element puts on its outgoing sequence flows, given the marking of the
+        /// instance once the element took its own.
         #[ink(message)]
-        fn execute_script(&mut self, element_index: u128) -> u128 {
+        fn execute_script(
+            &mut self,
+            _instance_id: u128,
+            element_index: u128,
+            _marking: BitSet,
+        ) -> BitSet {
             if element_index == 5 {
                 if *self.accepted {
-                    return 32;
+                    return BitSet::from_bits(32);
                 } else {
-                    return 64;
+                    return BitSet::from_bits(64);
                 }
             } else {
-                return 0;
+                return BitSet::new();
             }
         }
 
+        /// Payload of the error or escalation the event throws, for the selector set with
+        /// `set_event_payload` on throw events. The process throws none.
+        #[ink(message)]
+        fn thrown_payload(&self, _instance_id: u128, _element_index: u128) -> vec::Vec<u8> {
+            vec::Vec::new()
+        }
+
+        /// Keeps the payload the event caught, for the selector set with `set_event_payload` on
+        /// catch events. Unlike the throw events' one, it is called with the payload and returns
+        /// nothing.
+        #[ink(message)]
+        fn write_caught_payload(
+            &mut self,
+            _instance_id: u128,
+            _element_index: u128,
+            _payload: vec::Vec<u8>,
+        ) {
+        }
+
         #[ink(message)]
         fn check_in1(&self, element_index: u128) -> Result<(), Errors> {
             if 132 & (1 << element_index) != 0 {
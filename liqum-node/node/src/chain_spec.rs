@@ -1,6 +1,6 @@
 use liqum_node_runtime::{
-    AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-    SystemConfig, WASM_BINARY,
+    AccountId, AuraConfig, BalancesConfig, BpmnInterpreterConfig, GenesisConfig, GrandpaConfig,
+    Signature, SudoConfig, SystemConfig, WASM_BINARY,
 };
 use liqum_node_runtime::{ContractsConfig, ContractsSchedule};
 use sc_service::ChainType;
@@ -134,5 +134,6 @@ fn testnet_genesis(
                 ..Default::default()
             },
         }),
+        bpmn_interpreter: Some(BpmnInterpreterConfig::default()),
     }
 }
@@ -86,9 +86,12 @@ macro_rules! new_full_start {
         .with_rpc_extensions(|builder| -> Result<IoHandler<sc_rpc::Metadata>, _> {
             let handler = pallet_contracts_rpc::Contracts::new(builder.client().clone());
             let delegate = pallet_contracts_rpc::ContractsApi::to_delegate(handler);
+            let bpmn_handler = bpmn_interpreter_rpc::BpmnInterpreter::new(builder.client().clone());
+            let bpmn_delegate = bpmn_interpreter_rpc::BpmnInterpreterApi::to_delegate(bpmn_handler);
 
             let mut io = IoHandler::default();
             io.extend_with(delegate);
+            io.extend_with(bpmn_delegate);
             Ok(io)
         })?;
 
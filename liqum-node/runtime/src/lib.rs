@@ -292,10 +292,23 @@ impl contracts::Trait for Runtime {
 
 pub type InstanceId = u64;
 
+parameter_types! {
+    pub const MaxExecutionSteps: u32 = 250;
+    pub const InstanceDeposit: Balance = 1 * DOLLARS;
+    pub const InstanceRetention: BlockNumber = 7 * DAYS;
+}
+
 impl bpmn_interpreter::Trait for Runtime {
     type Event = Event;
     type InstanceId = InstanceId;
     type ContractAddressFor = contracts::SimpleAddressDeterminer<Runtime>;
+    type AdminOrigin = system::EnsureRoot<AccountId>;
+    type ExecutionPermission = bpmn_interpreter::OwnerParticipantOrContract<Runtime>;
+    type ScriptExecutor = bpmn_interpreter::ContractScripts<Runtime>;
+    type MaxExecutionSteps = MaxExecutionSteps;
+    type Currency = balances::Module<Runtime>;
+    type InstanceDeposit = InstanceDeposit;
+    type InstanceRetention = InstanceRetention;
 }
 
 construct_runtime!(
@@ -310,7 +323,7 @@ construct_runtime!(
         Aura: aura::{Module, Config<T>, Inherent(Timestamp)},
         Grandpa: grandpa::{Module, Call, Storage, Config, Event},
         // Used for the module bpmn_interpreter in `./bpmn_interpreter.rs`
-        BpmnInterpreter: bpmn_interpreter::{Module, Call, Storage, Event<T>},
+        BpmnInterpreter: bpmn_interpreter::{Module, Call, Config, Storage, Event<T>},
 
         Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
         TransactionPayment: transaction_payment::{Module, Storage},
@@ -491,4 +504,59 @@ impl_runtime_apis! {
             Contracts::rent_projection(address)
         }
     }
+
+    impl bpmn_interpreter_rpc_runtime_api::BpmnInterpreterApi<Block, InstanceId> for Runtime {
+        fn instance_state(
+            instance_id: InstanceId,
+        ) -> Option<bpmn_interpreter_rpc_runtime_api::InstanceState<InstanceId>> {
+            BpmnInterpreter::instance_state(instance_id)
+        }
+
+        fn enabled_elements(instance_id: InstanceId) -> Vec<u128> {
+            BpmnInterpreter::enabled_elements(instance_id)
+        }
+
+        fn sub_process_tree(
+            instance_id: InstanceId,
+        ) -> Vec<bpmn_interpreter_rpc_runtime_api::SubProcessInstance<InstanceId>> {
+            BpmnInterpreter::sub_process_tree(instance_id)
+        }
+
+        fn process_model(
+            process_id: InstanceId,
+        ) -> Option<bpmn_interpreter_rpc_runtime_api::ProcessModel<InstanceId>> {
+            BpmnInterpreter::process_model(process_id)
+        }
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    impl frame_benchmarking::Benchmark<Block> for Runtime {
+        fn dispatch_benchmark(
+            pallet: Vec<u8>,
+            benchmark: Vec<u8>,
+            lowest_range_values: Vec<u32>,
+            highest_range_values: Vec<u32>,
+            steps: Vec<u32>,
+            repeat: u32,
+        ) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+            use frame_benchmarking::{add_benchmark, BenchmarkBatch};
+
+            let mut batches = Vec::<BenchmarkBatch>::new();
+            let params = (
+                &pallet,
+                &benchmark,
+                &lowest_range_values,
+                &highest_range_values,
+                &steps,
+                repeat,
+            );
+
+            add_benchmark!(params, batches, b"bpmn-interpreter", BpmnInterpreter);
+
+            if batches.is_empty() {
+                return Err("Benchmark not found for this pallet.".into());
+            }
+            Ok(batches)
+        }
+    }
 }
@@ -4,6 +4,7 @@ use ink_lang as ink;
 
 #[ink::contract(version = "0.1.0")]
 mod invoice_managing_data {
+    use bitset::BitSet;
     use idata::{Errors, Idata};
     use ink_core::storage;
     use ink_prelude::vec::Vec;
@@ -28,19 +29,52 @@ mod invoice_managing_data {
             self.idata.set(idata)
         }
 
+        /// Tokens the element puts on its outgoing sequence flows, given the marking of the
+        /// instance once the element took its own.
         #[ink(message)]
-        fn execute_script(&mut self, element_index: u128) -> u128 {
+        fn execute_script(
+            &mut self,
+            _instance_id: u128,
+            element_index: u128,
+            _marking: BitSet,
+        ) -> BitSet {
             if element_index == 5 {
                 if *self.accepted {
-                    return 32;
+                    return BitSet::from_bits(32);
                 } else {
-                    return 64;
+                    return BitSet::from_bits(64);
                 }
             } else {
-                return 0;
+                return BitSet::new();
             }
         }
 
+        /// Payload of the error or escalation the event throws, for the selector set with
+        /// `set_event_payload` on throw events. The process throws none.
+        #[ink(message)]
+        fn thrown_payload(&self, _instance_id: u128, _element_index: u128) -> Vec<u8> {
+            Vec::new()
+        }
+
+        /// Keeps the payload the event caught, for the selector set with `set_event_payload` on
+        /// catch events. Unlike the throw events' one, it is called with the payload and returns
+        /// nothing.
+        #[ink(message)]
+        fn write_caught_payload(
+            &mut self,
+            _instance_id: u128,
+            _element_index: u128,
+            _payload: Vec<u8>,
+        ) {
+        }
+
+        /// Whether the standard loop activity runs another iteration once `iterations` of them
+        /// completed since it was activated. The process has no loops.
+        #[ink(message, selector = "0x1B5C0E7A")]
+        fn is_loop_continued(&self, _element_index: u128, _iterations: u128) -> bool {
+            false
+        }
+
         #[ink(message)]
         fn check_in1(&self, element_index: u128) -> Result<(), Errors> {
             if 132 & (1 << element_index) != 0 {
@@ -116,6 +150,16 @@ mod invoice_managing_data {
             self.idata.set_instance_count(element_index, instance_count)
         }
 
+        #[ink(message, selector = "0x4A3D912F")]
+        fn reset_loop_counter(&mut self, element_index: u128) {
+            self.idata.reset_loop_counter(element_index)
+        }
+
+        #[ink(message, selector = "0xC60B58E4")]
+        fn increase_loop_counter(&mut self, element_index: u128) -> u128 {
+            self.idata.increase_loop_counter(element_index)
+        }
+
         #[ink(message)]
         fn get_index_in_parent(&self) -> u128 {
             self.idata.get_index_in_parent()
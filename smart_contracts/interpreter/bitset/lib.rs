@@ -0,0 +1,353 @@
+//! Variable-length bit set shared by the BPMN interpreter pallet and the `iflow`, `idata` and
+//! `bpmn_interpreter` contracts.
+//!
+//! Pre and post conditions as well as the tokens on edges are sets of sequence flow indexes,
+//! while started activities are sets of element indexes. Both used to be encoded as `u128`
+//! bitmasks, limiting a model to 128 sequence flows and 128 elements.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use core::ops::{BitAnd, BitOr, BitOrAssign, Sub, SubAssign};
+use scale::{Compact, Decode, Encode, EncodeLike, Input, Output};
+
+/// Upper bound (exclusive) on the indexes a `BitSet` can hold.
+pub const MAX_BIT_INDEX: u128 = 1 << 16;
+
+const WORD_BITS: u128 = 64;
+
+/// Most words of a `BitSet`, those holding the indexes below `MAX_BIT_INDEX`.
+const MAX_WORDS: u32 = (MAX_BIT_INDEX / WORD_BITS) as u32;
+
+/// A set of sequence flow or element indexes, stored as little-endian 64-bit words.
+///
+/// Encoded as its words up to the last one holding an index, so that equal sets have the same
+/// encoding and its length tells how large the set is.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "type-metadata", derive(type_metadata::Metadata))]
+#[derive(Clone, Default, Eq)]
+pub struct BitSet(Vec<u64>);
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Set containing the bits of a legacy `u128` bitmask.
+    pub fn from_bits(bits: u128) -> Self {
+        let mut set = Self(vec![bits as u64, (bits >> WORD_BITS) as u64]);
+        set.trim();
+        set
+    }
+
+    /// Set containing only `index`.
+    pub fn with_bit(index: u128) -> Self {
+        let mut set = Self::new();
+        set.insert(index);
+        set
+    }
+
+    /// Decodes a marking returned by a data & scripts contract, accepting both the `BitSet`
+    /// encoding and a legacy `u128` bitmask (16 bytes, never the length of an encoded `BitSet`).
+    pub fn decode_marking(input: &[u8]) -> Result<Self, scale::Error> {
+        if input.len() == 16 {
+            u128::decode(&mut &input[..]).map(Self::from_bits)
+        } else {
+            Self::decode(&mut &input[..])
+        }
+    }
+
+    /// Largest index in the set.
+    pub fn max_index(&self) -> Option<u128> {
+        let words = self.words();
+        words.last().map(|word| {
+            (words.len() as u128 - 1) * WORD_BITS + (WORD_BITS - 1 - word.leading_zeros() as u128)
+        })
+    }
+
+    /// Adds `index` to the set. Indexes beyond `MAX_BIT_INDEX` are rejected.
+    pub fn insert(&mut self, index: u128) -> bool {
+        if index >= MAX_BIT_INDEX {
+            return false;
+        }
+        let (word, bit) = Self::position(index);
+        if self.0.len() <= word {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << bit;
+        true
+    }
+
+    pub fn remove(&mut self, index: u128) {
+        if index < MAX_BIT_INDEX {
+            let (word, bit) = Self::position(index);
+            if let Some(word) = self.0.get_mut(word) {
+                *word &= !(1 << bit);
+            }
+            self.trim();
+        }
+    }
+
+    pub fn contains(&self, index: u128) -> bool {
+        if index >= MAX_BIT_INDEX {
+            return false;
+        }
+        let (word, bit) = Self::position(index);
+        self.0
+            .get(word)
+            .map_or(false, |word| word & (1 << bit) != 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    /// Whether both sets share at least one index.
+    pub fn intersects(&self, other: &BitSet) -> bool {
+        self.0.iter().zip(other.0.iter()).any(|(a, b)| a & b != 0)
+    }
+
+    /// Whether every index of `other` is also in the set.
+    pub fn is_superset(&self, other: &BitSet) -> bool {
+        other
+            .0
+            .iter()
+            .enumerate()
+            .all(|(i, b)| self.0.get(i).map_or(0, |a| *a) & b == *b)
+    }
+
+    /// Iterates over the indexes in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u128> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, word)| {
+            (0..WORD_BITS)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| i as u128 * WORD_BITS + bit)
+        })
+    }
+
+    fn position(index: u128) -> (usize, u128) {
+        ((index / WORD_BITS) as usize, index % WORD_BITS)
+    }
+
+    fn words(&self) -> &[u64] {
+        let len = self
+            .0
+            .iter()
+            .rposition(|word| *word != 0)
+            .map_or(0, |i| i + 1);
+        &self.0[..len]
+    }
+
+    fn trim(&mut self) {
+        let len = self.words().len();
+        self.0.truncate(len);
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &BitSet) -> bool {
+        self.words() == other.words()
+    }
+}
+
+impl Encode for BitSet {
+    fn size_hint(&self) -> usize {
+        self.words().size_hint()
+    }
+
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        self.words().encode_to(dest)
+    }
+}
+
+impl EncodeLike for BitSet {}
+
+impl Decode for BitSet {
+    /// Rejects more words than `MAX_BIT_INDEX` needs, even empty ones, and drops the trailing
+    /// empty words.
+    fn decode<I: Input>(input: &mut I) -> Result<Self, scale::Error> {
+        let Compact(len) = Compact::<u32>::decode(input)?;
+        if len > MAX_WORDS {
+            return Err("BitSet holds indexes beyond MAX_BIT_INDEX".into());
+        }
+        let mut words = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            words.push(u64::decode(input)?);
+        }
+        let mut set = Self(words);
+        set.trim();
+        Ok(set)
+    }
+}
+
+impl From<u128> for BitSet {
+    fn from(bits: u128) -> Self {
+        Self::from_bits(bits)
+    }
+}
+
+impl BitOrAssign<&BitSet> for BitSet {
+    fn bitor_assign(&mut self, other: &BitSet) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a |= b;
+        }
+        self.trim();
+    }
+}
+
+impl SubAssign<&BitSet> for BitSet {
+    fn sub_assign(&mut self, other: &BitSet) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a &= !b;
+        }
+        self.trim();
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set |= other;
+        set
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, other: &BitSet) -> BitSet {
+        let mut set = BitSet(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(a, b)| a & b)
+                .collect(),
+        );
+        set.trim();
+        set
+    }
+}
+
+impl Sub for &BitSet {
+    type Output = BitSet;
+
+    fn sub(self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set -= other;
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(indexes: &[u128]) -> BitSet {
+        let mut set = BitSet::new();
+        for index in indexes {
+            set.insert(*index);
+        }
+        set
+    }
+
+    #[test]
+    fn holds_indexes_beyond_128() {
+        let set = set(&[0, 127, 128, 1000]);
+        assert!(set.contains(0));
+        assert!(set.contains(128));
+        assert!(set.contains(1000));
+        assert!(!set.contains(999));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 127, 128, 1000]);
+        assert_eq!(set.max_index(), Some(1000));
+    }
+
+    #[test]
+    fn rejects_indexes_out_of_bounds() {
+        let mut set = BitSet::new();
+        assert!(!set.insert(MAX_BIT_INDEX));
+        assert!(!set.insert(u128::MAX));
+        assert!(!set.contains(u128::MAX));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn converts_from_legacy_bitmask() {
+        let bits = 1 | 1 << 63 | 1 << 64 | 1 << 127;
+        let set = BitSet::from_bits(bits);
+        assert_eq!(set, self::set(&[0, 63, 64, 127]));
+        assert_eq!(BitSet::from_bits(0), BitSet::new());
+        assert_eq!(BitSet::from(4u128), BitSet::with_bit(2));
+    }
+
+    #[test]
+    fn equality_ignores_trailing_empty_words() {
+        assert_eq!(BitSet(vec![5, 0, 0]), BitSet(vec![5]));
+        assert_eq!(BitSet(vec![0]), BitSet::new());
+        assert!(BitSet(vec![0, 0]).is_empty());
+        assert_eq!(BitSet(vec![0, 0]).max_index(), None);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[1, 2, 200]);
+        let b = set(&[2, 3]);
+        assert_eq!(&a | &b, set(&[1, 2, 3, 200]));
+        assert_eq!(&a & &b, set(&[2]));
+        assert_eq!(&a - &b, set(&[1, 200]));
+        assert!(a.intersects(&b));
+        assert!(!a.intersects(&set(&[4, 300])));
+        assert!(a.is_superset(&set(&[1, 200])));
+        assert!(!a.is_superset(&b));
+        assert!(a.is_superset(&BitSet::new()));
+
+        let mut c = a.clone();
+        c -= &set(&[200]);
+        assert_eq!(c, set(&[1, 2]));
+        c |= &set(&[500]);
+        assert_eq!(c, set(&[1, 2, 500]));
+        c.remove(500);
+        c.remove(u128::MAX);
+        assert_eq!(c, set(&[1, 2]));
+    }
+
+    #[test]
+    fn encoding_round_trips() {
+        let a = set(&[0, 64, 1000]);
+        let decoded = BitSet::decode(&mut &a.encode()[..]).unwrap();
+        assert_eq!(a, decoded);
+    }
+
+    #[test]
+    fn encoding_is_canonical() {
+        let untrimmed = BitSet(vec![1 << 3, 0, 0]);
+        assert_eq!(untrimmed.encode(), set(&[3]).encode());
+        let decoded = BitSet::decode(&mut &vec![1u64 << 3, 0, 0].encode()[..]).unwrap();
+        assert_eq!(decoded.0, vec![1 << 3]);
+
+        let words = vec![0u64; MAX_WORDS as usize + 1];
+        assert!(BitSet::decode(&mut &words.encode()[..]).is_err());
+        let mut words = vec![0u64; MAX_WORDS as usize];
+        words[MAX_WORDS as usize - 1] = 1 << 63;
+        let decoded = BitSet::decode(&mut &words.encode()[..]).unwrap();
+        assert_eq!(decoded.max_index(), Some(MAX_BIT_INDEX - 1));
+    }
+
+    #[test]
+    fn decodes_legacy_and_current_markings() {
+        let legacy = (1u128 << 5 | 1 << 100).encode();
+        assert_eq!(BitSet::decode_marking(&legacy).unwrap(), set(&[5, 100]));
+        for indexes in [&[][..], &[3], &[3, 64], &[3, 64, 200]].iter() {
+            let current = set(indexes).encode();
+            assert_ne!(current.len(), 16);
+            assert_eq!(BitSet::decode_marking(&current).unwrap(), set(indexes));
+        }
+        assert!(BitSet::decode_marking(&[1, 2, 3]).is_err());
+    }
+}
@@ -4,6 +4,7 @@ use ink_lang as ink;
 
 #[ink::contract(version = "0.1.0")]
 mod bpmn_interpreter {
+    use bitset::BitSet;
     use ink_core::env::call::*;
     use ink_core::env::EnvError;
     use ink_core::storage::{self, Flush};
@@ -40,12 +41,24 @@ mod bpmn_interpreter {
         static ref SET_MARKING: Selector = Selector::from([0x5A,0x1D,0x86,0x60]);
         static ref GET_CHILD_PROC_INSTANCE: Selector = Selector::from([0x1D,0x2F,0xC3,0x66]);
         static ref SET_INSTANCE_COUNT: Selector = Selector::from([0x9B,0x70,0x40,0x9A]);
+        static ref RESET_LOOP_COUNTER: Selector = Selector::from([0x4A,0x3D,0x91,0x2F]);
+        static ref INCREASE_LOOP_COUNTER: Selector = Selector::from([0xC6,0x0B,0x58,0xE4]);
         // data & scripts
         static ref EXECUTE_SCRIPT: Selector = Selector::from([0xAC,0x52,0xC8,0xD3]);
+        static ref IS_LOOP_CONTINUED: Selector = Selector::from([0x1B,0x5C,0x0E,0x7A]);
     }
 
+    /// Flow element as (index, (preC, postC, type), next elements)
+    type FlowElement = (u128, (BitSet, BitSet, u128), Vec<u128>);
+
+    /// Most iterations of a standard loop activity, whatever its loop condition
+    const MAX_LOOP_ITERATIONS: u128 = 100;
+
     #[ink(storage)]
-    struct BpmnInterpreter {}
+    struct BpmnInterpreter {
+        /// Most elements a call of `execute_elements` can queue
+        max_execution_steps: storage::Value<u32>,
+    }
 
     #[ink(event)]
     struct MessageSent {
@@ -59,6 +72,16 @@ mod bpmn_interpreter {
         parent_case: AccountId,
     }
 
+    /// The execution of the instance stopped before an element whose successors would exceed
+    /// `max_execution_steps`. The elements left keep their tokens, `execute_elements` continues
+    /// them.
+    #[ink(event)]
+    struct ExecutionPaused {
+        #[ink(topic)]
+        parent_case: AccountId,
+        element_index: u128,
+    }
+
     #[cfg_attr(feature = "ink-generate-abi", derive(type_metadata::Metadata))]
     #[derive(scale::Encode, scale::Decode)]
     pub enum Errors {
@@ -74,7 +97,9 @@ mod bpmn_interpreter {
 
     impl BpmnInterpreter {
         #[ink(constructor)]
-        fn new(&mut self) {}
+        fn new(&mut self, max_execution_steps: u32) {
+            self.max_execution_steps.set(max_execution_steps);
+        }
 
         /// Instantiation of Root-Process
         #[ink(message)]
@@ -140,11 +165,11 @@ mod bpmn_interpreter {
             let first_element =
                 CallParams::<EnvTypes, u128>::eval(child_flow, *GET_FIRST_ELEMENT).fire()?;
             let post_condition =
-                CallParams::<EnvTypes, u128>::eval(child_flow, *GET_POST_CONDITION)
+                CallParams::<EnvTypes, BitSet>::eval(child_flow, *GET_POST_CONDITION)
                     .push_arg::<u128>(&first_element)
                     .fire()?;
             CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_MARKING)
-                .push_arg::<u128>(&post_condition)
+                .push_arg::<BitSet>(&post_condition)
                 .fire()?;
             let next: Vec<u128> =
                 CallParams::<EnvTypes, Vec<u128>>::eval(child_flow, *GET_ADY_ELEMENTS)
@@ -164,11 +189,11 @@ mod bpmn_interpreter {
             event_info: u128,
         ) -> Result<(), Errors> {
             // This function only receive THROW EVENTS (throw event verification made in function executeElement)
-            let mut parent_state: [u128; 2] = [0; 2];
-            parent_state[0] =
-                CallParams::<EnvTypes, u128>::eval(parent_case, *GET_MARKING).fire()?;
-            parent_state[1] =
-                CallParams::<EnvTypes, u128>::eval(parent_case, *GET_STARTED_ACTIVITIES).fire()?;
+            let parent_state = [
+                CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_MARKING).fire()?,
+                CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_STARTED_ACTIVITIES)
+                    .fire()?,
+            ];
             match event_info {
                 event_info if event_info & 4096 == 4096 => {
                     // Message (BIT 15), to publish a Message in the Ethereum Event Log
@@ -177,7 +202,7 @@ mod bpmn_interpreter {
                 event_info if event_info & 5632 == 5632 => {
                     // 9- End, 10- Default, 12- Message
                     // If there are not tokens to consume nor started activities in any subprocess
-                    if parent_state[0] | parent_state[1] == 0 {
+                    if parent_state[0].is_empty() && parent_state[1].is_empty() {
                         // Sub-process ended, thus continue execution on parent
                         self.try_catch_event(parent_case, event_code, event_info, true)?;
                     }
@@ -193,7 +218,7 @@ mod bpmn_interpreter {
                         parent_case,
                         event_code,
                         event_info,
-                        parent_state[0] | parent_state[1] == 0,
+                        parent_state[0].is_empty() && parent_state[1].is_empty(),
                     )?;
                 }
             }
@@ -222,14 +247,16 @@ mod bpmn_interpreter {
                 CallParams::<EnvTypes, AccountId>::eval(catch_case, *GET_CHILD_FLOW_INSTANCE)
                     .fire()?;
 
-            let mut parent_state: [u128; 2] = [0; 2];
-            parent_state[0] =
-                CallParams::<EnvTypes, u128>::eval(catch_case, *GET_MARKING).fire()?;
-            parent_state[1] =
-                CallParams::<EnvTypes, u128>::eval(catch_case, *GET_STARTED_ACTIVITIES).fire()?;
+            let parent_state = [
+                CallParams::<EnvTypes, BitSet>::eval(catch_case, *GET_MARKING).fire()?,
+                CallParams::<EnvTypes, BitSet>::eval(catch_case, *GET_STARTED_ACTIVITIES).fire()?,
+            ];
 
             let sub_process_index =
                 CallParams::<EnvTypes, u128>::eval(parent_case, *GET_INDEX_IN_PARENT).fire()?;
+            let sub_process_info = CallParams::<EnvTypes, u128>::eval(child_flow, *GET_TYPE_INFO)
+                .push_arg(&sub_process_index)
+                .fire()?;
             let run_inst_count = if instance_completed {
                 CallParams::<EnvTypes, u128>::eval(catch_case, *DECREASE_INSTANCE_COUNT)
                     .push_arg(&sub_process_index)
@@ -239,38 +266,46 @@ mod bpmn_interpreter {
                     .push_arg(&sub_process_index)
                     .fire()?
             };
+            // Standard Loop (BIT 8) running another iteration once its instance completed
+            let loop_continued = instance_completed
+                && run_inst_count == 0
+                && sub_process_info & 256 == 256
+                && event_info & 7168 != 0
+                && {
+                    let iterations =
+                        CallParams::<EnvTypes, u128>::eval(catch_case, *INCREASE_LOOP_COUNTER)
+                            .push_arg(&sub_process_index)
+                            .fire()?;
+                    self.is_loop_continued(catch_case, sub_process_index, iterations)?
+                };
 
-            if run_inst_count == 0 {
+            if run_inst_count == 0 && !loop_continued {
                 // Update the corresponding sub-process, call activity as completed
                 CallParams::<EnvTypes, ()>::invoke(catch_case, *SET_ACTIVITY_MARKING)
-                    .push_arg(&(parent_state[1] & !(1 << 1 << sub_process_index)))
+                    .push_arg(&(&parent_state[1] - &BitSet::with_bit(sub_process_index)))
                     .fire()?
             }
 
-            let sub_process_info =
-                CallParams::<EnvTypes, u128>::eval(child_flow, *GET_INSTANCE_COUNT)
-                    .push_arg(&sub_process_index)
-                    .fire()?;
-
             if event_info & 7168 != 0 {
                 // If receiving 10- Default, 11- Terminate or 12- Message
-                if run_inst_count == 0 && sub_process_info & 4096 != 4096 {
+                if run_inst_count == 0 && !loop_continued && sub_process_info & 4096 != 4096 {
                     // No Instances of the sub-process propagating the event and The sub-process isn't an event-sub-process (BIT 12)
                     let post_condition =
-                        CallParams::<EnvTypes, u128>::eval(child_flow, *GET_POST_CONDITION)
+                        CallParams::<EnvTypes, BitSet>::eval(child_flow, *GET_POST_CONDITION)
                             .push_arg::<u128>(&sub_process_index)
                             .fire()?;
+                    // The token leaves the completed sub-process on its outgoing sequence flows
                     CallParams::<EnvTypes, ()>::invoke(catch_case, *SET_MARKING)
-                        .push_arg(&(parent_state[0] & !post_condition))
+                        .push_arg(&(&parent_state[0] | &post_condition))
                         .fire()?;
                     let first_ady_element =
                         CallParams::<EnvTypes, Vec<u128>>::eval(child_flow, *GET_ADY_ELEMENTS)
-                            .push_arg::<u128>(&sub_process_info)
+                            .push_arg::<u128>(&sub_process_index)
                             .fire()?[0];
                     self.execute_elements(catch_case, first_ady_element)?;
-                } else if sub_process_info & 128 == 128 {
-                    // Multi-Instance Sequential (BIT 7), with pending instances to be started.
-                    self.create_instance(sub_process_index, parent_case)?;
+                } else if sub_process_info & 128 == 128 || loop_continued {
+                    // Multi-Instance Sequential (BIT 7) or Standard Loop (BIT 8), with pending instances to be started.
+                    self.create_instance(sub_process_index, catch_case)?;
                 }
             } else {
                 // Signal, Error or Escalation
@@ -318,7 +353,9 @@ mod bpmn_interpreter {
                             self.create_instance(attached_to, parent_case)?;
                             // Marking the event-sub-process as started
                             CallParams::<EnvTypes, ()>::invoke(catch_case, *SET_ACTIVITY_MARKING)
-                                .push_arg::<u128>(&(parent_state[1] | (1 << attached_to)))
+                                .push_arg::<BitSet>(
+                                    &(&parent_state[1] | &BitSet::with_bit(attached_to)),
+                                )
                                 .fire()?;
                             return Ok(());
                         } else if catch_event_info & 256 == 256 && attached_to == sub_process_index
@@ -329,10 +366,12 @@ mod bpmn_interpreter {
                                 self.kill_process(parent_case)?;
                             }
                             // The subprocess propagating the event must be interrupted
-                            let post_condition =
-                                CallParams::<EnvTypes, u128>::eval(child_flow, *GET_POST_CONDITION)
-                                    .push_arg::<u128>(&event)
-                                    .fire()?;
+                            let post_condition = CallParams::<EnvTypes, BitSet>::eval(
+                                child_flow,
+                                *GET_POST_CONDITION,
+                            )
+                            .push_arg::<u128>(&event)
+                            .fire()?;
                             let first_ady_element = CallParams::<EnvTypes, Vec<u128>>::eval(
                                 child_flow,
                                 *GET_ADY_ELEMENTS,
@@ -341,7 +380,7 @@ mod bpmn_interpreter {
                             .fire()?[0];
                             // Update the marking with the output of the boundary event
                             CallParams::<EnvTypes, ()>::invoke(catch_case, *SET_MARKING)
-                                .push_arg::<u128>(&(parent_state[0] & !post_condition))
+                                .push_arg::<BitSet>(&(&parent_state[0] | &post_condition))
                                 .fire()?;
                             // Continue the execution of possible internal elements
                             self.execute_elements(catch_case, first_ady_element)?;
@@ -355,14 +394,34 @@ mod bpmn_interpreter {
             Ok(())
         }
 
+        /// Whether the standard loop activity runs another iteration once `iterations` of them
+        /// completed since it was activated: below `MAX_LOOP_ITERATIONS`, and while the loop
+        /// condition of the data & scripts contract holds.
+        fn is_loop_continued(
+            &self,
+            parent_case: AccountId,
+            element_index: u128,
+            iterations: u128,
+        ) -> Result<bool, Errors> {
+            if iterations >= MAX_LOOP_ITERATIONS {
+                return Ok(false);
+            }
+            let condition = CallParams::<EnvTypes, bool>::eval(parent_case, *IS_LOOP_CONTINUED)
+                .push_arg::<u128>(&element_index)
+                .push_arg::<u128>(&iterations)
+                .fire()?;
+            Ok(condition)
+        }
+
         fn kill_process(&self, parent_case: AccountId) -> Result<(), Errors> {
             let started_activities =
-                CallParams::<EnvTypes, u128>::eval(parent_case, *GET_STARTED_ACTIVITIES).fire()?;
+                CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_STARTED_ACTIVITIES)
+                    .fire()?;
             CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_MARKING)
-                .push_arg::<u128>(&0)
+                .push_arg::<BitSet>(&BitSet::new())
                 .fire()?;
             CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_ACTIVITY_MARKING)
-                .push_arg::<u128>(&0)
+                .push_arg::<BitSet>(&BitSet::new())
                 .fire()?;
             let child_flow_instance =
                 CallParams::<EnvTypes, AccountId>::eval(parent_case, *GET_CHILD_FLOW_INSTANCE)
@@ -371,7 +430,7 @@ mod bpmn_interpreter {
                 CallParams::<EnvTypes, Vec<u128>>::eval(child_flow_instance, *GET_SUB_PROC_LIST)
                     .fire()?;
             for child in children {
-                if started_activities & (1 << child) != 0 {
+                if started_activities.contains(child) {
                     let child_proc_inst = CallParams::<EnvTypes, Vec<AccountId>>::eval(
                         parent_case,
                         *GET_CHILD_PROC_INSTANCE,
@@ -397,9 +456,6 @@ mod bpmn_interpreter {
                     .fire()?;
             let events =
                 CallParams::<EnvTypes, Vec<u128>>::eval(child_flow, *GET_EVENT_LIST).fire()?;
-            // let mut parent_state: [u128; 2] = [0; 2];
-            // parent_state[1] =
-            //     CallParams::<EnvTypes, u128>::eval(parent_case, *GET_STARTED_ACTIVITIES).fire()?;
             for event in events {
                 let event_info = CallParams::<EnvTypes, u128>::eval(child_flow, *GET_TYPE_INFO)
                     .push_arg::<u128>(&event)
@@ -425,7 +481,7 @@ mod bpmn_interpreter {
                         }
                         self.create_instance(attached_to, parent_case)?;
                         CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_ACTIVITY_MARKING)
-                            .push_arg::<u128>(&(1 << attached_to))
+                            .push_arg::<BitSet>(&BitSet::with_bit(attached_to))
                             .fire()?;
                     } else if catch_event_info & 256 == 256 {
                         // Boundary (BIT 6) of the subproces propagating the event
@@ -441,14 +497,15 @@ mod bpmn_interpreter {
                             self.kill_processes(child_proc_inst)?;
                         }
                         let marking =
-                            CallParams::<EnvTypes, u128>::eval(parent_case, *GET_MARKING).fire()?;
+                            CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_MARKING)
+                                .fire()?;
                         let post_condition =
-                            CallParams::<EnvTypes, u128>::eval(child_flow, *GET_POST_CONDITION)
+                            CallParams::<EnvTypes, BitSet>::eval(child_flow, *GET_POST_CONDITION)
                                 .push_arg::<u128>(&event)
                                 .fire()?;
                         // Update the marking with the output of the boundary event
                         CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_MARKING)
-                            .push_arg::<u128>(&(marking & !post_condition))
+                            .push_arg::<BitSet>(&(&marking - &post_condition))
                             .fire()?;
                         let first_ady_element =
                             CallParams::<EnvTypes, Vec<u128>>::eval(child_flow, *GET_ADY_ELEMENTS)
@@ -459,17 +516,18 @@ mod bpmn_interpreter {
                     } else if event_info & 160 == 160 {
                         // Start (not Event Subprocess) OR Intermediate Event
                         let marking =
-                            CallParams::<EnvTypes, u128>::eval(parent_case, *GET_MARKING).fire()?;
+                            CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_MARKING)
+                                .fire()?;
                         let post_condition =
-                            CallParams::<EnvTypes, u128>::eval(child_flow, *GET_POST_CONDITION)
+                            CallParams::<EnvTypes, BitSet>::eval(child_flow, *GET_POST_CONDITION)
                                 .push_arg::<u128>(&event)
                                 .fire()?;
                         let pre_condition =
-                            CallParams::<EnvTypes, u128>::eval(child_flow, *GET_PRE_CONDITION)
+                            CallParams::<EnvTypes, BitSet>::eval(child_flow, *GET_PRE_CONDITION)
                                 .push_arg::<u128>(&event)
                                 .fire()?;
                         CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_MARKING)
-                            .push_arg::<u128>(&(marking & !pre_condition | post_condition))
+                            .push_arg::<BitSet>(&(&(&marking - &pre_condition) | &post_condition))
                             .fire()?;
                         let first_ady_element =
                             CallParams::<EnvTypes, Vec<u128>>::eval(child_flow, *GET_ADY_ELEMENTS)
@@ -486,9 +544,10 @@ mod bpmn_interpreter {
                 CallParams::<EnvTypes, Vec<u128>>::eval(child_flow_instance, *GET_SUB_PROC_LIST)
                     .fire()?;
             let started_activities =
-                CallParams::<EnvTypes, u128>::eval(parent_case, *GET_STARTED_ACTIVITIES).fire()?;
+                CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_STARTED_ACTIVITIES)
+                    .fire()?;
             for child in children {
-                if started_activities & (1 << child) != 0 {
+                if started_activities.contains(child) {
                     let child_proc_inst = CallParams::<EnvTypes, Vec<AccountId>>::eval(
                         parent_case,
                         *GET_CHILD_PROC_INSTANCE,
@@ -508,6 +567,103 @@ mod bpmn_interpreter {
             Ok(())
         }
 
+        /// Collects the elements reachable from the start event and the events of the flow,
+        /// as (index, (preC, postC, type), next elements).
+        fn get_flow_elements(&self, child_flow: AccountId) -> Result<Vec<FlowElement>, Errors> {
+            let mut pending =
+                CallParams::<EnvTypes, Vec<u128>>::eval(child_flow, *GET_EVENT_LIST).fire()?;
+            pending
+                .push(CallParams::<EnvTypes, u128>::eval(child_flow, *GET_FIRST_ELEMENT).fire()?);
+            let mut elements: Vec<FlowElement> = Vec::new();
+            while let Some(element_index) = pending.pop() {
+                if elements.iter().any(|(index, _, _)| *index == element_index) {
+                    continue;
+                }
+                let (cond, next) =
+                    CallParams::<EnvTypes, ((BitSet, BitSet, u128), Vec<u128>)>::eval(
+                        child_flow,
+                        *GET_ELEMENT_INFO,
+                    )
+                    .push_arg::<u128>(&element_index)
+                    .fire()?;
+                pending.extend(next.iter().cloned());
+                elements.push((element_index, cond, next));
+            }
+            Ok(elements)
+        }
+
+        /// An OR-Join is enabled once at least one incoming edge holds a token, and no token
+        /// outside the join nor any started activity can still reach an empty incoming edge.
+        fn is_or_join_enabled(
+            &self,
+            elements: &[FlowElement],
+            join_index: u128,
+            marking: &BitSet,
+            started_activities: &BitSet,
+        ) -> bool {
+            let empty = (BitSet::new(), BitSet::new(), 0);
+            let cond_of = |element_index: u128| {
+                elements
+                    .iter()
+                    .find(|(index, _, _)| *index == element_index)
+                    .map_or(&empty, |(_, cond, _)| cond)
+            };
+            let join_pre_condition = &cond_of(join_index).0;
+            if !marking.intersects(join_pre_condition) {
+                return false;
+            }
+            let empty_edges = join_pre_condition - marking;
+            if empty_edges.is_empty() {
+                return true;
+            }
+            let outside_tokens = marking - join_pre_condition;
+            let mut pending: Vec<u128> = elements
+                .iter()
+                .filter(|(index, cond, _)| {
+                    *index != join_index
+                        && (outside_tokens.intersects(&cond.0)
+                            || started_activities.contains(*index))
+                })
+                .map(|(index, _, _)| *index)
+                .collect();
+            let mut visited: Vec<u128> = Vec::new();
+            while let Some(element_index) = pending.pop() {
+                if visited.contains(&element_index) {
+                    continue;
+                }
+                visited.push(element_index);
+                if cond_of(element_index).1.intersects(&empty_edges) {
+                    // A token upstream can still arrive at the join
+                    return false;
+                }
+                if let Some((_, _, next)) = elements
+                    .iter()
+                    .find(|(index, _, _)| *index == element_index)
+                {
+                    pending.extend(next.iter().filter(|next_index| {
+                        **next_index != join_index && !visited.contains(next_index)
+                    }));
+                }
+            }
+            true
+        }
+
+        fn get_enabled_or_joins(
+            &self,
+            elements: &[FlowElement],
+            marking: &BitSet,
+            started_activities: &BitSet,
+        ) -> Vec<u128> {
+            elements
+                .iter()
+                .filter(|(_, cond, _)| cond.2 & 74 == 74 && marking.intersects(&cond.0))
+                .map(|(index, _, _)| *index)
+                .filter(|index| {
+                    self.is_or_join_enabled(elements, *index, marking, started_activities)
+                })
+                .collect()
+        }
+
         #[ink(message)]
         fn execute_elements(
             &self,
@@ -521,41 +677,79 @@ mod bpmn_interpreter {
 
             // 0- tokensOnEdges
             // 1- startedActivities
-            let mut parent_state: [u128; 2] = [0; 2];
-            parent_state[0] =
-                CallParams::<EnvTypes, u128>::eval(parent_case, *GET_MARKING).fire()?;
-            parent_state[1] =
-                CallParams::<EnvTypes, u128>::eval(parent_case, *GET_STARTED_ACTIVITIES).fire()?;
-
-            // Execution queue and pointers to the first & last element (i.e. basic circular queue implementation)
-            let mut queue: [u128; 100] = [0; 100];
+            let mut parent_state = [
+                CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_MARKING).fire()?,
+                CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_STARTED_ACTIVITIES)
+                    .fire()?,
+            ];
+
+            // Execution queue and pointer to the first element, growing up to `max_execution_steps`
+            let max_execution_steps = *self.max_execution_steps as usize;
+            let mut queue: Vec<u128> = Vec::new();
+            queue.push(element_index);
             let mut i: usize = 0;
-            let mut count: usize = 0;
-            queue[count] = element_index;
-            count += 1;
-            while i < count {
+            // Elements of the flow, only fetched when an OR-Join must be evaluated
+            let mut flow_elements: Option<Vec<FlowElement>> = None;
+            loop {
+                if i >= queue.len() {
+                    // Inclusive joins left waiting may have been released by tokens taking another path
+                    if let Some(elements) = &flow_elements {
+                        for join_index in
+                            self.get_enabled_or_joins(elements, &parent_state[0], &parent_state[1])
+                        {
+                            if queue.len() < max_execution_steps {
+                                queue.push(join_index);
+                            }
+                        }
+                    }
+                    if i >= queue.len() {
+                        break;
+                    }
+                }
                 element_index = queue[i];
-                i += 1;
-                let ([pre_condition, post_condition, type_info], next) =
-                    CallParams::<EnvTypes, ([u128; 3], Vec<u128>)>::eval(
+                let ((pre_condition, post_condition, type_info), next) =
+                    CallParams::<EnvTypes, ((BitSet, BitSet, u128), Vec<u128>)>::eval(
                         child_flow,
                         *GET_ELEMENT_INFO,
                     )
                     .push_arg::<u128>(&element_index)
                     .fire()?;
+                if queue.len() + next.len() > max_execution_steps {
+                    // Stopping before the element writes anything, the state so far is stored
+                    // below and the element keeps its tokens
+                    self.env().emit_event(ExecutionPaused {
+                        parent_case,
+                        element_index,
+                    });
+                    break;
+                }
+                i += 1;
 
                 // Verifying Preconditions (i.e. Is the element enabled?)
                 match type_info {
                     type_info if type_info & 42 == 42 => {
                         // else if (AND Join)
-                        if parent_state[0] & pre_condition != pre_condition {
+                        if !parent_state[0].is_superset(&pre_condition) {
                             continue;
                         }
-                        parent_state[0] &= !pre_condition;
+                        parent_state[0] -= &pre_condition;
                     }
                     type_info if type_info & 74 == 74 => {
                         // else if (OR Join)
-                        ///// OR Join Implementation //////
+                        // Enabled once no other token can still reach an empty incoming edge
+                        if flow_elements.is_none() {
+                            flow_elements = Some(self.get_flow_elements(child_flow)?);
+                        }
+                        let elements = flow_elements.as_ref().map_or(&[][..], |e| &e[..]);
+                        if !self.is_or_join_enabled(
+                            elements,
+                            element_index,
+                            &parent_state[0],
+                            &parent_state[1],
+                        ) {
+                            continue;
+                        }
+                        parent_state[0] -= &pre_condition;
                     }
                     type_info
                         if (type_info & 1 == 1
@@ -563,11 +757,11 @@ mod bpmn_interpreter {
                             || type_info & 2 == 2) =>
                     {
                         // If (Activity || Intermediate/End Event || Gateway != AND/OR Join)
-                        if parent_state[0] & pre_condition == 0 {
+                        if !parent_state[0].intersects(&pre_condition) {
                             continue;
                         }
                         // Removing tokens from input arcs
-                        parent_state[0] &= !pre_condition;
+                        parent_state[0] -= &pre_condition;
                     }
                     _ => {
                         continue;
@@ -585,7 +779,7 @@ mod bpmn_interpreter {
                         for _ in 0..child_instances {
                             self.create_instance(element_index, parent_case)?;
                         }
-                        parent_state[1] |= 1 << element_index;
+                        parent_state[1].insert(element_index);
                     }
                     type_info
                         if (type_info & 129 == 129
@@ -596,16 +790,29 @@ mod bpmn_interpreter {
                         // If (0- Activity, 7- Sequential Multi-Instance) ||
                         // Sub-process(0- Activity, 5- Sub-process) or Call-Activity(0- Activity, 4- Call-Activity)
                         // but NOT Event Sub-process(12- Event Subprocess)
-                        let instance = self.create_instance(element_index, parent_case)?;
-                        let instance_count =
-                            CallParams::<EnvTypes, u128>::eval(child_flow, *GET_INSTANCE_COUNT)
+                        if type_info & 256 == 256 {
+                            // Standard Loop (BIT 8) activated, counting its iterations from scratch
+                            CallParams::<EnvTypes, ()>::invoke(parent_case, *RESET_LOOP_COUNTER)
                                 .push_arg::<u128>(&element_index)
                                 .fire()?;
-                        CallParams::<EnvTypes, ()>::invoke(instance, *SET_INSTANCE_COUNT)
-                            .push_arg::<u128>(&element_index)
-                            .push_arg::<u128>(&instance_count)
-                            .fire()?;
-                        parent_state[1] |= 1 << element_index;
+                        }
+                        if type_info & 769 == 769
+                            && !self.is_loop_continued(parent_case, element_index, 0)?
+                        {
+                            // Standard Loop (BIT 8) tested before (BIT 9) the first iteration, whose condition fails
+                            parent_state[0] |= &post_condition;
+                        } else {
+                            let instance = self.create_instance(element_index, parent_case)?;
+                            let instance_count =
+                                CallParams::<EnvTypes, u128>::eval(child_flow, *GET_INSTANCE_COUNT)
+                                    .push_arg::<u128>(&element_index)
+                                    .fire()?;
+                            CallParams::<EnvTypes, ()>::invoke(instance, *SET_INSTANCE_COUNT)
+                                .push_arg::<u128>(&element_index)
+                                .push_arg::<u128>(&instance_count)
+                                .fire()?;
+                            parent_state[1].insert(element_index);
+                        }
                     }
                     type_info
                         if (type_info & 4105 == 4105
@@ -617,10 +824,12 @@ mod bpmn_interpreter {
                         CallParams::<EnvTypes, u128>::eval(parent_case, *EXECUTE_SCRIPT)
                             .push_arg::<u128>(&element_index)
                             .fire()?;
-                        parent_state[0] |=
+                        // Data & scripts contracts still return the produced tokens as a u128 bitmask
+                        parent_state[0] |= &BitSet::from_bits(
                             CallParams::<EnvTypes, u128>::eval(parent_case, *EXECUTE_SCRIPT)
                                 .push_arg::<u128>(&element_index)
-                                .fire()?;
+                                .fire()?,
+                        );
                     }
                     type_info
                         if ((type_info & 9 == 9 && type_info & 27657 != 0)
@@ -629,15 +838,15 @@ mod bpmn_interpreter {
                         // If (User(11), Service(13), Receive(14) or Default(10) Task || Gateways(1) not XOR/OR Split)
                         // The execution of User/Service/Receive is triggered off-chain,
                         // Thus the starting point would be the data contract which executes any script/data-update related to the task.
-                        parent_state[0] |= post_condition;
+                        parent_state[0] |= &post_condition;
                     }
                     type_info if type_info & 12 == 12 => {
                         // If (2- Event, 3- Throw(1))
                         CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_MARKING)
-                            .push_arg::<u128>(&parent_state[0])
+                            .push_arg::<BitSet>(&parent_state[0])
                             .fire()?;
                         CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_ACTIVITY_MARKING)
-                            .push_arg::<u128>(&parent_state[1])
+                            .push_arg::<BitSet>(&parent_state[1])
                             .fire()?;
                         let event_code =
                             CallParams::<EnvTypes, [u8; 32]>::eval(child_flow, *GET_EVENT_CODE)
@@ -645,13 +854,14 @@ mod bpmn_interpreter {
                                 .fire()?;
                         self.throw_event(parent_case, event_code, type_info)?;
                         let marking =
-                            CallParams::<EnvTypes, u128>::eval(parent_case, *GET_MARKING).fire()?;
-                        let started_activities = CallParams::<EnvTypes, u128>::eval(
+                            CallParams::<EnvTypes, BitSet>::eval(parent_case, *GET_MARKING)
+                                .fire()?;
+                        let started_activities = CallParams::<EnvTypes, BitSet>::eval(
                             parent_case,
                             *GET_STARTED_ACTIVITIES,
                         )
                         .fire()?;
-                        if marking | started_activities == 0 {
+                        if marking.is_empty() && started_activities.is_empty() {
                             // By throwing the event, a kill was performed so the current instance was terminated
                             return Ok(());
                         }
@@ -659,7 +869,7 @@ mod bpmn_interpreter {
                         parent_state[1] = started_activities;
                         if type_info & 128 == 128 {
                             // If Intermediate event (BIT 7)
-                            parent_state[0] |= post_condition;
+                            parent_state[0] |= &post_condition;
                         }
                     }
                     _ => (),
@@ -667,18 +877,15 @@ mod bpmn_interpreter {
 
                 // Adding the possible candidates to be executed to the queue.
                 // The enablement of the element is checked at the moment it gets out of the queue.
-                for next_elem in next {
-                    queue[count] = next_elem;
-                    count = (count + 1) % 100;
-                }
+                queue.extend(next);
             }
 
             // Updating the state (storage) after the execution of each internal element.
             CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_MARKING)
-                .push_arg::<u128>(&parent_state[0])
+                .push_arg::<BitSet>(&parent_state[0])
                 .fire()?;
             CallParams::<EnvTypes, ()>::invoke(parent_case, *SET_ACTIVITY_MARKING)
-                .push_arg::<u128>(&parent_state[1])
+                .push_arg::<BitSet>(&parent_state[1])
                 .fire()?;
             Ok(())
         }
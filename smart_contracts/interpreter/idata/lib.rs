@@ -4,6 +4,7 @@ use ink_lang as ink;
 
 #[ink::contract(version = "0.1.0")]
 mod idata {
+    use bitset::BitSet;
     use ink_core::env::call::*;
     use ink_core::env::EnvError;
     #[cfg(not(feature = "ink-as-dependency"))]
@@ -29,33 +30,35 @@ mod idata {
 
     #[ink(storage)]
     struct Idata {
-        tokens_on_edges: storage::Value<u128>,
-        started_activities: storage::Value<u128>,
+        tokens_on_edges: storage::Value<BitSet>,
+        started_activities: storage::Value<BitSet>,
         idata_parent: storage::Value<AccountId>,
         iflow_node: storage::Value<AccountId>,
         index_in_parent: storage::Value<u128>,
         children: storage::HashMap<u128, Vec<AccountId>>,
         instance_count: storage::HashMap<u128, u128>,
+        /// Iterations completed by each standard loop activity since it was last activated
+        loop_counters: storage::HashMap<u128, u128>,
     }
 
     impl Idata {
         /// Initializes the value to the initial value.
         #[ink(constructor)]
         fn new(&mut self) {
-            self.tokens_on_edges.set(0);
-            self.started_activities.set(0);
+            self.tokens_on_edges.set(BitSet::new());
+            self.started_activities.set(BitSet::new());
             self.idata_parent.set(AccountId::default());
             self.iflow_node.set(AccountId::default());
             self.index_in_parent.set(0);
         }
 
         #[ink(message)]
-        fn set_activity_marking(&mut self, n_marking: u128) {
+        fn set_activity_marking(&mut self, n_marking: BitSet) {
             self.started_activities.set(n_marking);
         }
 
         #[ink(message)]
-        fn set_marking(&mut self, n_marking: u128) {
+        fn set_marking(&mut self, n_marking: BitSet) {
             self.tokens_on_edges.set(n_marking);
         }
 
@@ -76,13 +79,13 @@ mod idata {
 
         /// Returns the current state.
         #[ink(message)]
-        fn get_marking(&self) -> u128 {
-            *self.tokens_on_edges
+        fn get_marking(&self) -> BitSet {
+            self.tokens_on_edges.clone()
         }
 
         #[ink(message)]
-        fn get_started_activities(&self) -> u128 {
-            *self.started_activities
+        fn get_started_activities(&self) -> BitSet {
+            self.started_activities.clone()
         }
 
         #[ink(message)]
@@ -102,6 +105,20 @@ mod idata {
             self.instance_count.insert(element_index, instance_count);
         }
 
+        #[ink(message)]
+        fn reset_loop_counter(&mut self, element_index: u128) {
+            self.loop_counters.insert(element_index, 0);
+        }
+
+        /// Counts one more completed iteration of the loop activity, returning the iterations
+        /// completed since it was activated.
+        #[ink(message)]
+        fn increase_loop_counter(&mut self, element_index: u128) -> u128 {
+            let iterations = self.loop_counters.get(&element_index).unwrap_or(&0) + 1;
+            self.loop_counters.insert(element_index, iterations);
+            iterations
+        }
+
         #[ink(message)]
         fn get_index_in_parent(&self) -> u128 {
             *self.index_in_parent
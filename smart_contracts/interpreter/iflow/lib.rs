@@ -5,16 +5,26 @@ use ink_lang as ink;
 
 #[ink::contract(version = "0.1.0")]
 mod iflow {
+    use bitset::{BitSet, MAX_BIT_INDEX};
     use ink_core::storage::{self, Flush};
     use ink_prelude::vec::Vec;
 
+    #[cfg_attr(feature = "ink-generate-abi", derive(type_metadata::Metadata))]
+    #[derive(scale::Encode, scale::Decode)]
+    pub enum Errors {
+        /// The element index or an edge of its conditions is beyond `MAX_BIT_INDEX`.
+        IndexOutOfBounds,
+        /// The element is already registered with another type.
+        ElementTypeMismatch,
+    }
+
     #[ink(storage)]
     struct Iflow {
         start_event: storage::Value<u128>,
         factory: storage::Value<AccountId>,
         interpreter: storage::Value<AccountId>,
-        // elemIndex => [preC, postC, type]
-        cond_table: storage::HashMap<u128, [u128; 3]>,
+        // elemIndex => (preC, postC, type)
+        cond_table: storage::HashMap<u128, (BitSet, BitSet, u128)>,
         // Element Index => List of elements that can be enabled with the completion of the key element
         next_elem: storage::HashMap<u128, Vec<u128>>,
         // List of Indexes of the subprocesses
@@ -42,24 +52,22 @@ mod iflow {
         }
 
         #[ink(message)]
-        fn get_pre_condition(&self, element_index: u128) -> u128 {
+        fn get_pre_condition(&self, element_index: u128) -> BitSet {
             self.cond_table
                 .get(&element_index)
-                .map_or(0, |cond| cond[0])
+                .map_or(BitSet::new(), |cond| cond.0.clone())
         }
 
         #[ink(message)]
-        fn get_post_condition(&self, element_index: u128) -> u128 {
+        fn get_post_condition(&self, element_index: u128) -> BitSet {
             self.cond_table
                 .get(&element_index)
-                .map_or(0, |cond| cond[1])
+                .map_or(BitSet::new(), |cond| cond.1.clone())
         }
 
         #[ink(message)]
         fn get_type_info(&self, element_index: u128) -> u128 {
-            self.cond_table
-                .get(&element_index)
-                .map_or(0, |cond| cond[2])
+            self.cond_table.get(&element_index).map_or(0, |cond| cond.2)
         }
 
         #[ink(message)]
@@ -68,9 +76,12 @@ mod iflow {
         }
 
         #[ink(message)]
-        fn get_element_info(&self, element_index: u128) -> ([u128; 3], Vec<u128>) {
+        fn get_element_info(&self, element_index: u128) -> ((BitSet, BitSet, u128), Vec<u128>) {
             (
-                *self.cond_table.get(&element_index).unwrap_or(&[0; 3]),
+                self.cond_table
+                    .get(&element_index)
+                    .cloned()
+                    .unwrap_or_default(),
                 self.next_elem
                     .get(&element_index)
                     .unwrap_or(&Vec::default())
@@ -143,12 +154,19 @@ mod iflow {
         fn set_element(
             &mut self,
             element_index: u128,
-            pre_condition: u128,
-            post_condition: u128,
+            pre_condition: BitSet,
+            post_condition: BitSet,
             type_info: u128,
             event_code: [u8; 32],
             _next_elem: Vec<u128>,
-        ) {
+        ) -> Result<(), Errors> {
+            // Element indexes are stored in the started activities, edges in the markings
+            if element_index >= MAX_BIT_INDEX
+                || pre_condition.max_index().unwrap_or(0) >= MAX_BIT_INDEX
+                || post_condition.max_index().unwrap_or(0) >= MAX_BIT_INDEX
+            {
+                return Err(Errors::IndexOutOfBounds);
+            }
             let _type_info = self.get_type_info(element_index);
             match _type_info {
                 0 => {
@@ -166,13 +184,14 @@ mod iflow {
                 _ => {
                     //"Should be equal!"
                     if type_info != _type_info {
-                        return;
+                        return Err(Errors::ElementTypeMismatch);
                     }
                 }
             }
             self.cond_table
-                .insert(element_index, [pre_condition, post_condition, type_info]);
+                .insert(element_index, (pre_condition, post_condition, type_info));
             self.next_elem.insert(element_index, _next_elem);
+            Ok(())
         }
 
         #[ink(message)]